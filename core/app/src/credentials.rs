@@ -0,0 +1,187 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use common::error::AppError;
+use sha2::{Digest, Sha256};
+
+use crate::utils::get_env;
+
+/// Master key used to encrypt saved credentials.
+const KEY_VAR: &str = "BASABLE_CREDENTIALS_KEY";
+
+/// Comma-separated list of retired master keys. Credentials encrypted with them can still be
+/// read, and are re-encrypted with the current key at startup.
+const OLD_KEYS_VAR: &str = "BASABLE_CREDENTIALS_OLD_KEYS";
+
+/// Prefix of encrypted values, followed by `:<key id>:<base64 nonce + ciphertext>`.
+const PREFIX: &str = "enc:v1";
+
+const NONCE_SIZE: usize = 12;
+
+/// Size of the salt master keys are derived with, see [`LocalDB::credentials_salt`].
+///
+/// [`LocalDB::credentials_salt`]: crate::state::LocalDB::credentials_salt
+pub(crate) const SALT_SIZE: usize = 16;
+
+struct MasterKey {
+    /// Short fingerprint of the key, stored with each value to know which key encrypted it.
+    id: String,
+    cipher: Aes256Gcm,
+}
+
+impl MasterKey {
+    /// Derives the encryption key from `secret` with Argon2id.
+    fn new(secret: &str, salt: &[u8]) -> Result<Self, AppError> {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(secret.as_bytes(), salt, &mut key)
+            .map_err(|err| AppError::ServerError(format!("Failed to derive credentials key: {err}")))?;
+
+        let id = Sha256::digest(key)[..4]
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+
+        Ok(MasterKey {
+            id,
+            cipher: Aes256Gcm::new(&key.into()),
+        })
+    }
+}
+
+/// Encrypts and decrypts the credentials kept in the [`LocalDB`](crate::state::LocalDB) with
+/// AES-256-GCM. Keys are derived from the master keys set in the environment and the salt
+/// stored in the [`LocalDB`](crate::state::LocalDB).
+pub(crate) struct Credentials {
+    current: MasterKey,
+    old: Vec<MasterKey>,
+}
+
+impl Credentials {
+    pub fn new(key: &str, old_keys: &[&str], salt: &[u8]) -> Result<Self, AppError> {
+        Ok(Credentials {
+            current: MasterKey::new(key, salt)?,
+            old: old_keys
+                .iter()
+                .map(|k| MasterKey::new(k, salt))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Reads the master keys from `BASABLE_CREDENTIALS_KEY` and `BASABLE_CREDENTIALS_OLD_KEYS`.
+    pub fn from_env(salt: &[u8]) -> Result<Self, AppError> {
+        let key = get_env(KEY_VAR)
+            .ok()
+            .filter(|key| !key.is_empty())
+            .ok_or_else(key_not_set)?;
+
+        let old_keys = get_env(OLD_KEYS_VAR).unwrap_or_default();
+        let old_keys: Vec<&str> = old_keys
+            .split(',')
+            .map(|k| k.trim())
+            .filter(|k| !k.is_empty())
+            .collect();
+
+        Credentials::new(&key, &old_keys, salt)
+    }
+
+    pub fn encrypt(&self, plain: &str) -> Result<String, AppError> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .current
+            .cipher
+            .encrypt(&nonce, plain.as_bytes())
+            .map_err(|err| AppError::ServerError(err.to_string()))?;
+
+        let mut payload = nonce.to_vec();
+        payload.extend(ciphertext);
+
+        Ok(format!("{PREFIX}:{}:{}", self.current.id, STANDARD.encode(payload)))
+    }
+
+    /// Decrypts a value written by [`Credentials::encrypt`] with the current or a retired key.
+    pub fn decrypt(&self, stored: &str) -> Result<String, AppError> {
+        let (key_id, payload) = split(stored).ok_or_else(|| {
+            AppError::ServerError(
+                "Saved credentials aren't encrypted. Restart the server to encrypt them.".to_string(),
+            )
+        })?;
+
+        let key = std::iter::once(&self.current)
+            .chain(&self.old)
+            .find(|k| k.id == key_id)
+            .ok_or_else(|| {
+                AppError::ServerError(format!(
+                    "Saved credentials were encrypted with an unknown key ({key_id}). Add it to {OLD_KEYS_VAR}."
+                ))
+            })?;
+
+        let invalid = || AppError::ServerError("Saved credentials are corrupted".to_string());
+        let payload = STANDARD.decode(payload).map_err(|_| invalid())?;
+        if payload.len() < NONCE_SIZE {
+            return Err(invalid());
+        }
+
+        let (nonce, ciphertext) = payload.split_at(NONCE_SIZE);
+        let plain = key
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| invalid())?;
+
+        String::from_utf8(plain).map_err(|_| invalid())
+    }
+
+    /// Whether `stored` was encrypted with the current key.
+    pub fn is_current(&self, stored: &str) -> bool {
+        split(stored).is_some_and(|(key_id, _)| key_id == self.current.id)
+    }
+
+    /// Whether `stored` was saved before credentials were encrypted.
+    pub fn is_plain(stored: &str) -> bool {
+        split(stored).is_none()
+    }
+}
+
+/// Error for when a password is saved or read but `BASABLE_CREDENTIALS_KEY` isn't set.
+pub(crate) fn key_not_set() -> AppError {
+    AppError::ServerError(format!("{KEY_VAR} must be set to save credentials"))
+}
+
+/// Split an encrypted value into its key id and payload.
+fn split(stored: &str) -> Option<(&str, &str)> {
+    stored
+        .strip_prefix(PREFIX)?
+        .strip_prefix(':')?
+        .split_once(':')
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{Credentials, SALT_SIZE};
+
+    const SALT: [u8; SALT_SIZE] = [7; SALT_SIZE];
+
+    #[test]
+    fn test_rotation() {
+        let old = Credentials::new("old-secret", &[], &SALT).unwrap();
+        let stored = old.encrypt("p@ss:word").unwrap();
+        assert!(!stored.contains("p@ss"));
+        assert_eq!(old.decrypt(&stored).unwrap(), "p@ss:word");
+
+        let rotated = Credentials::new("new-secret", &["old-secret"], &SALT).unwrap();
+        assert!(!rotated.is_current(&stored));
+        assert_eq!(rotated.decrypt(&stored).unwrap(), "p@ss:word");
+        assert!(rotated.is_current(&rotated.encrypt("p@ss:word").unwrap()));
+
+        let forgotten = Credentials::new("new-secret", &[], &SALT).unwrap();
+        assert!(forgotten.decrypt(&stored).is_err());
+
+        // The same key with another salt is a different key.
+        let resalted = Credentials::new("old-secret", &[], &[8; SALT_SIZE]).unwrap();
+        assert!(resalted.decrypt(&stored).is_err());
+        assert!(resalted.decrypt("p@ss:word").is_err());
+    }
+}
@@ -1,21 +1,50 @@
-use std::str::FromStr;
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 use axum::http::StatusCode;
-use base::config::{ConfigRaw, DatabaseType, SourceType};
-use base::connector::Connector;
-use base::db::DB;
-use base::mysql::connector::MysqlConnector;
-use base::mysql::db::MySqlDB;
+use base::config::{ConfigRaw, ConnectionProfile, DatabaseType, SourceType};
+use base::registry;
 use base::SharedDB;
 use common::error::AppError;
 use uuid::Uuid;
 
-use crate::user::{create_jwt, JwtSession, User};
+use crate::{
+    state::local_db_path,
+    user::{create_jwt, JwtSession, User},
+    utils::{data_dir, resolve_under},
+};
 
 #[derive(Default)]
 pub(crate) struct Basable {
     pub connections: Vec<SharedDB>,
+    /// Uploaded files, by the id of the connection reading them.
+    pub uploads: HashMap<Uuid, UploadDir>,
+}
+
+/// Directory of an uploaded data file. It's deleted when dropped, which happens once the
+/// connection reading it is closed or replaced.
+pub(crate) struct UploadDir(pub PathBuf);
+
+impl Drop for UploadDir {
+    fn drop(&mut self) {
+        let dir = std::mem::take(&mut self.0);
+
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    if let Err(err) = tokio::fs::remove_dir_all(&dir).await {
+                        tracing::warn!("failed to remove upload {}: {err}", dir.display());
+                    }
+                });
+            }
+            Err(_) => {
+                let _ = std::fs::remove_dir_all(&dir);
+            }
+        }
+    }
 }
 
 impl Basable {
@@ -26,22 +55,7 @@ impl Basable {
         config: &ConfigRaw,
         user_id: String,
     ) -> Result<SharedDB, AppError> {
-        let mut db = match config.get_source()? {
-            SourceType::Database(db) => match db {
-                DatabaseType::Mysql => {
-                    let conn = MysqlConnector::new(config.clone())?;
-                    let db = MySqlDB::new(Arc::new(conn), user_id);
-                    Ok(db)
-                }
-                _ => Err(AppError::not_implemented()),
-            },
-            _ => Err(AppError::not_implemented()),
-        }?;
-
-        let conn = db.connector().clone();
-        db.load_tables(conn)?;
-
-        Ok(Arc::new(db))
+        registry::connect(&check_path(config)?, user_id)
     }
 
     /// Creates a new guest user using the request `SocketAddr`
@@ -55,11 +69,38 @@ impl Basable {
         Ok(session_id)
     }
 
-    /// Add connection.
+    /// Reopens a saved [`ConnectionProfile`]. The connection id is the profile id.
+    pub(crate) fn open_profile(
+        profile: &ConnectionProfile,
+        user_id: String,
+    ) -> Result<SharedDB, AppError> {
+        let id = Uuid::from_str(&profile.id).map_err(|err| AppError::ServerError(err.to_string()))?;
+        registry::connect_with_id(&check_path(&profile.config)?, user_id, id)
+    }
+
+    /// Add connection, replacing any open connection with the same id.
     pub(crate) fn add_connection(&mut self, db: &SharedDB) {
+        self.connections.retain(|c| c.id() != db.id());
+        self.uploads.remove(db.id());
         self.connections.push(db.clone());
     }
 
+    /// Close connection `id` of `user_id`, if it's open.
+    pub(crate) fn remove_connection(&mut self, id: &str, user_id: &str) {
+        let closed: Vec<Uuid> = self
+            .connections
+            .iter()
+            .filter(|c| c.id().to_string() == id && c.user_id() == user_id)
+            .map(|c| *c.id())
+            .collect();
+
+        self.connections
+            .retain(|c| c.id().to_string() != id || c.user_id() != user_id);
+        closed.iter().for_each(|id| {
+            self.uploads.remove(id);
+        });
+    }
+
     pub fn get_connection(&self, id: &str, user_id: &str) -> Result<SharedDB, AppError> {
 
         match Uuid::from_str(id) {
@@ -68,7 +109,7 @@ impl Basable {
                     .connections
                     .iter()
                     .find(|c| *c.id() == id && c.user_id() == user_id)
-                    .map(|c| c.clone());
+                    .cloned();
 
                 match conn {
                     Some(conn) => Ok(conn),
@@ -79,3 +120,31 @@ impl Basable {
         }
     }
 }
+
+/// Sources read from a file can only read files in the [`data_dir`], and never the app's own
+/// [`LocalDB`](crate::state::LocalDB). Returns `config` with the path resolved.
+fn check_path(config: &ConfigRaw) -> Result<ConfigRaw, AppError> {
+    let Some(path) = config.path.as_deref() else {
+        return Ok(config.clone());
+    };
+    let reads_file = matches!(
+        config.get_source()?,
+        SourceType::Database(DatabaseType::Sqlite) | SourceType::File(_)
+    );
+    if !reads_file {
+        return Ok(config.clone());
+    }
+
+    let file = resolve_under(&data_dir(), Path::new(path))?;
+    if Path::new(&local_db_path()).canonicalize().is_ok_and(|local_db| local_db == file) {
+        return Err(AppError::HttpError(
+            StatusCode::FORBIDDEN,
+            format!("`{path}` can't be opened"),
+        ));
+    }
+
+    Ok(ConfigRaw {
+        path: Some(file.to_string_lossy().to_string()),
+        ..config.clone()
+    })
+}
@@ -0,0 +1,317 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+};
+
+use axum::http::StatusCode;
+use base::{graphs::chrono::ChronoAnalysisBasis, row::ColumnValue, SharedDB, SharedTable};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use common::{
+    data::table::{HistoryColumn, TableQueryOpts},
+    error::AppError,
+    query::{
+        filter::{Filter, FilterCombinator, FilterExpression},
+        QueryOrder,
+    },
+};
+use serde::Serialize;
+
+type Row = HashMap<String, ColumnValue>;
+
+/// Rows fetched per query when a table is scanned.
+const SCAN_CHUNK: usize = 1000;
+
+/// Format used to compare native date columns in queries. Fractional seconds are kept, so an
+/// end bound of 23:59:59.999999 still includes the last second of the day, and left out when
+/// zero, so bounds compare like the values of date columns stored as text.
+const QUERY_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+/// Formats tried on string values that don't match the column pattern, and on range bounds.
+const FALLBACK_FORMATS: [&str; 3] = ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%+"];
+
+/// Number of rows created or updated in a period of an activity chart.
+#[derive(Serialize)]
+pub(crate) struct HistoryActivity {
+    pub period: String,
+    pub count: usize,
+}
+
+/// Row history queries on a [`HistoryColumn`] of a table.
+///
+/// Native date columns are filtered and sorted by the database. Columns holding dates as
+/// strings can't be compared there, so the whole table is scanned, whatever the range or
+/// number of rows asked for, and values are parsed with the column `pattern`. Only the rows
+/// returned are kept in memory.
+pub(crate) struct History<'a> {
+    db: &'a SharedDB,
+    table: &'a SharedTable,
+    column: HistoryColumn,
+    native: bool,
+
+    /// Primary key, ordering rows with the same time so chunks and pages don't overlap.
+    key: Option<String>,
+}
+
+impl<'a> History<'a> {
+    pub fn new(
+        db: &'a SharedDB,
+        table: &'a SharedTable,
+        column: HistoryColumn,
+    ) -> Result<Self, AppError> {
+        let columns = table.query_columns()?;
+        let key = columns.iter().find(|c| c.primary).map(|c| c.name.clone());
+        let col_type = columns
+            .into_iter()
+            .find(|c| c.name == column.name())
+            .map(|c| c.col_type.to_lowercase())
+            .ok_or_else(|| {
+                AppError::HttpError(
+                    StatusCode::BAD_REQUEST,
+                    format!(
+                        "Unknown column `{}` in table `{}`",
+                        column.name(),
+                        table.name()
+                    ),
+                )
+            })?;
+
+        Ok(History {
+            db,
+            table,
+            column,
+            native: col_type.contains("date") || col_type.contains("time"),
+            key,
+        })
+    }
+
+    /// Rows whose history column is between `from` and `to`, most recent first.
+    pub fn between(
+        &self,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<Row>, AppError> {
+        if self.native {
+            let opts = self.query_opts(Some((from, to)), None, offset, limit);
+            return self.table.query_data(opts, self.db);
+        }
+
+        let rows = self.scan(offset + limit, |time| from <= time && time <= to)?;
+        Ok(rows.into_iter().skip(offset).take(limit).collect())
+    }
+
+    /// The `count` most recent rows. For string columns, this scans the whole table.
+    pub fn latest(&self, count: usize) -> Result<Vec<Row>, AppError> {
+        if self.native {
+            let mut opts = self.query_opts(None, None, 0, count);
+            opts.filters = Some(vec![Filter {
+                combinator: FilterCombinator::BASE,
+                column: self.column.name().to_string(),
+                expression: FilterExpression::NotNull,
+            }]);
+
+            return self.table.query_data(opts, self.db);
+        }
+
+        self.scan(count, |_| true)
+    }
+
+    /// Number of rows per day, month or year between `from` and `to`.
+    pub fn activity(
+        &self,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+        basis: ChronoAnalysisBasis,
+    ) -> Result<Vec<HistoryActivity>, AppError> {
+        let format = match basis {
+            ChronoAnalysisBasis::Daily => "%Y-%m-%d",
+            ChronoAnalysisBasis::Monthly => "%Y-%m",
+            ChronoAnalysisBasis::Yearly => "%Y",
+        };
+
+        // Only the history column is needed, so even string columns are cheap to scan.
+        let range = self.native.then_some((from, to));
+        let column = vec![self.column.name().to_string()];
+
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        self.for_each_chunk(range, Some(column), |row| {
+            if let Some(time) = self.time(&row) {
+                if from <= time && time <= to {
+                    *counts.entry(time.format(format).to_string()).or_default() += 1;
+                }
+            }
+        })?;
+
+        Ok(counts
+            .into_iter()
+            .map(|(period, count)| HistoryActivity { period, count })
+            .collect())
+    }
+
+    /// The `max` most recent rows whose time is kept by `keep`, most recent first.
+    fn scan(&self, max: usize, keep: impl Fn(NaiveDateTime) -> bool) -> Result<Vec<Row>, AppError> {
+        let mut rows = Vec::new();
+        self.for_each_chunk(None, None, |row| {
+            if let Some(time) = self.time(&row).filter(|time| keep(*time)) {
+                rows.push((time, row));
+            }
+
+            // Trimmed once in a while rather than on every row.
+            if rows.len() > max.saturating_mul(2).max(SCAN_CHUNK) {
+                rows.sort_by_key(|(time, _)| Reverse(*time));
+                rows.truncate(max);
+            }
+        })?;
+
+        rows.sort_by_key(|(time, _)| Reverse(*time));
+        rows.truncate(max);
+        Ok(rows.into_iter().map(|(_, row)| row).collect())
+    }
+
+    fn for_each_chunk(
+        &self,
+        range: Option<(NaiveDateTime, NaiveDateTime)>,
+        columns: Option<Vec<String>>,
+        mut f: impl FnMut(Row),
+    ) -> Result<(), AppError> {
+        let mut offset = 0;
+
+        loop {
+            let opts = self.query_opts(range, columns.clone(), offset, SCAN_CHUNK);
+            let rows = self.table.query_data(opts, self.db)?;
+            let fetched = rows.len();
+
+            rows.into_iter().for_each(&mut f);
+            if fetched < SCAN_CHUNK {
+                return Ok(());
+            }
+
+            offset += SCAN_CHUNK;
+        }
+    }
+
+    fn query_opts(
+        &self,
+        range: Option<(NaiveDateTime, NaiveDateTime)>,
+        columns: Option<Vec<String>>,
+        offset: usize,
+        row_count: usize,
+    ) -> TableQueryOpts {
+        let name = self.column.name().to_string();
+        let filters = range.map(|(from, to)| {
+            vec![Filter {
+                combinator: FilterCombinator::BASE,
+                column: name.clone(),
+                expression: FilterExpression::Btw(
+                    from.format(QUERY_FORMAT).to_string(),
+                    to.format(QUERY_FORMAT).to_string(),
+                ),
+            }]
+        });
+
+        TableQueryOpts {
+            table: self.table.name().to_string(),
+            offset,
+            row_count,
+            filters,
+            columns,
+            order_by: Some(QueryOrder::DESC(name)),
+            then_by: self.key.clone().map(QueryOrder::DESC),
+            search_opts: None,
+        }
+    }
+
+    /// The history column value of `row`, or `None` if it's empty or doesn't parse.
+    fn time(&self, row: &Row) -> Option<NaiveDateTime> {
+        match row.get(self.column.name())? {
+            ColumnValue::Date(year, month, day, hour, min, sec, micro) => {
+                NaiveDate::from_ymd_opt(*year as i32, *month as u32, *day as u32)?
+                    .and_hms_micro_opt(*hour as u32, *min as u32, *sec as u32, *micro)
+            }
+            ColumnValue::Text(text) => parse_time(text, self.column.pattern()),
+            ColumnValue::Int(value) => parse_time(&value.to_string(), self.column.pattern()),
+            ColumnValue::UInt(value) => parse_time(&value.to_string(), self.column.pattern()),
+            _ => None,
+        }
+    }
+}
+
+/// Parse `value` with `pattern`, then with the [`FALLBACK_FORMATS`]. Date-only values are
+/// taken at midnight.
+pub(crate) fn parse_time(value: &str, pattern: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+
+    std::iter::once(pattern)
+        .chain(FALLBACK_FORMATS)
+        .find_map(|format| {
+            NaiveDateTime::parse_from_str(value, format)
+                .ok()
+                .or_else(|| {
+                    NaiveDate::parse_from_str(value, format)
+                        .ok()
+                        .map(|date| date.and_time(NaiveTime::MIN))
+                })
+        })
+}
+
+/// Parse a range bound given in a query param. A date-only `to` covers the whole day.
+pub(crate) fn parse_bound(value: &str, is_end: bool) -> Result<NaiveDateTime, AppError> {
+    if let Ok(date) = NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d") {
+        let time = match is_end {
+            true => NaiveTime::from_hms_micro_opt(23, 59, 59, 999_999).unwrap_or(NaiveTime::MIN),
+            false => NaiveTime::MIN,
+        };
+
+        return Ok(date.and_time(time));
+    }
+
+    parse_time(value, QUERY_FORMAT).ok_or_else(|| {
+        AppError::HttpError(
+            StatusCode::BAD_REQUEST,
+            format!("`{value}` is not a valid date, expected YYYY-MM-DD or YYYY-MM-DD HH:MM:SS"),
+        )
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use chrono::NaiveDate;
+
+    use super::{parse_bound, parse_time, QUERY_FORMAT};
+
+    #[test]
+    fn test_parse_time() {
+        let day = NaiveDate::from_ymd_opt(2024, 3, 9).unwrap();
+
+        assert_eq!(
+            parse_time("09/03/2024", "%d/%m/%Y"),
+            day.and_hms_opt(0, 0, 0)
+        );
+        assert_eq!(
+            parse_time("2024-03-09 10:30:00", "%d/%m/%Y"),
+            day.and_hms_opt(10, 30, 0)
+        );
+        assert_eq!(parse_time("1709980200", "%s"), day.and_hms_opt(10, 30, 0));
+        assert_eq!(parse_time("yesterday", "%d/%m/%Y"), None);
+
+        assert_eq!(
+            parse_bound("2024-03-09", true).unwrap(),
+            day.and_hms_micro_opt(23, 59, 59, 999_999).unwrap()
+        );
+        assert!(parse_bound("09/03/2024", false).is_err());
+
+        let format = |value: &str, is_end| {
+            parse_bound(value, is_end)
+                .unwrap()
+                .format(QUERY_FORMAT)
+                .to_string()
+        };
+        assert_eq!(format("2024-03-09", false), "2024-03-09 00:00:00");
+        assert_eq!(format("2024-03-09", true), "2024-03-09 23:59:59.999999");
+        assert_eq!(
+            format("2024-03-09 10:30:00.25", false),
+            "2024-03-09 10:30:00.250"
+        );
+    }
+}
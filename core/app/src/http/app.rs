@@ -12,12 +12,14 @@ use axum::{
     Router,
 };
 use std::net::SocketAddr;
+use std::sync::Arc;
 
 use tower::ServiceBuilder;
 use tower_http::cors::Any;
 use tower_http::services::{ServeDir, ServeFile};
 use tower_http::{cors::CorsLayer, trace::TraceLayer};
 
+use crate::credentials::Credentials;
 use crate::state::AppState;
 use crate::AppError;
 
@@ -54,9 +56,21 @@ pub fn app() -> Result<BasableHttpService, AppError> {
         ])
         .allow_methods(Any);
 
-    let state = AppState::create()?;
+    let mut state = AppState::create()?;
     state.local_db.setup()?;
 
+    // Keys are derived once: it's deliberately slow.
+    match Credentials::from_env(&state.local_db.credentials_salt()?) {
+        Ok(credentials) => {
+            let rotated = state.local_db.rotate_credentials(&credentials)?;
+            if rotated > 0 {
+                tracing::debug!("re-encrypted {rotated} saved credentials");
+            }
+            state.credentials = Some(Arc::new(credentials));
+        }
+        Err(err) => tracing::warn!("{err}"),
+    }
+
     let routes = core_routes();
     let static_files_service =
         get_service(ServeDir::new("./web").not_found_service(ServeFile::new("web/index.html")));
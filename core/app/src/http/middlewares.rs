@@ -25,7 +25,7 @@ where
         let mut auth_header = parts.headers.get(AUTHORIZATION);
 
         // If Authorization header does not exist, use session-id to retrieve guest user.
-        if let None = auth_header {
+        if auth_header.is_none() {
             auth_header = parts.headers.get("session-id");
         }
 
@@ -65,11 +65,9 @@ where
                 let conn_id = header.to_str().map_err(|err| {
                     AppError::HttpError(StatusCode::UNAUTHORIZED, err.to_string())
                 })?;
-                let bsbl = state.instance.lock().map_err(|err| {
-                    AppError::HttpError(StatusCode::UNAUTHORIZED, err.to_string())
-                })?;
+                let bsbl = state.instance.read().await;
                 let db = bsbl.get_connection(conn_id, &user.id)?;
-                std::mem::drop(bsbl); // release Mutex lock
+                std::mem::drop(bsbl); // release RwLock read guard
 
                 Ok(DbExtractor(db))
             }
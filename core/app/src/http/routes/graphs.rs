@@ -9,7 +9,7 @@ use axum_macros::debug_handler;
 use base::graphs::{category::CategoryGraphOpts, chrono::ChronoAnalysisOpts, geo::GeoGraphOpts, trend::TrendGraphOpts, AnalysisResults, FromQueryParams};
 
 use crate::{
-    http::middlewares::{AuthExtractor, DbExtractor}, state::AppState, AppError
+    http::middlewares::{AuthExtractor, DbExtractor}, state::AppState, utils::blocking, AppError
 };
 
 #[debug_handler]
@@ -20,7 +20,7 @@ pub async fn chrono_graph(
     State(_): State<AppState>,
 ) -> Result<Json<AnalysisResults>, AppError> {
     let opts = ChronoAnalysisOpts::from_query_params(params)?;
-    let results = db.chrono_graph(opts)?;
+    let results = blocking(move || db.chrono_graph(opts)).await?;
 
     Ok(Json(results))
 }
@@ -33,7 +33,7 @@ pub async fn trend_graph(
     State(_): State<AppState>,
 ) -> Result<Json<AnalysisResults>, AppError> {
     let opts = TrendGraphOpts::from_query_params(params)?;
-    let graph = db.trend_graph(opts)?;
+    let graph = blocking(move || db.trend_graph(opts)).await?;
 
     Ok(Json(graph))
 }
@@ -46,7 +46,7 @@ pub async fn category_graph(
     State(_): State<AppState>,
 ) -> Result<Json<AnalysisResults>, AppError> {
     let opts = CategoryGraphOpts::from_query_params(params)?;
-    let graph = db.category_graph(opts)?;
+    let graph = blocking(move || db.category_graph(opts)).await?;
 
     Ok(Json(graph))
 }
@@ -59,7 +59,7 @@ pub async fn geo_graph(
     State(_): State<AppState>,
 ) -> Result<Json<AnalysisResults>, AppError> {
     let opts = GeoGraphOpts::from_query_params(params)?;
-    let graph = db.geo_graph(opts)?;
+    let graph = blocking(move || db.geo_graph(opts)).await?;
 
     Ok(Json(graph))
 }
@@ -1,23 +1,31 @@
+use axum::extract::multipart::MultipartError;
+use axum::extract::{DefaultBodyLimit, Multipart};
+use axum::http::StatusCode;
 use axum::routing::{get, post};
 use axum::Router;
-use base::config::ConfigRaw;
+use base::config::{ConfigRaw, FileType};
+use base::SharedDB;
 use common::DbServerDetails;
 use graphs::graphs_routes;
 
-use crate::foundation::Basable;
+use crate::foundation::{Basable, UploadDir};
 use crate::http::middlewares::AuthExtractor;
 use crate::state::AppState;
+use crate::utils::{blocking, uploads_dir};
 use crate::AppError;
 use axum::{extract::State, Json};
 use axum_macros::debug_handler;
+use tokio::io::AsyncWriteExt;
 
 use self::auth::auth_routes;
+use self::profile::profile_routes;
 use self::table::table_routes;
 
 use super::middlewares::DbExtractor;
 
 pub(super) mod auth;
 pub(super) mod graphs;
+pub(super) mod profile;
 pub(super) mod table;
 
 #[debug_handler]
@@ -26,25 +34,127 @@ async fn connect(
     AuthExtractor(user): AuthExtractor,
     Json(config): Json<ConfigRaw>,
 ) -> Result<Json<String>, AppError> {
-    let mut bsbl = state.instance.lock().unwrap();
-    let storage = state.local_db;
-
     let user_id = user.id.clone();
-    let db = Basable::create_connection(&config, user_id)?;
+    let db = blocking(move || Basable::create_connection(&config, user_id)).await?;
+
+    let conn_id = add_connection(&state, db).await?;
 
+    Ok(Json(conn_id))
+}
+
+/// Adds `db` to the app instance, saves a default config for each of its tables and returns
+/// the connection id.
+async fn add_connection(state: &AppState, db: SharedDB) -> Result<String, AppError> {
+    let mut bsbl = state.instance.write().await;
     bsbl.add_connection(&db);
-    std::mem::drop(bsbl); // release Mutex lock
+    std::mem::drop(bsbl); // release RwLock write guard
 
     let conn_id = db.id().to_string();
-    let tables = db.tables();
-    if !tables.is_empty() {
-        for tbl in tables {
+    let storage = state.local_db.clone();
+    let id = conn_id.clone();
+
+    blocking(move || {
+        for tbl in db.tables() {
             if let Some(config) = tbl.init_config() {
-                storage.create_table_config(&conn_id, config)?;
+                storage.create_table_config(&id, config)?;
             }
         }
+
+        Ok(())
+    })
+    .await?;
+
+    Ok(conn_id)
+}
+
+/// Maximum size of an uploaded data file.
+const MAX_UPLOAD_SIZE: usize = 100 * 1024 * 1024;
+
+/// Connects to a data file sent as `multipart/form-data`. The file is expected
+/// in the `file` field, and its type in the `source` field (defaults to the file extension).
+///
+/// The file is streamed to the [`uploads_dir`], and deleted when the connection is closed.
+#[debug_handler]
+async fn upload_file(
+    State(state): State<AppState>,
+    AuthExtractor(user): AuthExtractor,
+    mut multipart: Multipart,
+) -> Result<Json<String>, AppError> {
+    let bad_request = |msg: String| AppError::HttpError(StatusCode::BAD_REQUEST, msg);
+    let form_error = |err: MultipartError| AppError::HttpError(err.status(), err.body_text());
+    let server_error = |err: std::io::Error| AppError::ServerError(err.to_string());
+
+    let mut source = None;
+    let mut upload = None;
+
+    while let Some(mut field) = multipart.next_field().await.map_err(form_error)? {
+        match field.name() {
+            Some("source") => {
+                let text = field.text().await.map_err(form_error)?;
+                source = Some(text);
+            }
+            Some("file") => {
+                // Keep the file name so it can be used as the table name.
+                let file_name = field.file_name().unwrap_or_default().to_string();
+                let name = std::path::Path::new(&file_name)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .filter(|n| !n.is_empty())
+                    .unwrap_or_else(|| "data".to_string());
+
+                let dir = UploadDir(uploads_dir().join(uuid::Uuid::new_v4().to_string()));
+                tokio::fs::create_dir_all(&dir.0)
+                    .await
+                    .map_err(server_error)?;
+                let path = dir.0.join(name);
+
+                let mut file = tokio::fs::File::create(&path).await.map_err(server_error)?;
+                let mut size = 0;
+                while let Some(chunk) = field.chunk().await.map_err(form_error)? {
+                    size += chunk.len();
+                    if size > MAX_UPLOAD_SIZE {
+                        return Err(AppError::HttpError(
+                            StatusCode::PAYLOAD_TOO_LARGE,
+                            format!(
+                                "Uploads are limited to {} MB",
+                                MAX_UPLOAD_SIZE / 1024 / 1024
+                            ),
+                        ));
+                    }
+                    file.write_all(&chunk).await.map_err(server_error)?;
+                }
+                file.flush().await.map_err(server_error)?;
+
+                upload = Some((file_name, path, dir));
+            }
+            _ => {}
+        }
     }
 
+    let (file_name, path, dir) =
+        upload.ok_or_else(|| bad_request("No file uploaded".to_string()))?;
+
+    let source = source
+        .or_else(|| {
+            std::path::Path::new(&file_name)
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+        })
+        .ok_or_else(|| bad_request("File source type not provided".to_string()))?;
+    FileType::try_from(source.as_str())?;
+
+    let config = ConfigRaw {
+        source_type: "file".to_string(),
+        source,
+        path: Some(path.to_string_lossy().to_string()),
+        ..Default::default()
+    };
+
+    let db = blocking(move || Basable::create_connection(&config, user.id)).await?;
+    let id = *db.id();
+    let conn_id = add_connection(&state, db).await?;
+    state.instance.write().await.uploads.insert(id, dir);
+
     Ok(Json(conn_id))
 }
 
@@ -53,7 +163,7 @@ async fn server_details(
     DbExtractor(db): DbExtractor,
     State(_): State<AppState>,
 ) -> Result<Json<DbServerDetails>, AppError> {
-    let details = db.details()?;
+    let details = blocking(move || db.details()).await?;
 
     Ok(Json(details))
 }
@@ -61,8 +171,13 @@ async fn server_details(
 pub(super) fn core_routes() -> Router<AppState> {
     Router::new()
         .route("/connect", post(connect))
+        .route(
+            "/connect/upload",
+            post(upload_file).layer(DefaultBodyLimit::max(MAX_UPLOAD_SIZE)),
+        )
         .route("/server", get(server_details))
         .nest("/auth", auth_routes())
+        .nest("/profiles", profile_routes())
         .nest("/tables", table_routes())
         .nest("/graphs", graphs_routes())
 }
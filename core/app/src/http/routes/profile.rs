@@ -0,0 +1,180 @@
+use std::path::Component;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, patch, post},
+    Json, Router,
+};
+use axum_macros::debug_handler;
+use base::config::{ConfigRaw, ConnectionProfile};
+
+use crate::{
+    foundation::Basable,
+    http::middlewares::AuthExtractor,
+    state::AppState,
+    utils::{blocking, profile_files_dir, resolve_under, uploads_dir},
+    AppError,
+};
+
+use super::add_connection;
+
+#[debug_handler]
+async fn list_profiles(
+    AuthExtractor(user): AuthExtractor,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ConnectionProfile>>, AppError> {
+    let storage = state.local_db;
+    let profiles = blocking(move || storage.list_profiles(&user.id)).await?;
+
+    Ok(Json(profiles))
+}
+
+#[debug_handler]
+async fn create_profile(
+    AuthExtractor(user): AuthExtractor,
+    State(state): State<AppState>,
+    Json(mut profile): Json<ConnectionProfile>,
+) -> Result<Json<ConnectionProfile>, AppError> {
+    profile.config.get_source()?;
+
+    let (storage, credentials) = (state.local_db, state.credentials);
+    let profile = blocking(move || {
+        keep_upload(&mut profile.config)?;
+        storage
+            .create_profile(&user.id, profile.clone(), credentials.as_deref())
+            .inspect_err(|_| remove_kept_upload(&profile.config))
+    })
+    .await?;
+
+    Ok(Json(profile))
+}
+
+#[debug_handler]
+async fn update_profile(
+    Path(id): Path<String>,
+    AuthExtractor(user): AuthExtractor,
+    State(state): State<AppState>,
+    Json(mut profile): Json<ConnectionProfile>,
+) -> Result<Json<ConnectionProfile>, AppError> {
+    profile.config.get_source()?;
+
+    let (storage, credentials) = (state.local_db, state.credentials);
+    let profile = blocking(move || {
+        let old = storage.find_profile(&id, &user.id)?;
+        keep_upload(&mut profile.config)?;
+        let saved = storage
+            .update_profile(&id, &user.id, profile.clone(), credentials.as_deref())
+            .inspect_err(|_| remove_kept_upload(&profile.config))?;
+
+        if let Some(old) = old.filter(|old| old.config.path != saved.config.path) {
+            remove_kept_upload(&old.config);
+        }
+        Ok(saved)
+    })
+    .await?;
+
+    Ok(Json(profile))
+}
+
+#[debug_handler]
+async fn delete_profile(
+    Path(id): Path<String>,
+    AuthExtractor(user): AuthExtractor,
+    State(state): State<AppState>,
+) -> Result<String, AppError> {
+    let storage = state.local_db;
+    let (profile_id, user_id) = (id.clone(), user.id.clone());
+    blocking(move || {
+        let profile = storage.find_profile(&profile_id, &user_id)?;
+        storage.delete_profile(&profile_id, &user_id)?;
+        if let Some(profile) = profile {
+            remove_kept_upload(&profile.config);
+        }
+        Ok(())
+    })
+    .await?;
+
+    let mut bsbl = state.instance.write().await;
+    bsbl.remove_connection(&id, &user.id);
+
+    Ok("Operation successful".to_string())
+}
+
+/// Opens a connection from a saved profile and returns its id, which is the profile id. An
+/// open connection with the same id is replaced.
+#[debug_handler]
+async fn connect_profile(
+    Path(id): Path<String>,
+    AuthExtractor(user): AuthExtractor,
+    State(state): State<AppState>,
+) -> Result<Json<String>, AppError> {
+    let (storage, credentials) = (state.local_db.clone(), state.credentials.clone());
+    let db = blocking(move || {
+        let profile = storage.get_profile(&id, &user.id, credentials.as_deref())?;
+        Basable::open_profile(&profile, user.id)
+    })
+    .await?;
+
+    let conn_id = add_connection(&state, db).await?;
+
+    Ok(Json(conn_id))
+}
+
+/// Copies the uploaded file `config` reads to the [`profile_files_dir`], and points `config`
+/// to the copy. Uploads are deleted with their connection, and on restart.
+fn keep_upload(config: &mut ConfigRaw) -> Result<(), AppError> {
+    let Some(path) = &config.path else {
+        return Ok(());
+    };
+    let Ok(upload) = std::path::Path::new(path).strip_prefix(uploads_dir()) else {
+        return Ok(());
+    };
+
+    let upload = resolve_under(&uploads_dir(), upload).map_err(|err| match err {
+        AppError::HttpError(StatusCode::NOT_FOUND, _) => AppError::HttpError(
+            StatusCode::NOT_FOUND,
+            "The uploaded file was deleted with its connection, it must be uploaded again"
+                .to_string(),
+        ),
+        err => err,
+    })?;
+    let server_error = |err: std::io::Error| AppError::ServerError(err.to_string());
+
+    let dir = profile_files_dir().join(uuid::Uuid::new_v4().to_string());
+    std::fs::create_dir_all(&dir).map_err(server_error)?;
+    let kept = dir.join(upload.file_name().unwrap_or_default());
+    std::fs::copy(&upload, &kept).map_err(server_error)?;
+
+    config.path = Some(kept.to_string_lossy().to_string());
+    Ok(())
+}
+
+/// Removes the copy [`keep_upload`] made for `config`, if any.
+fn remove_kept_upload(config: &ConfigRaw) {
+    let Some(path) = &config.path else {
+        return;
+    };
+    let Ok(kept) = std::path::Path::new(path).strip_prefix(profile_files_dir()) else {
+        return;
+    };
+
+    // Copies are alone in a directory named after a uuid.
+    let mut components = kept.components();
+    if let (Some(Component::Normal(dir)), Some(Component::Normal(_)), None) =
+        (components.next(), components.next(), components.next())
+    {
+        let dir = profile_files_dir().join(dir);
+        if let Err(err) = std::fs::remove_dir_all(&dir) {
+            tracing::warn!("failed to remove profile file {}: {err}", dir.display());
+        }
+    }
+}
+
+/// Routes for saved connection profiles
+pub(super) fn profile_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_profiles).post(create_profile))
+        .route("/:id", patch(update_profile).delete(delete_profile))
+        .route("/:id/connect", post(connect_profile))
+}
@@ -1,41 +1,80 @@
 use std::collections::HashMap;
 
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    body::Body,
+    extract::{DefaultBodyLimit, Multipart, Path, Query, Request, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
     routing::{delete, get, patch, post},
     Json, Router,
 };
 use axum_macros::debug_handler;
-use base::mysql::ColumnValue;
-use common::data::{
-    columns::ColumnList,
-    table::{
-        TableConfig, TableExportOpts, TableExportResponse, TableQueryOpts, TableSummaries,
-        UpdateTableData,
+use base::{
+    graphs::chrono::ChronoAnalysisBasis,
+    row::ColumnValue,
+    table::WriteOpts,
+    SharedDB, SharedTable,
+};
+use common::{
+    data::{
+        columns::ColumnList,
+        table::{
+            BatchTableData, HistoryColumn, NotifyTrigger, NotifyTriggerTime, SpecialColumn, TableConfig, TableEdit,
+            TableEditBatch, TableExportOpts, TableQueryOpts, TableSummaries,
+            UpdateTableData,
+        },
     },
+    query::filter::{Filter, FilterCombinator, FilterExpression},
 };
+use flate2::{write::GzEncoder, Compression};
+use serde::Serialize;
+use serde_json::json;
+use tokio::io::AsyncWriteExt;
+use tower_http::services::ServeFile;
 use uuid::Uuid;
 
 use crate::{
     http::middlewares::{AuthExtractor, DbExtractor, TableExtractor},
-    state::AppState,
+    state::{AppState, LocalDB},
+    history::{parse_bound, History, HistoryActivity},
+    import::{
+        ImportFormat, ImportOpts, ImportReport, ImportStatus, Importer, TempFile, DEFAULT_BATCH_SIZE,
+    },
+    media::{self, MediaSource},
+    remote::{AllowedUrls, MEDIA_URLS_VAR, WEBHOOK_URLS_VAR},
+    utils::{blocking, media_dir, stream_reader, stream_writer},
+    webhooks::{Notified, Notifier, WebhookDelivery},
     AppError,
 };
 
+/// Number of webhook deliveries returned by [`get_webhook_deliveries`].
+const DELIVERY_LOG_LIMIT: usize = 100;
+
+/// Most rows sent in the payload of the webhooks of an update or delete.
+const MAX_WEBHOOK_ROWS: usize = 10_000;
+
 #[debug_handler]
 pub(crate) async fn save_configuration(
     Path(table_name): Path<String>,
     AuthExtractor(_): AuthExtractor,
     DbExtractor(db): DbExtractor,
-    TableExtractor(_): TableExtractor,
+    TableExtractor(table): TableExtractor,
     State(state): State<AppState>,
     Json(config): Json<TableConfig>,
 ) -> Result<String, AppError> {
     let storage = state.local_db;
     let conn_id = db.id().to_string();
 
-    storage.update_table_config(&table_name, &conn_id, config)?;
+    blocking(move || {
+        table.check_columns(&config.columns())?;
+        let webhook_urls = AllowedUrls::from_env(WEBHOOK_URLS_VAR);
+        for event in config.events.iter().flatten() {
+            webhook_urls.check(event.url())?;
+        }
+
+        storage.update_table_config(&table_name, &conn_id, config)
+    })
+    .await?;
     Ok("Operation successful".to_string())
 }
 
@@ -50,7 +89,7 @@ pub(crate) async fn get_configuration(
     let storage = state.local_db;
     let conn_id = db.id().to_string();
 
-    let config = storage.get_table_config(&table_name, &conn_id)?;
+    let config = blocking(move || storage.get_table_config(&table_name, &conn_id)).await?;
     Ok(Json(config))
 }
 
@@ -62,7 +101,7 @@ pub(crate) async fn get_columns(
     TableExtractor(table): TableExtractor,
     State(_): State<AppState>,
 ) -> Result<Json<ColumnList>, AppError> {
-    let cols = table.query_columns()?;
+    let cols = blocking(move || table.query_columns()).await?;
 
     Ok(Json(cols))
 }
@@ -73,10 +112,15 @@ pub(crate) async fn query_data(
     AuthExtractor(_): AuthExtractor,
     DbExtractor(db): DbExtractor,
     TableExtractor(table): TableExtractor,
-    State(_): State<AppState>,
-    Json(opts): Json<TableQueryOpts>,
+    State(state): State<AppState>,
+    Json(mut opts): Json<TableQueryOpts>,
 ) -> Result<Json<Vec<HashMap<String, ColumnValue>>>, AppError> {
-    let data = table.query_data(opts, &db)?;
+    let storage = state.local_db;
+    let data = blocking(move || {
+        apply_config(&storage, &db, &table, &mut opts)?;
+        table.query_data(opts, &db)
+    })
+    .await?;
     Ok(Json(data))
 }
 
@@ -86,77 +130,349 @@ pub(crate) async fn query_result_count(
     AuthExtractor(_): AuthExtractor,
     DbExtractor(db): DbExtractor,
     TableExtractor(table): TableExtractor,
-    State(_): State<AppState>,
-    Json(filter): Json<TableQueryOpts>,
+    State(state): State<AppState>,
+    Json(mut filter): Json<TableQueryOpts>,
 ) -> Result<Json<usize>, AppError> {
-    let count = table.query_result_count(filter, &db)?;
+    let storage = state.local_db;
+    let count = blocking(move || {
+        apply_config(&storage, &db, &table, &mut filter)?;
+        table.query_result_count(filter, &db)
+    })
+    .await?;
     Ok(Json(count))
 }
 
 #[debug_handler]
 pub(crate) async fn insert_data(
-    Path(_): Path<String>,
+    Path(table_name): Path<String>,
     AuthExtractor(_): AuthExtractor,
-    DbExtractor(_): DbExtractor,
+    DbExtractor(db): DbExtractor,
     TableExtractor(table): TableExtractor,
-    State(_): State<AppState>,
+    State(state): State<AppState>,
     Json(data): Json<HashMap<String, String>>,
-) -> Result<String, AppError> {
-    table.insert_data(data)?;
-    Ok("Operation successful".to_string())
+) -> Result<Response, AppError> {
+    let storage = state.local_db;
+    let notified = blocking(move || {
+        let notifier = Notifier::new(&storage, &db.id().to_string(), &table_name)?;
+        let rows = vec![json!(data)];
+
+        notifier.around(NotifyTrigger::Create, rows, || table.insert_data(data))
+    })
+    .await?;
+    Ok(success_response(notified.warnings))
+}
+
+/// Inserts, or upserts, many rows in a single transaction, returning what happened to each
+/// row and its key. Upserts run both the `Create` and `Update` webhooks.
+#[debug_handler]
+pub(crate) async fn write_batch(
+    Path(table_name): Path<String>,
+    AuthExtractor(_): AuthExtractor,
+    DbExtractor(db): DbExtractor,
+    TableExtractor(table): TableExtractor,
+    State(state): State<AppState>,
+    Json(data): Json<BatchTableData>,
+) -> Result<Response, AppError> {
+    let storage = state.local_db;
+    let notified = blocking(move || {
+        let BatchTableData {
+            rows,
+            upsert,
+            upsert_on,
+        } = data;
+
+        let Some(first) = rows.first() else {
+            return Ok(Notified {
+                value: Vec::new(),
+                warnings: Vec::new(),
+            });
+        };
+        let mut columns: Vec<String> = first.keys().cloned().collect();
+        columns.sort();
+
+        let payload: Vec<serde_json::Value> = rows.iter().map(|row| json!(row)).collect();
+        let values = rows
+            .into_iter()
+            .enumerate()
+            .map(|(index, mut row)| {
+                let values: Option<Vec<ColumnValue>> = columns
+                    .iter()
+                    .map(|col| row.remove(col).map(|v| v.map_or(ColumnValue::NULL, ColumnValue::Text)))
+                    .collect();
+
+                values.filter(|_| row.is_empty()).ok_or_else(|| {
+                    AppError::HttpError(
+                        StatusCode::BAD_REQUEST,
+                        format!("Row {index} doesn't have the same columns as the first row"),
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let key_column = match storage.find_table_config(table.name(), &db.id().to_string())? {
+            Some(TableConfig {
+                pk_column: Some(pk), ..
+            }) => Some(pk),
+            _ => table.query_columns()?.into_iter().find(|c| c.primary).map(|c| c.name),
+        };
+
+        let upsert_on = match (upsert, upsert_on) {
+            (false, _) => None,
+            (true, Some(upsert_on)) => Some(upsert_on),
+            (true, None) => Some(vec![upsert_column(&table, key_column.as_deref(), &columns)?]),
+        };
+
+        let notifier = Notifier::new(&storage, &db.id().to_string(), &table_name)?;
+        let write = || {
+            let opts = WriteOpts {
+                key_column,
+                upsert_on,
+            };
+            table.write_rows(&columns, values, opts)
+        };
+
+        match upsert {
+            true => notifier.around_all(
+                vec![(NotifyTrigger::Create, payload.clone()), (NotifyTrigger::Update, payload)],
+                write,
+            ),
+            false => notifier.around(NotifyTrigger::Create, payload, write),
+        }
+    })
+    .await?;
+    Ok(notified_response("rows", notified))
+}
+
+/// The column matching rows to existing ones in an upsert: `key_column` if it's written,
+/// otherwise the first written unique column.
+fn upsert_column(table: &SharedTable, key_column: Option<&str>, columns: &[String]) -> Result<String, AppError> {
+    if let Some(key) = key_column.filter(|key| columns.iter().any(|c| c == key)) {
+        return Ok(key.to_string());
+    }
+
+    table
+        .query_columns()?
+        .into_iter()
+        .find(|c| (c.unique || c.primary) && columns.contains(&c.name))
+        .map(|c| c.name)
+        .ok_or_else(|| {
+            AppError::HttpError(
+                StatusCode::BAD_REQUEST,
+                "No key or unique column is written to match rows on, upsert_on must be given".to_string(),
+            )
+        })
+}
+
+/// Applies a batch of inserts, updates and deletes in a single transaction. If an edit fails,
+/// they're all rolled back and the response reports what became of each.
+#[debug_handler]
+pub(crate) async fn edit_batch(
+    Path(table_name): Path<String>,
+    AuthExtractor(_): AuthExtractor,
+    DbExtractor(db): DbExtractor,
+    TableExtractor(table): TableExtractor,
+    State(state): State<AppState>,
+    Json(batch): Json<TableEditBatch>,
+) -> Result<Response, AppError> {
+    let storage = state.local_db;
+    let result = blocking(move || {
+        let TableEditBatch {
+            mut unique_key,
+            edits,
+        } = batch;
+
+        let finds_rows = edits.iter().any(|edit| !matches!(edit, TableEdit::Insert { .. }));
+        if unique_key.is_empty() && finds_rows {
+            unique_key = pk_column(&storage, &db, &table)?;
+        }
+
+        let mut changes = vec![
+            (NotifyTrigger::Create, Vec::new()),
+            (NotifyTrigger::Update, Vec::new()),
+            (NotifyTrigger::Delete, Vec::new()),
+        ];
+        for edit in &edits {
+            let (at, row) = match edit {
+                TableEdit::Insert { values } => (0, json!(values)),
+                TableEdit::Update { key, values } => {
+                    let mut row = json!(values);
+                    row[&unique_key] = json!(key);
+                    (1, row)
+                }
+                TableEdit::Delete { key } => (2, json!({ unique_key.as_str(): key })),
+            };
+            changes[at].1.push(row);
+        }
+        changes.retain(|(_, rows)| !rows.is_empty());
+
+        let notifier = Notifier::new(&storage, &db.id().to_string(), &table_name)?;
+        let mut report = Vec::new();
+        let applied = notifier.around_all(changes, || {
+            table.apply_edits(&unique_key, edits).map_err(|err| {
+                report = err.outcomes;
+                err.error
+            })
+        });
+
+        Ok(applied.map_err(|error| (error, report)))
+    })
+    .await?;
+
+    match result {
+        Ok(notified) => Ok(notified_response("edits", notified)),
+        Err((error, outcomes)) => {
+            let (status, error) = match error {
+                AppError::HttpError(status, msg) => (status, msg),
+                err => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            };
+
+            Ok((status, Json(json!({ "error": error, "edits": outcomes }))).into_response())
+        }
+    }
 }
 
 #[debug_handler]
 pub(crate) async fn update_data(
-    Path(_): Path<String>,
+    Path(table_name): Path<String>,
     AuthExtractor(_): AuthExtractor,
-    DbExtractor(_): DbExtractor,
+    DbExtractor(db): DbExtractor,
     TableExtractor(table): TableExtractor,
-    State(_): State<AppState>,
-    Json(options): Json<UpdateTableData>,
-) -> Result<String, AppError> {
-    table.update_data(options)?;
-    Ok("Operation successful".to_string())
+    State(state): State<AppState>,
+    Json(mut options): Json<UpdateTableData>,
+) -> Result<Response, AppError> {
+    let storage = state.local_db;
+    let notified = blocking(move || {
+        if options.unique_key.is_empty() {
+            options.unique_key = pk_column(&storage, &db, &table)?;
+        }
+
+        let notifier = Notifier::new(&storage, &db.id().to_string(), &table_name)?;
+        let (key, before) = (options.unique_key.clone(), options.unique_values.clone());
+        let after = updated_keys(&options);
+        let rows = |time: &NotifyTriggerTime| {
+            let keys = match time {
+                NotifyTriggerTime::Before => &before,
+                NotifyTriggerTime::After => &after,
+            };
+            affected_rows(&db, &table, &key, keys.clone())
+        };
+
+        notifier.around_read(NotifyTrigger::Update, rows, || table.update_data(options))
+    })
+    .await?;
+    Ok(success_response(notified.warnings))
 }
 
 pub(crate) async fn delete_data(
     Query(params): Query<HashMap<String, String>>,
-    Path(_): Path<String>,
+    Path(table_name): Path<String>,
     AuthExtractor(_): AuthExtractor,
-    DbExtractor(_): DbExtractor,
+    DbExtractor(db): DbExtractor,
     TableExtractor(table): TableExtractor,
-    State(_): State<AppState>,
-) -> Result<String, AppError> {
-    match (params.get("column"), params.get("values")) {
-        (Some(column), Some(values)) => {
-            let values: Vec<&str> = values.split(",").collect();
-            table.delete_data(column, values)?;
-            Ok("Operation successful".to_string())
-        }
-        _ => Err(AppError::HttpError(
-            StatusCode::EXPECTATION_FAILED,
-            "query params 'column' and 'values' cannot be empty".to_string(),
-        )),
+    State(state): State<AppState>,
+) -> Result<Response, AppError> {
+    let values = query_param(&params, "values")?.to_string();
+    let column = params.get("column").cloned();
+    let storage = state.local_db;
+
+    let notified = blocking(move || {
+        let column = match column {
+            Some(column) => column,
+            None => pk_column(&storage, &db, &table)?,
+        };
+
+        let values: Vec<&str> = values.split(",").collect();
+        let notifier = Notifier::new(&storage, &db.id().to_string(), &table_name)?;
+        let rows = match notifier.listens_to(&NotifyTrigger::Delete) {
+            true => {
+                let keys = values.iter().map(|v| v.to_string()).collect();
+                affected_rows(&db, &table, &column, keys)?
+            }
+            false => Vec::new(),
+        };
+
+        notifier.around(NotifyTrigger::Delete, rows, || table.delete_data(&column, values))
+    })
+    .await?;
+
+    Ok(success_response(notified.warnings))
+}
+
+/// Responds with "Operation successful", or with a JSON `message` and the `warnings` of the
+/// failed `After` webhooks if there are any.
+fn success_response(warnings: Vec<String>) -> Response {
+    let message = "Operation successful";
+    match warnings.is_empty() {
+        true => message.into_response(),
+        false => Json(json!({ "message": message, "warnings": warnings })).into_response(),
+    }
+}
+
+/// Responds with the operation's value, or with it in `field` next to the `warnings` of the
+/// failed `After` webhooks if there are any.
+fn notified_response<T: Serialize>(field: &str, notified: Notified<T>) -> Response {
+    match notified.warnings.is_empty() {
+        true => Json(notified.value).into_response(),
+        false => Json(json!({ field: notified.value, "warnings": notified.warnings })).into_response(),
     }
 }
 
+/// Streams the export as a download, read from the database while it's being sent.
 pub(crate) async fn export(
-    Path(_): Path<String>,
+    Path(table_name): Path<String>,
     AuthExtractor(_): AuthExtractor,
     DbExtractor(db): DbExtractor,
     TableExtractor(table): TableExtractor,
-    State(_): State<AppState>,
-    Json(opts): Json<TableExportOpts>,
-) -> Result<Json<TableExportResponse>, AppError> {
+    State(state): State<AppState>,
+    Json(mut opts): Json<TableExportOpts>,
+) -> Result<Response, AppError> {
     let format = opts.format.clone();
-    let data = table.export(opts, &db)?;
-    let resp = TableExportResponse {
-        data,
-        mimetype: format.as_mimetype(),
-        filename: format!("{}.{}", Uuid::new_v4(), format.as_extension()),
+    let gzip = opts.gzip;
+    let storage = state.local_db;
+    let body = stream_writer(move |out| {
+        apply_config(&storage, &db, &table, &mut opts.query_opts)?;
+
+        if !gzip {
+            return table.export(opts, &db, out);
+        }
+
+        let mut encoder = GzEncoder::new(out, Compression::default());
+        table.export(opts, &db, &mut encoder)?;
+        encoder
+            .finish()
+            .map_err(|err| AppError::ServerError(err.to_string()))?;
+
+        Ok(())
+    })
+    .await?;
+
+    let mut filename = format!("{}.{}", export_filename(&table_name), format.as_extension());
+    let content_type = if gzip {
+        filename.push_str(".gz");
+        "application/gzip".to_string()
+    } else {
+        format.as_mimetype()
     };
 
-    Ok(Json(resp))
+    Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{filename}\""),
+        )
+        .body(body)
+        .map_err(|err| AppError::ServerError(err.to_string()))
+}
+
+/// `table_name` with anything that can't safely go in a Content-Disposition filename replaced.
+fn export_filename(table_name: &str) -> String {
+    table_name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+            _ => '_',
+        })
+        .collect()
 }
 
 #[debug_handler]
@@ -165,7 +481,7 @@ pub(crate) async fn load_tables(
     DbExtractor(db): DbExtractor,
     State(_): State<AppState>,
 ) -> Result<Json<TableSummaries>, AppError> {
-    let tables = db.build_table_list()?;
+    let tables = blocking(move || db.build_table_list()).await?;
 
     Ok(Json(tables))
 }
@@ -178,7 +494,7 @@ pub(crate) async fn clear_table(
     TableExtractor(table): TableExtractor,
     State(_): State<AppState>,
 ) -> Result<String, AppError> {
-    table.clear()?;
+    blocking(move || table.clear()).await?;
     Ok("operation successful".to_string())
 }
 
@@ -189,10 +505,512 @@ pub(crate) async fn drop_table(
     DbExtractor(db): DbExtractor,
     State(_): State<AppState>,
 ) -> Result<String, AppError> {
-    db.drop_table(&table_name)?;
+    blocking(move || db.drop_table(&table_name)).await?;
     Ok("operation successful".to_string())
 }
 
+/// Apply the saved config of `table`, if any, to a data query. See [`TableConfig::apply`].
+fn apply_config(
+    storage: &LocalDB,
+    db: &SharedDB,
+    table: &SharedTable,
+    opts: &mut TableQueryOpts,
+) -> Result<(), AppError> {
+    if let Some(config) = storage.find_table_config(table.name(), &db.id().to_string())? {
+        config.apply(opts, || {
+            let columns = table.query_columns()?;
+            Ok(columns.into_iter().map(|c| c.name).collect())
+        })?;
+    }
+
+    Ok(())
+}
+
+/// The rows changed by an update, as sent to webhooks: the updated columns of each row along
+/// with its unique key.
+/// The unique key of every updated row once the update is done, which changes if the
+/// update sets the key column.
+fn updated_keys(options: &UpdateTableData) -> Vec<String> {
+    let sets_key = options.columns.contains(&options.unique_key);
+    options
+        .unique_values
+        .iter()
+        .zip(&options.input)
+        .map(|(unique_value, input)| match input.get(&options.unique_key) {
+            Some(value) if sets_key => value.clone(),
+            _ => unique_value.clone(),
+        })
+        .collect()
+}
+
+/// The rows whose `column` is one of `values`, sent as the payload of webhooks. They're
+/// serialized the way [`query_data`] returns them.
+fn affected_rows(
+    db: &SharedDB,
+    table: &SharedTable,
+    column: &str,
+    values: Vec<String>,
+) -> Result<Vec<serde_json::Value>, AppError> {
+    if values.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let opts = TableQueryOpts {
+        table: table.name().to_string(),
+        offset: 0,
+        row_count: MAX_WEBHOOK_ROWS,
+        filters: Some(vec![Filter {
+            combinator: FilterCombinator::BASE,
+            column: column.to_string(),
+            expression: FilterExpression::Includes(values),
+        }]),
+        columns: None,
+        order_by: None,
+        then_by: None,
+        search_opts: None,
+    };
+
+    let rows = table.query_data(opts, db)?;
+    Ok(rows.iter().map(|row| json!(row)).collect())
+}
+
+fn query_param<'a>(params: &'a HashMap<String, String>, name: &str) -> Result<&'a str, AppError> {
+    params.get(name).map(|value| value.as_str()).ok_or_else(|| {
+        AppError::HttpError(
+            StatusCode::EXPECTATION_FAILED,
+            format!("query param '{name}' cannot be empty"),
+        )
+    })
+}
+
+fn number_param(params: &HashMap<String, String>, name: &str, default: usize) -> Result<usize, AppError> {
+    match params.get(name) {
+        Some(value) => value.parse().map_err(|_| {
+            AppError::HttpError(
+                StatusCode::BAD_REQUEST,
+                format!("query param '{name}' must be a number"),
+            )
+        }),
+        None => Ok(default),
+    }
+}
+
+/// The `created_column` (default) or `updated_column` of `config`, picked by `name`.
+fn history_column(config: &TableConfig, name: Option<&String>) -> Result<HistoryColumn, AppError> {
+    let (column, field) = match name.map(|name| name.as_str()) {
+        None | Some("created") => (&config.created_column, "created_column"),
+        Some("updated") => (&config.updated_column, "updated_column"),
+        Some(name) => {
+            return Err(AppError::HttpError(
+                StatusCode::BAD_REQUEST,
+                format!("`{name}` is not a history column, expected `created` or `updated`"),
+            ))
+        }
+    };
+
+    column.clone().ok_or_else(|| {
+        AppError::HttpError(
+            StatusCode::EXPECTATION_FAILED,
+            format!("Table `{}` has no {field}", config.name),
+        )
+    })
+}
+
+fn special_column(
+    storage: &LocalDB,
+    db: &SharedDB,
+    table: &SharedTable,
+    column: &str,
+) -> Result<SpecialColumn, AppError> {
+    storage
+        .find_table_config(table.name(), &db.id().to_string())?
+        .and_then(|config| config.special_columns)
+        .unwrap_or_default()
+        .into_iter()
+        .find(|special| special.name() == column)
+        .ok_or_else(|| {
+            AppError::HttpError(
+                StatusCode::BAD_REQUEST,
+                format!("`{column}` is not a special column of table `{}`", table.name()),
+            )
+        })
+}
+
+/// The value of `column` in the row whose `key_column` is `key`.
+fn media_value(
+    db: &SharedDB,
+    table: &SharedTable,
+    key_column: String,
+    key: String,
+    column: String,
+) -> Result<String, AppError> {
+    let opts = TableQueryOpts {
+        table: table.name().to_string(),
+        offset: 0,
+        row_count: 1,
+        filters: Some(vec![Filter {
+            combinator: FilterCombinator::BASE,
+            column: key_column,
+            expression: FilterExpression::Eq(key),
+        }]),
+        columns: Some(vec![column.clone()]),
+        order_by: None,
+        then_by: None,
+        search_opts: None,
+    };
+
+    let rows = table.query_data(opts, db)?;
+    match rows.first().and_then(|row| row.get(&column)) {
+        Some(ColumnValue::Text(value)) => Ok(value.clone()),
+        Some(ColumnValue::Int(value)) => Ok(value.to_string()),
+        Some(ColumnValue::UInt(value)) => Ok(value.to_string()),
+        _ => Err(media::media_not_found()),
+    }
+}
+
+/// The `pk_column` saved for `table`, used when a request doesn't name the key column.
+fn pk_column(storage: &LocalDB, db: &SharedDB, table: &SharedTable) -> Result<String, AppError> {
+    storage
+        .find_table_config(table.name(), &db.id().to_string())?
+        .and_then(|config| config.pk_column)
+        .ok_or_else(|| {
+            AppError::HttpError(
+                StatusCode::EXPECTATION_FAILED,
+                "A key column must be given since the table has no pk_column".to_string(),
+            )
+        })
+}
+
+/// Recent webhook deliveries of a table, newest first.
+#[debug_handler]
+pub(crate) async fn get_webhook_deliveries(
+    Path(table_name): Path<String>,
+    AuthExtractor(_): AuthExtractor,
+    DbExtractor(db): DbExtractor,
+    TableExtractor(_): TableExtractor,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<WebhookDelivery>>, AppError> {
+    let storage = state.local_db;
+    let conn_id = db.id().to_string();
+
+    let deliveries =
+        blocking(move || storage.list_deliveries(&conn_id, &table_name, DELIVERY_LOG_LIMIT)).await?;
+    Ok(Json(deliveries))
+}
+
+/// Default number of rows returned by history queries.
+const DEFAULT_HISTORY_LIMIT: usize = 20;
+
+/// Default edge of image thumbnails, in pixels.
+const DEFAULT_THUMBNAIL_SIZE: u32 = 128;
+
+/// Response headers of a media server passed on to the client.
+const MEDIA_HEADERS: [header::HeaderName; 6] = [
+    header::CONTENT_TYPE,
+    header::CONTENT_LENGTH,
+    header::CONTENT_RANGE,
+    header::ACCEPT_RANGES,
+    header::ETAG,
+    header::LAST_MODIFIED,
+];
+
+/// Serves the media a special column of a row points to. The row is found by its `pk_column`
+/// value, given as the `key` query param. Files and URLs are streamed with range support,
+/// unless `thumbnail` is set for images.
+#[debug_handler]
+pub(crate) async fn get_media(
+    Query(params): Query<HashMap<String, String>>,
+    Path(_): Path<String>,
+    AuthExtractor(_): AuthExtractor,
+    DbExtractor(db): DbExtractor,
+    TableExtractor(table): TableExtractor,
+    State(state): State<AppState>,
+    request: Request,
+) -> Result<Response, AppError> {
+    let column = query_param(&params, "column")?.to_string();
+    let key = query_param(&params, "key")?.to_string();
+    let thumbnail = match params.get("thumbnail") {
+        Some(size) if size.is_empty() => Some(DEFAULT_THUMBNAIL_SIZE),
+        Some(size) => Some(size.parse().map_err(|_| {
+            AppError::HttpError(StatusCode::BAD_REQUEST, "Invalid thumbnail size".to_string())
+        })?),
+        None => None,
+    };
+
+    let storage = state.local_db;
+    let source = blocking(move || {
+        let special = special_column(&storage, &db, &table, &column)?;
+        if let Some(size) = thumbnail {
+            media::check_thumbnail(&special, size)?;
+        }
+
+        let key_column = pk_column(&storage, &db, &table)?;
+        let value = media_value(&db, &table, key_column, key, column)?;
+        MediaSource::resolve(
+            &special,
+            &value,
+            &media_dir(),
+            &AllowedUrls::from_env(MEDIA_URLS_VAR),
+        )
+    })
+    .await?;
+
+    if let Some(size) = thumbnail {
+        let png = blocking(move || source.thumbnail(size)).await?;
+        return Ok(([(header::CONTENT_TYPE, "image/png")], png).into_response());
+    }
+
+    match source {
+        MediaSource::File(path) => {
+            let response = ServeFile::new(path)
+                .try_call(request)
+                .await
+                .map_err(|err| AppError::ServerError(err.to_string()))?;
+
+            Ok(response.map(Body::new))
+        }
+        MediaSource::Url(url) => {
+            let range = request
+                .headers()
+                .get(header::RANGE)
+                .and_then(|range| range.to_str().ok())
+                .map(|range| range.to_string());
+            let upstream = blocking(move || media::fetch(&url, range.as_deref())).await?;
+
+            let mut response = Response::builder().status(upstream.status());
+            for name in MEDIA_HEADERS {
+                if let Some(value) = upstream.header(name.as_str()) {
+                    response = response.header(name, value);
+                }
+            }
+
+            response
+                .body(stream_reader(upstream.into_reader()))
+                .map_err(|err| AppError::ServerError(err.to_string()))
+        }
+    }
+}
+
+/// Rows created (or updated, with `column=updated`) between the `from` and `to` query params,
+/// most recent first.
+#[debug_handler]
+pub(crate) async fn query_history(
+    Query(params): Query<HashMap<String, String>>,
+    Path(_): Path<String>,
+    AuthExtractor(_): AuthExtractor,
+    DbExtractor(db): DbExtractor,
+    TableExtractor(table): TableExtractor,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<HashMap<String, ColumnValue>>>, AppError> {
+    let from = parse_bound(query_param(&params, "from")?, false)?;
+    let to = parse_bound(query_param(&params, "to")?, true)?;
+    let offset = number_param(&params, "offset", 0)?;
+    let limit = number_param(&params, "limit", DEFAULT_HISTORY_LIMIT)?;
+
+    let storage = state.local_db;
+    let rows = blocking(move || {
+        let config = storage.get_table_config(table.name(), &db.id().to_string())?;
+        let column = history_column(&config, params.get("column"))?;
+
+        let mut rows = History::new(&db, &table, column)?.between(from, to, offset, limit)?;
+        rows.iter_mut().for_each(|row| config.strip_excluded(row));
+        Ok(rows)
+    })
+    .await?;
+    Ok(Json(rows))
+}
+
+/// The last changed rows: by `updated_column`, or `created_column` if the table has none.
+#[debug_handler]
+pub(crate) async fn latest_history(
+    Query(params): Query<HashMap<String, String>>,
+    Path(_): Path<String>,
+    AuthExtractor(_): AuthExtractor,
+    DbExtractor(db): DbExtractor,
+    TableExtractor(table): TableExtractor,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<HashMap<String, ColumnValue>>>, AppError> {
+    let count = number_param(&params, "count", DEFAULT_HISTORY_LIMIT)?;
+
+    let storage = state.local_db;
+    let rows = blocking(move || {
+        let config = storage.get_table_config(table.name(), &db.id().to_string())?;
+        let column = config
+            .updated_column
+            .clone()
+            .or_else(|| config.created_column.clone())
+            .ok_or_else(|| {
+                AppError::HttpError(
+                    StatusCode::EXPECTATION_FAILED,
+                    format!("Table `{}` has no created_column or updated_column", table.name()),
+                )
+            })?;
+
+        let mut rows = History::new(&db, &table, column)?.latest(count)?;
+        rows.iter_mut().for_each(|row| config.strip_excluded(row));
+        Ok(rows)
+    })
+    .await?;
+    Ok(Json(rows))
+}
+
+/// Number of rows created (or updated, with `column=updated`) per period between the `from`
+/// and `to` query params. `basis` is `Date` (default), `Month` or `Year`.
+#[debug_handler]
+pub(crate) async fn history_activity(
+    Query(params): Query<HashMap<String, String>>,
+    Path(_): Path<String>,
+    AuthExtractor(_): AuthExtractor,
+    DbExtractor(db): DbExtractor,
+    TableExtractor(table): TableExtractor,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<HistoryActivity>>, AppError> {
+    let from = parse_bound(query_param(&params, "from")?, false)?;
+    let to = parse_bound(query_param(&params, "to")?, true)?;
+    let basis = match params.get("basis") {
+        Some(basis) => ChronoAnalysisBasis::try_from(basis.to_owned())
+            .map_err(|err| AppError::HttpError(StatusCode::EXPECTATION_FAILED, err))?,
+        None => ChronoAnalysisBasis::Daily,
+    };
+
+    let storage = state.local_db;
+    let activity = blocking(move || {
+        let config = storage.get_table_config(table.name(), &db.id().to_string())?;
+        let column = history_column(&config, params.get("column"))?;
+
+        History::new(&db, &table, column)?.activity(from, to, basis)
+    })
+    .await?;
+    Ok(Json(activity))
+}
+
+/// Largest file accepted by [`import_data`].
+const MAX_IMPORT_SIZE: usize = 1024 * 1024 * 1024;
+
+/// Imports a file sent as `multipart/form-data` into a table. The file is expected in the
+/// `file` field, and its format in the `format` field (defaults to the file extension).
+///
+/// Dry runs only validate the file and return the report. Otherwise the import runs in the
+/// background, and its id is returned to follow it with [`import_progress`].
+#[debug_handler]
+pub(crate) async fn import_data(
+    Path(_): Path<String>,
+    AuthExtractor(user): AuthExtractor,
+    DbExtractor(db): DbExtractor,
+    TableExtractor(table): TableExtractor,
+    State(state): State<AppState>,
+    mut multipart: Multipart,
+) -> Result<Response, AppError> {
+    let bad_request = |msg: String| AppError::HttpError(StatusCode::BAD_REQUEST, msg);
+    let server_error = |err: std::io::Error| AppError::ServerError(err.to_string());
+
+    let mut format = None;
+    let mut mapping = None;
+    let mut dry_run = false;
+    let mut skip_invalid = false;
+    let mut batch_size = DEFAULT_BATCH_SIZE;
+    let mut sheet = None;
+    let mut upload = None;
+
+    while let Some(mut field) = multipart
+        .next_field()
+        .await
+        .map_err(|err| bad_request(err.to_string()))?
+    {
+        let name = field.name().unwrap_or_default().to_string();
+
+        if name == "file" {
+            let file_name = field.file_name().unwrap_or_default().to_string();
+            let path = std::env::temp_dir().join(format!("basable-import-{}", Uuid::new_v4()));
+            let temp = TempFile(path);
+
+            // Written as it's received: imports can be larger than we'd keep in memory.
+            let mut file = tokio::fs::File::create(&temp.0).await.map_err(server_error)?;
+            while let Some(chunk) = field.chunk().await.map_err(|err| bad_request(err.to_string()))? {
+                file.write_all(&chunk).await.map_err(server_error)?;
+            }
+            file.flush().await.map_err(server_error)?;
+
+            upload = Some((temp, file_name));
+            continue;
+        }
+
+        let text = field.text().await.map_err(|err| bad_request(err.to_string()))?;
+        match name.as_str() {
+            "format" => format = Some(text),
+            "mapping" => {
+                let map = serde_json::from_str(&text)
+                    .map_err(|err| bad_request(format!("Invalid column mapping: {err}")))?;
+                mapping = Some(map);
+            }
+            "dry_run" => dry_run = matches!(text.as_str(), "true" | "1"),
+            "skip_invalid" => skip_invalid = matches!(text.as_str(), "true" | "1"),
+            "batch_size" => {
+                batch_size = text
+                    .parse()
+                    .map_err(|_| bad_request("batch_size must be a number".to_string()))?;
+            }
+            "sheet" => sheet = Some(text),
+            _ => {}
+        }
+    }
+
+    let (upload, file_name) = upload.ok_or_else(|| bad_request("No file uploaded".to_string()))?;
+    let format = format
+        .or_else(|| {
+            std::path::Path::new(&file_name)
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_string())
+        })
+        .ok_or_else(|| bad_request("File format not provided".to_string()))?;
+
+    let opts = ImportOpts {
+        format: ImportFormat::try_from(format.as_str())?,
+        mapping,
+        batch_size,
+        skip_invalid,
+        sheet,
+    };
+    let table_name = table.name().to_string();
+    let importer = blocking(move || Importer::new(table, opts)).await?;
+
+    if dry_run {
+        let report = blocking(move || {
+            let mut report = ImportReport::default();
+            importer.validate(&upload.0, &mut report)?;
+            report.status = ImportStatus::Done;
+            Ok(report)
+        })
+        .await?;
+
+        return Ok(Json(report).into_response());
+    }
+
+    let jobs = state.imports;
+    let id = jobs.start(&user.id);
+    let mut report = jobs.get(&id, &user.id)?;
+
+    // The upload is removed once the import is over.
+    let (storage, conn_id) = (state.local_db, db.id().to_string());
+    tokio::task::spawn_blocking(move || {
+        let notifier = Notifier::new(&storage, &conn_id, &table_name);
+        importer.run(&upload.0, &mut report, &jobs, notifier)
+    });
+
+    Ok((StatusCode::ACCEPTED, Json(json!({ "id": id }))).into_response())
+}
+
+/// Progress of an import started by [`import_data`].
+#[debug_handler]
+pub(crate) async fn import_progress(
+    Path(id): Path<String>,
+    AuthExtractor(user): AuthExtractor,
+    State(state): State<AppState>,
+) -> Result<Json<ImportReport>, AppError> {
+    let report = state.imports.get(&id, &user.id)?;
+    Ok(Json(report))
+}
+
 /// Define routes for managing database table
 pub(super) fn table_routes() -> Router<AppState> {
     Router::new()
@@ -203,9 +1021,21 @@ pub(super) fn table_routes() -> Router<AppState> {
         .route("/query-data/:table_name", post(query_data))
         .route("/query-result-count/:table_name", post(query_result_count))
         .route("/data/:table_name", post(insert_data))
+        .route("/data/batch/:table_name", post(write_batch))
+        .route("/data/edits/:table_name", post(edit_batch))
         .route("/data/:table_name", patch(update_data))
         .route("/data/:table_name", delete(delete_data))
         .route("/data/export/:table_name", post(export))
         .route("/data/clear/:table_name", delete(clear_table))
         .route("/drop/:table_name", delete(drop_table))
+        .route("/webhooks/:table_name", get(get_webhook_deliveries))
+        .route("/media/:table_name", get(get_media))
+        .route("/history/:table_name", get(query_history))
+        .route("/history/latest/:table_name", get(latest_history))
+        .route("/history/activity/:table_name", get(history_activity))
+        .route(
+            "/import/:table_name",
+            post(import_data).layer(DefaultBodyLimit::max(MAX_IMPORT_SIZE)),
+        )
+        .route("/import/progress/:id", get(import_progress))
 }
@@ -0,0 +1,983 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, Bytes},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use axum::http::StatusCode;
+use base::{row::ColumnValue, SharedTable};
+use calamine::{open_workbook, Data, DataType, Range, Reader, Xlsx, XlsxError};
+use chrono::NaiveTime;
+use common::{
+    data::{
+        columns::{Column, ColumnList},
+        table::NotifyTrigger,
+    },
+    error::AppError,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::{history::parse_time, webhooks::Notifier};
+
+/// Rows inserted per transaction, unless the request asks otherwise.
+pub(crate) const DEFAULT_BATCH_SIZE: usize = 1000;
+
+/// Invalid rows listed in an [`ImportReport`]. Further ones are only counted.
+const MAX_REPORTED_ERRORS: usize = 100;
+
+/// Finished imports kept for their progress to be read.
+const MAX_FINISHED_JOBS: usize = 100;
+
+/// Format of an imported file.
+#[derive(Clone, Copy)]
+pub(crate) enum ImportFormat {
+    Csv,
+    Tsv,
+    Json,
+    Ndjson,
+    Xlsx,
+}
+
+impl TryFrom<&str> for ImportFormat {
+    type Error = AppError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "csv" => Ok(ImportFormat::Csv),
+            "tsv" => Ok(ImportFormat::Tsv),
+            "json" => Ok(ImportFormat::Json),
+            "ndjson" | "jsonl" => Ok(ImportFormat::Ndjson),
+            "xlsx" => Ok(ImportFormat::Xlsx),
+            other => Err(AppError::HttpError(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Unsupported import format `{other}`, expected csv, tsv, json, ndjson or xlsx"
+                ),
+            )),
+        }
+    }
+}
+
+pub(crate) struct ImportOpts {
+    pub format: ImportFormat,
+
+    /// File column to table column. File columns that aren't mapped are ignored. Without a
+    /// mapping, file columns are imported into the table columns of the same name.
+    pub mapping: Option<HashMap<String, String>>,
+    pub batch_size: usize,
+
+    /// Import the valid rows even if some are invalid, instead of importing nothing.
+    pub skip_invalid: bool,
+
+    /// Sheet of an XLSX workbook to import. Defaults to the first one.
+    pub sheet: Option<String>,
+}
+
+#[derive(Serialize, Clone, Copy, Default, PartialEq, Debug)]
+pub(crate) enum ImportStatus {
+    #[default]
+    Validating,
+    Importing,
+    Done,
+    Failed,
+}
+
+/// A row of the file that can't be imported.
+#[derive(Serialize, Clone, Debug)]
+pub(crate) struct RowError {
+    /// Position of the row in the file, starting at 1 for the first data row.
+    pub row: usize,
+    pub column: Option<String>,
+    pub error: String,
+}
+
+/// Progress and outcome of an import.
+#[derive(Serialize, Clone, Default, Debug)]
+pub(crate) struct ImportReport {
+    pub id: String,
+    pub status: ImportStatus,
+
+    /// Number of rows in the file, known once it's validated.
+    pub total_rows: usize,
+    pub valid_rows: usize,
+    pub processed_rows: usize,
+    pub imported_rows: usize,
+
+    /// Table columns filled by the import.
+    pub columns: Vec<String>,
+
+    /// File columns that don't match any table column.
+    pub ignored_columns: Vec<String>,
+    pub invalid_rows: usize,
+    pub errors: Vec<RowError>,
+
+    /// Why the import stopped, when it failed.
+    pub error: Option<String>,
+
+    /// Failed `After` webhooks of the imported batches.
+    pub warnings: Vec<String>,
+}
+
+impl ImportReport {
+    fn row_error(&mut self, row: usize, column: Option<&str>, error: String) {
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(RowError {
+                row,
+                column: column.map(|c| c.to_string()),
+                error,
+            });
+        }
+    }
+}
+
+struct ImportJob {
+    user_id: String,
+    report: ImportReport,
+}
+
+/// Imports running in the background, and the last finished ones.
+#[derive(Clone, Default)]
+pub(crate) struct ImportJobs(Arc<Mutex<HashMap<String, ImportJob>>>);
+
+impl ImportJobs {
+    /// Registers a new import of `user_id` and returns its id.
+    pub fn start(&self, user_id: &str) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        let report = ImportReport {
+            id: id.clone(),
+            ..ImportReport::default()
+        };
+
+        let mut jobs = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        let finished: Vec<String> = jobs
+            .iter()
+            .filter(|(_, job)| {
+                matches!(job.report.status, ImportStatus::Done | ImportStatus::Failed)
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+        if finished.len() >= MAX_FINISHED_JOBS {
+            finished.iter().for_each(|id| {
+                jobs.remove(id);
+            });
+        }
+
+        jobs.insert(
+            id.clone(),
+            ImportJob {
+                user_id: user_id.to_string(),
+                report,
+            },
+        );
+
+        id
+    }
+
+    pub fn update(&self, report: &ImportReport) {
+        let mut jobs = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(job) = jobs.get_mut(&report.id) {
+            job.report = report.clone();
+        }
+    }
+
+    pub fn get(&self, id: &str, user_id: &str) -> Result<ImportReport, AppError> {
+        let jobs = self.0.lock().unwrap_or_else(|err| err.into_inner());
+
+        jobs.get(id)
+            .filter(|job| job.user_id == user_id)
+            .map(|job| job.report.clone())
+            .ok_or_else(|| {
+                AppError::HttpError(StatusCode::NOT_FOUND, "Import not found".to_string())
+            })
+    }
+}
+
+/// An uploaded file, deleted once dropped.
+pub(crate) struct TempFile(pub PathBuf);
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.0).ok();
+    }
+}
+
+/// A row read from the file: table column and value pairs. `None` is a null value.
+type Record = Vec<(String, Option<String>)>;
+
+/// Imports a file into a table. The file is read twice: rows are validated first, and only
+/// inserted, in batches of one transaction each, if they're all valid or `skip_invalid` is set.
+pub(crate) struct Importer {
+    table: SharedTable,
+    columns: ColumnList,
+    opts: ImportOpts,
+}
+
+impl Importer {
+    pub fn new(table: SharedTable, opts: ImportOpts) -> Result<Self, AppError> {
+        if let Some(mapping) = &opts.mapping {
+            let targets: Vec<&str> = mapping.values().map(|c| c.as_str()).collect();
+            table.check_columns(&targets)?;
+        }
+
+        if opts.batch_size == 0 {
+            return Err(AppError::HttpError(
+                StatusCode::BAD_REQUEST,
+                "batch_size must be greater than 0".to_string(),
+            ));
+        }
+
+        Ok(Importer {
+            columns: table.query_columns()?,
+            table,
+            opts,
+        })
+    }
+
+    /// Checks every row of `path`, without inserting anything. Fails if a required column, one
+    /// that can't be null and has no default, isn't imported.
+    pub fn validate(&self, path: &Path, report: &mut ImportReport) -> Result<(), AppError> {
+        let mut ignored: Vec<String> = Vec::new();
+        let mut seen: Vec<&str> = Vec::new();
+
+        // Rows without some columns that can't be null, and whether they're valid otherwise.
+        // Whether that's an error depends on the other rows having them.
+        let mut absent: Vec<(usize, Vec<&str>, bool)> = Vec::new();
+
+        for (index, record) in self.records(path, &mut ignored)?.enumerate() {
+            let row = index + 1;
+            report.total_rows += 1;
+
+            let record = match record {
+                Ok(record) => record,
+                Err(err) => {
+                    report.invalid_rows += 1;
+                    report.row_error(row, None, err);
+                    continue;
+                }
+            };
+
+            let mut valid = true;
+            for (name, value) in &record {
+                let Some(column) = self.column(name) else {
+                    if !ignored.contains(name) {
+                        ignored.push(name.clone());
+                    }
+                    continue;
+                };
+                if !seen.contains(&column.name.as_str()) {
+                    seen.push(&column.name);
+                }
+
+                if let Err(err) = coerce(value.as_deref(), column) {
+                    valid = false;
+                    report.row_error(row, Some(name), err);
+                }
+            }
+
+            let not_null: Vec<&str> = self
+                .columns
+                .iter()
+                .filter(|c| !c.nullable)
+                .filter(|c| {
+                    !record
+                        .iter()
+                        .any(|(name, _)| self.column(name).is_some_and(|m| m.name == c.name))
+                })
+                .map(|c| c.name.as_str())
+                .collect();
+            if !not_null.is_empty() {
+                absent.push((row, not_null, valid));
+            }
+
+            match valid {
+                true => report.valid_rows += 1,
+                false => report.invalid_rows += 1,
+            }
+        }
+
+        // Keep the table order.
+        report.columns = self
+            .columns
+            .iter()
+            .filter(|c| seen.contains(&c.name.as_str()))
+            .map(|c| c.name.clone())
+            .collect();
+        report.ignored_columns = ignored;
+
+        if report.columns.is_empty() {
+            return Err(AppError::HttpError(
+                StatusCode::BAD_REQUEST,
+                "No column of the file matches a table column".to_string(),
+            ));
+        }
+
+        if let Some(column) = self
+            .columns
+            .iter()
+            .find(|c| is_required(c) && !seen.contains(&c.name.as_str()))
+        {
+            return Err(AppError::HttpError(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!(
+                    "`{}` is required, but no column of the file is imported into it",
+                    column.name
+                ),
+            ));
+        }
+
+        for (row, not_null, valid) in absent {
+            let Some(column) = not_null
+                .into_iter()
+                .find(|c| report.columns.iter().any(|name| name == c))
+            else {
+                continue;
+            };
+
+            report.row_error(row, Some(column), format!("`{column}` can't be empty"));
+            if valid {
+                report.valid_rows -= 1;
+                report.invalid_rows += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Inserts the rows of a file checked by [`Importer::validate`]. `progress` is called
+    /// after each batch. Each batch is inserted between the `Create` webhooks of `notifier`.
+    pub fn import(
+        &self,
+        path: &Path,
+        report: &mut ImportReport,
+        notifier: &Notifier,
+        progress: impl Fn(&ImportReport),
+    ) -> Result<(), AppError> {
+        if report.invalid_rows > 0 && !self.opts.skip_invalid {
+            return Err(AppError::HttpError(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!(
+                    "{} rows are invalid, nothing was imported",
+                    report.invalid_rows
+                ),
+            ));
+        }
+
+        let columns: Vec<&Column> = report
+            .columns
+            .iter()
+            .filter_map(|name| self.columns.iter().find(|c| c.name == *name))
+            .collect();
+        let names: Vec<String> = columns.iter().map(|c| c.name.clone()).collect();
+
+        let mut batch = Vec::with_capacity(self.opts.batch_size);
+        for record in self.records(path, &mut Vec::new())? {
+            report.processed_rows += 1;
+
+            // Invalid rows were reported when the file was validated.
+            let row = record
+                .ok()
+                .and_then(|record| self.coerce_row(&record, &columns).ok());
+            if let Some(row) = row {
+                batch.push(row);
+            }
+
+            if batch.len() == self.opts.batch_size {
+                self.insert(&names, std::mem::take(&mut batch), notifier, report)?;
+                progress(report);
+            }
+        }
+
+        if !batch.is_empty() {
+            self.insert(&names, batch, notifier, report)?;
+        }
+
+        Ok(())
+    }
+
+    fn insert(
+        &self,
+        names: &[String],
+        batch: Vec<Vec<ColumnValue>>,
+        notifier: &Notifier,
+        report: &mut ImportReport,
+    ) -> Result<(), AppError> {
+        let rows = match notifier.listens_to(&NotifyTrigger::Create) {
+            true => batch
+                .iter()
+                .map(|row| json!(names.iter().zip(row).collect::<HashMap<_, _>>()))
+                .collect(),
+            false => Vec::new(),
+        };
+
+        let notified = notifier.around(NotifyTrigger::Create, rows, || {
+            self.table.insert_batch(names, batch)
+        })?;
+        report.imported_rows += notified.value;
+        report.warnings.extend(notified.warnings);
+
+        Ok(())
+    }
+
+    /// Validates then imports `path`, keeping the progress in `jobs` up to date. The
+    /// `notifier` of the table is loaded by the caller, failing the import if it couldn't be.
+    pub fn run(
+        &self,
+        path: &Path,
+        report: &mut ImportReport,
+        jobs: &ImportJobs,
+        notifier: Result<Notifier, AppError>,
+    ) {
+        let result = self.validate(path, report).and_then(|_| {
+            let notifier = notifier?;
+            report.status = ImportStatus::Importing;
+            jobs.update(report);
+            self.import(path, report, &notifier, |report| jobs.update(report))
+        });
+
+        match result {
+            Ok(_) => report.status = ImportStatus::Done,
+            Err(err) => {
+                report.status = ImportStatus::Failed;
+                report.error = Some(err.to_string());
+            }
+        }
+
+        jobs.update(report);
+    }
+
+    /// The table column a file column is imported into.
+    fn column(&self, file_column: &str) -> Option<&Column> {
+        let name = match &self.opts.mapping {
+            Some(mapping) => mapping.get(file_column)?.as_str(),
+            None => file_column,
+        };
+
+        self.columns.iter().find(|c| c.name == name)
+    }
+
+    fn coerce_row(&self, record: &Record, columns: &[&Column]) -> Result<Vec<ColumnValue>, String> {
+        columns
+            .iter()
+            .map(|column| {
+                let value = record
+                    .iter()
+                    .find(|(name, _)| self.column(name).is_some_and(|c| c.name == column.name))
+                    .and_then(|(_, value)| value.as_deref());
+
+                coerce(value, column)
+            })
+            .collect()
+    }
+
+    /// Reads the rows of `path` one at a time. File columns not imported are added to `ignored`.
+    fn records(
+        &self,
+        path: &Path,
+        ignored: &mut Vec<String>,
+    ) -> Result<Box<dyn Iterator<Item = Result<Record, String>>>, AppError> {
+        let invalid = |err: String| AppError::HttpError(StatusCode::UNPROCESSABLE_ENTITY, err);
+
+        match self.opts.format {
+            ImportFormat::Csv | ImportFormat::Tsv => {
+                let (delimiter, quoting) = match self.opts.format {
+                    ImportFormat::Tsv => (b'\t', false),
+                    _ => (b',', true),
+                };
+
+                let mut reader = csv::ReaderBuilder::new()
+                    .delimiter(delimiter)
+                    .quoting(quoting)
+                    .flexible(true)
+                    .from_path(path)
+                    .map_err(|err| invalid(err.to_string()))?;
+
+                let headers: Vec<String> = reader
+                    .headers()
+                    .map_err(|err| invalid(err.to_string()))?
+                    .iter()
+                    .map(|h| h.trim().to_string())
+                    .collect();
+                ignored.extend(headers.iter().filter(|h| self.column(h).is_none()).cloned());
+
+                Ok(Box::new(reader.into_records().map(move |record| {
+                    let record = record.map_err(|err| err.to_string())?;
+                    Ok(headers
+                        .iter()
+                        .cloned()
+                        .zip(record.iter().map(|v| Some(v.to_string())))
+                        .collect())
+                })))
+            }
+            ImportFormat::Ndjson => {
+                let file = File::open(path).map_err(|err| invalid(err.to_string()))?;
+                let lines = BufReader::new(file)
+                    .lines()
+                    .filter(|line| !line.as_ref().is_ok_and(|l| l.trim().is_empty()));
+
+                Ok(Box::new(lines.map(|line| {
+                    let line = line.map_err(|err| err.to_string())?;
+                    let value = serde_json::from_str(&line).map_err(|err| err.to_string())?;
+                    json_record(value)
+                })))
+            }
+            ImportFormat::Json => {
+                let file = File::open(path).map_err(|err| invalid(err.to_string()))?;
+                let values = JsonArray::open(BufReader::new(file))
+                    .map_err(|err| invalid(format!("Expected an array of objects: {err}")))?;
+
+                Ok(Box::new(values.map(|value| json_record(value?))))
+            }
+            ImportFormat::Xlsx => {
+                let (headers, range) =
+                    read_sheet(path, self.opts.sheet.as_deref()).map_err(invalid)?;
+                ignored.extend(headers.iter().filter(|h| self.column(h).is_none()).cloned());
+
+                // Cells are read as the type of the column they're imported into.
+                let col_types: Vec<String> = headers
+                    .iter()
+                    .map(|h| {
+                        self.column(h)
+                            .map(|c| c.col_type.to_lowercase())
+                            .unwrap_or_default()
+                    })
+                    .collect();
+
+                Ok(Box::new((1..range.height()).filter_map(move |row| {
+                    let cells: Vec<Option<&Data>> = (0..headers.len())
+                        .map(|col| range.get((row, col)))
+                        .collect();
+                    if cells.iter().all(|cell| cell.is_none_or(|c| c.is_empty())) {
+                        return None;
+                    }
+
+                    let record = headers
+                        .iter()
+                        .zip(&col_types)
+                        .zip(cells)
+                        .map(|((header, col_type), cell)| {
+                            let value = match cell {
+                                Some(cell) => cell_value(cell, col_type)
+                                    .map_err(|err| format!("`{header}` {err}"))?,
+                                None => None,
+                            };
+                            Ok((header.clone(), value))
+                        })
+                        .collect();
+
+                    Some(record)
+                })))
+            }
+        }
+    }
+}
+
+/// Opens a sheet of an XLSX workbook, the first one by default, returning its header row and
+/// its cells.
+fn read_sheet(path: &Path, sheet: Option<&str>) -> Result<(Vec<String>, Range<Data>), String> {
+    let mut workbook: Xlsx<BufReader<File>> =
+        open_workbook(path).map_err(|err: XlsxError| err.to_string())?;
+    let name = match sheet {
+        Some(name) => name.to_string(),
+        None => workbook
+            .sheet_names()
+            .first()
+            .cloned()
+            .ok_or_else(|| "The workbook has no sheet".to_string())?,
+    };
+
+    let range = workbook
+        .worksheet_range(&name)
+        .map_err(|err| format!("Sheet `{name}`: {err}"))?;
+    let headers = range
+        .rows()
+        .next()
+        .map(|row| {
+            row.iter()
+                .map(|cell| cell.to_string().trim().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok((headers, range))
+}
+
+/// An XLSX cell as text, for [`coerce`] to convert to `col_type`.
+fn cell_value(cell: &Data, col_type: &str) -> Result<Option<String>, String> {
+    let value = match cell {
+        Data::Empty => return Ok(None),
+        Data::String(v) | Data::DateTimeIso(v) | Data::DurationIso(v) => v.clone(),
+        Data::Int(v) => v.to_string(),
+        Data::Float(v) => v.to_string(),
+        Data::Bool(v) if is_text(col_type) || col_type.contains("bool") => v.to_string(),
+        // Other columns holding booleans are numbers, like MySQL `tinyint(1)`.
+        Data::Bool(v) => u8::from(*v).to_string(),
+        Data::DateTime(v) if v.is_duration() => {
+            let seconds = v
+                .as_duration()
+                .ok_or("holds an invalid time")?
+                .num_seconds();
+            format!(
+                "{:02}:{:02}:{:02}",
+                seconds / 3600,
+                seconds % 3600 / 60,
+                seconds % 60
+            )
+        }
+        Data::DateTime(v) => {
+            let time = v.as_datetime().ok_or("holds an invalid date")?;
+            // Times of day are dates before the first day of Excel.
+            let time_of_day = v.as_f64() < 1.0;
+            let time_column = col_type.starts_with("time") && !col_type.starts_with("timestamp");
+            let format = if time_of_day || time_column {
+                "%H:%M:%S%.f"
+            } else if time.time() == NaiveTime::MIN {
+                "%Y-%m-%d"
+            } else {
+                "%Y-%m-%d %H:%M:%S%.f"
+            };
+            time.format(format).to_string()
+        }
+        Data::Error(err) => return Err(format!("holds the error {err}")),
+    };
+
+    Ok(Some(value))
+}
+
+/// Reads the elements of a JSON array one at a time, so the array is never held in memory.
+struct JsonArray<R: BufRead> {
+    bytes: Bytes<R>,
+    done: bool,
+    first: bool,
+}
+
+impl<R: BufRead> JsonArray<R> {
+    fn open(reader: R) -> Result<Self, String> {
+        let mut bytes = reader.bytes();
+        let start = bytes
+            .by_ref()
+            .map(|b| b.map_err(|err| err.to_string()))
+            .find(|b| !b.as_ref().is_ok_and(u8::is_ascii_whitespace))
+            .transpose()?;
+
+        match start {
+            Some(b'[') => Ok(JsonArray {
+                bytes,
+                done: false,
+                first: true,
+            }),
+            _ => Err("the file doesn't start with `[`".to_string()),
+        }
+    }
+
+    /// The bytes of the next element, up to the `,` or `]` after it.
+    fn next_element(&mut self) -> Result<Option<Vec<u8>>, String> {
+        let mut element = Vec::new();
+        let (mut depth, mut in_string, mut escaped) = (0usize, false, false);
+
+        loop {
+            let Some(byte) = self
+                .bytes
+                .next()
+                .transpose()
+                .map_err(|err| err.to_string())?
+            else {
+                return Err("unexpected end of file".to_string());
+            };
+
+            if in_string {
+                match byte {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'"' => in_string = false,
+                    _ => {}
+                }
+                element.push(byte);
+                continue;
+            }
+
+            match byte {
+                b'"' => in_string = true,
+                b'{' | b'[' => depth += 1,
+                b'}' | b']' if depth > 0 => depth -= 1,
+                b']' => {
+                    self.done = true;
+                    // Only an empty array has nothing before its `]`.
+                    if self.first && element.iter().all(u8::is_ascii_whitespace) {
+                        return Ok(None);
+                    }
+                    return Ok(Some(element));
+                }
+                b',' if depth == 0 => return Ok(Some(element)),
+                _ => {}
+            }
+            element.push(byte);
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for JsonArray<R> {
+    type Item = Result<Value, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let element = match self.next_element() {
+            Ok(element) => element?,
+            Err(err) => {
+                // The rest of the file can't be read.
+                self.done = true;
+                return Some(Err(err));
+            }
+        };
+        self.first = false;
+
+        Some(serde_json::from_slice(&element).map_err(|err| err.to_string()))
+    }
+}
+
+fn json_record(value: Value) -> Result<Record, String> {
+    let Value::Object(object) = value else {
+        return Err("expected a JSON object".to_string());
+    };
+
+    Ok(object
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Value::Null => None,
+                Value::String(s) => Some(s),
+                // Numbers, booleans and nested values keep their JSON text.
+                v => Some(v.to_string()),
+            };
+
+            (key, value)
+        })
+        .collect())
+}
+
+/// Convert a file value to the type of `column`.
+pub(crate) fn coerce(value: Option<&str>, column: &Column) -> Result<ColumnValue, String> {
+    let col_type = column.col_type.to_lowercase();
+
+    let value = match value {
+        Some(value) if is_text(&col_type) => Some(value),
+        value => value.map(|v| v.trim()).filter(|v| !v.is_empty()),
+    };
+
+    let Some(value) = value else {
+        return match column.nullable {
+            true => Ok(ColumnValue::NULL),
+            false => Err(format!("`{}` can't be empty", column.name)),
+        };
+    };
+
+    let invalid = |kind: &str| format!("`{value}` is not a valid {kind}");
+
+    if col_type.contains("timestamp") || col_type.contains("datetime") {
+        return parse_time(value, "%Y-%m-%d %H:%M:%S")
+            .map(|time| ColumnValue::Text(time.format("%Y-%m-%d %H:%M:%S%.f").to_string()))
+            .ok_or_else(|| invalid("date and time"));
+    }
+
+    if col_type.contains("date") {
+        // Keep the time if there's one: Mongo `date` values are date times.
+        return parse_time(value, "%Y-%m-%d")
+            .map(|time| match time.time() == NaiveTime::MIN {
+                true => time.format("%Y-%m-%d").to_string(),
+                false => time.format("%Y-%m-%d %H:%M:%S%.f").to_string(),
+            })
+            .map(ColumnValue::Text)
+            .ok_or_else(|| invalid("date"));
+    }
+
+    if col_type.starts_with("time") {
+        return ["%H:%M:%S%.f", "%H:%M"]
+            .iter()
+            .find_map(|format| NaiveTime::parse_from_str(value, format).ok())
+            .map(|time| ColumnValue::Text(time.format("%H:%M:%S%.f").to_string()))
+            .ok_or_else(|| invalid("time"));
+    }
+
+    if col_type.contains("bool") || col_type == "tinyint(1)" || col_type == "bit(1)" {
+        return match value.to_lowercase().as_str() {
+            "1" | "true" | "t" | "yes" | "y" => Ok(ColumnValue::Int(1)),
+            "0" | "false" | "f" | "no" | "n" => Ok(ColumnValue::Int(0)),
+            _ => Err(invalid("boolean")),
+        };
+    }
+
+    if col_type.contains("int") || col_type.contains("serial") || col_type == "long" {
+        let number: i64 = value.parse().map_err(|_| invalid("integer"))?;
+        if col_type.contains("unsigned") && number < 0 {
+            return Err(invalid("unsigned integer"));
+        }
+
+        return Ok(ColumnValue::Int(number));
+    }
+
+    if ["decimal", "numeric", "money"]
+        .iter()
+        .any(|t| col_type.contains(t))
+    {
+        // Sent as text so no precision is lost.
+        return value
+            .parse::<f64>()
+            .map(|_| ColumnValue::Text(value.to_string()))
+            .map_err(|_| invalid("number"));
+    }
+
+    if ["float", "double", "real"]
+        .iter()
+        .any(|t| col_type.contains(t))
+    {
+        return value
+            .parse::<f64>()
+            .map(ColumnValue::Double)
+            .map_err(|_| invalid("number"));
+    }
+
+    if col_type.contains("json") {
+        return serde_json::from_str::<Value>(value)
+            .map(|_| ColumnValue::Text(value.to_string()))
+            .map_err(|_| invalid("JSON value"));
+    }
+
+    Ok(ColumnValue::Text(value.to_string()))
+}
+
+/// Whether `column` must be given a value: it can't be null and has no default. Integer primary
+/// keys are left out, since they're generated.
+fn is_required(column: &Column) -> bool {
+    let col_type = column.col_type.to_lowercase();
+    let generated = column.primary && (col_type.contains("int") || col_type.contains("serial"));
+
+    !column.nullable && column.default_value.is_none() && !generated
+}
+
+/// Whether values of `col_type`, lowercase, are text.
+fn is_text(col_type: &str) -> bool {
+    ["char", "text", "string", "clob"]
+        .iter()
+        .any(|t| col_type.contains(t))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use base::{export::ExportWriter, row::ColumnValue};
+    use calamine::Data;
+    use common::data::{
+        columns::Column,
+        table::{TableExportFormat, XmlExportOpts},
+    };
+
+    use super::{cell_value, coerce, read_sheet, JsonArray, TempFile};
+
+    fn column(col_type: &str, nullable: bool) -> Column {
+        Column {
+            name: "col".to_string(),
+            col_type: col_type.to_string(),
+            nullable,
+            default_value: None,
+            unique: false,
+            primary: false,
+        }
+    }
+
+    #[test]
+    fn test_coerce() {
+        assert_eq!(
+            coerce(Some(" 42 "), &column("bigint", false)),
+            Ok(ColumnValue::Int(42))
+        );
+        assert!(coerce(Some("4.2"), &column("int(11)", false)).is_err());
+        assert!(coerce(Some("-1"), &column("int unsigned", false)).is_err());
+        assert_eq!(
+            coerce(Some("yes"), &column("tinyint(1)", false)),
+            Ok(ColumnValue::Int(1))
+        );
+        assert_eq!(
+            coerce(Some("1.5"), &column("double", false)),
+            Ok(ColumnValue::Double(1.5))
+        );
+        assert_eq!(
+            coerce(Some("2024-03-09T10:30:00"), &column("datetime", false)),
+            Ok(ColumnValue::Text("2024-03-09 10:30:00".to_string()))
+        );
+        assert!(coerce(Some("09/03/2024"), &column("date", false)).is_err());
+
+        assert_eq!(
+            coerce(Some(""), &column("int", true)),
+            Ok(ColumnValue::NULL)
+        );
+        assert!(coerce(None, &column("int", false)).is_err());
+        assert_eq!(
+            coerce(Some(""), &column("varchar(20)", false)),
+            Ok(ColumnValue::Text(String::new()))
+        );
+    }
+
+    #[test]
+    fn test_json_array() {
+        let read = |json: &str| -> Vec<Result<serde_json::Value, String>> {
+            JsonArray::open(json.as_bytes()).unwrap().collect()
+        };
+
+        let values = read(r#" [{"a": "x,]}\"", "b": [1, {"c": 2}]}, 3 ,{}] "#);
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[0].as_ref().unwrap()["a"], "x,]}\"");
+        assert_eq!(values[0].as_ref().unwrap()["b"][1]["c"], 2);
+        assert_eq!(values[1], Ok(serde_json::json!(3)));
+
+        assert!(read("[]").is_empty());
+        assert!(read(r#"[{"a": 1}, oops]"#)[1].is_err());
+        assert!(read(r#"[{"a": 1}"#)[0].is_err());
+        assert!(JsonArray::open(r#"{"a": 1}"#.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_xlsx_cells() {
+        let path = std::env::temp_dir().join(format!("basable-test-{}.xlsx", uuid::Uuid::new_v4()));
+        let temp = TempFile(path);
+
+        let columns = vec!["id".to_string(), "born".to_string(), "name".to_string()];
+        let mut out = Vec::new();
+        let mut writer = ExportWriter::new(
+            TableExportFormat::XLSX,
+            XmlExportOpts::default(),
+            columns,
+            &mut out,
+        )
+        .unwrap();
+        writer
+            .write_values(&[
+                ColumnValue::Int(7),
+                ColumnValue::Date(2024, 3, 9, 0, 0, 0, 0),
+                ColumnValue::NULL,
+            ])
+            .unwrap();
+        writer.finish().unwrap();
+        std::fs::write(&temp.0, out).unwrap();
+
+        let (headers, range) = read_sheet(&temp.0, None).unwrap();
+        assert_eq!(headers, ["id", "born", "name"]);
+        assert_eq!(range.height(), 2);
+
+        let cell = |col| range.get((1, col)).cloned().unwrap_or(Data::Empty);
+        assert_eq!(cell_value(&cell(0), "int"), Ok(Some("7".to_string())));
+        assert_eq!(
+            cell_value(&cell(1), "date"),
+            Ok(Some("2024-03-09".to_string()))
+        );
+        assert_eq!(cell_value(&cell(2), "varchar(20)"), Ok(None));
+        assert_eq!(
+            cell_value(&Data::Bool(true), "int"),
+            Ok(Some("1".to_string()))
+        );
+        assert!(read_sheet(&temp.0, Some("Missing")).is_err());
+    }
+}
@@ -5,11 +5,17 @@ use utils::get_env;
 
 use crate::http::app::app;
 
+mod credentials;
 mod foundation;
+mod history;
 mod http;
+mod import;
+mod media;
+mod remote;
 mod state;
 mod user;
 mod utils;
+mod webhooks;
 
 enum DeploymentMode {
     Cloud,
@@ -17,10 +23,7 @@ enum DeploymentMode {
 }
 impl DeploymentMode {
     fn is_local(&self) -> bool {
-        match self {
-            DeploymentMode::Local => true,
-            _ => false,
-        }
+        matches!(self, DeploymentMode::Local)
     }
 }
 impl From<String> for DeploymentMode {
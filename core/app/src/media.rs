@@ -0,0 +1,275 @@
+use std::{
+    io::{Cursor, Read},
+    path::{Component, Path, PathBuf},
+    time::Duration,
+};
+
+use axum::http::StatusCode;
+use common::{
+    data::table::{SpecialColumn, SpecialValueType},
+    error::AppError,
+};
+use image::ImageFormat;
+
+use crate::{
+    remote::{self, AllowedUrls},
+    utils::resolve_under,
+};
+
+/// Placeholder for the column value in a [`SpecialColumn`] path.
+const VALUE_PLACEHOLDER: &str = "{value}";
+
+/// Largest thumbnail edge, in pixels.
+pub(crate) const MAX_THUMBNAIL_SIZE: u32 = 1024;
+
+/// Largest image we'll load in memory to make a thumbnail.
+const MAX_IMAGE_BYTES: u64 = 32 * 1024 * 1024;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Where the media of a [`SpecialColumn`] value is stored.
+#[derive(Debug, PartialEq)]
+pub(crate) enum MediaSource {
+    File(PathBuf),
+    Url(String),
+}
+
+impl MediaSource {
+    /// Resolves `value` against the path of `column`. Local media must be inside `root`, the
+    /// [`media_dir`](crate::utils::media_dir): relative paths are relative to it, and anything
+    /// resolving outside of it is `403 Forbidden`. URLs must start with one of `urls`.
+    pub fn resolve(
+        column: &SpecialColumn,
+        value: &str,
+        root: &Path,
+        urls: &AllowedUrls,
+    ) -> Result<Self, AppError> {
+        let path = column.path();
+
+        // Values are relative to the path: they can't climb out of it.
+        let relative = Path::new(value);
+        let is_relative = relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+        if value.is_empty() || !is_relative {
+            return Err(AppError::HttpError(
+                StatusCode::BAD_REQUEST,
+                format!("`{value}` is not a valid media path"),
+            ));
+        }
+
+        if path.starts_with("http://") || path.starts_with("https://") {
+            let value = encode_url_value(value);
+            let url = match path.contains(VALUE_PLACEHOLDER) {
+                true => path.replace(VALUE_PLACEHOLDER, &value),
+                false => format!("{}/{}", path.trim_end_matches('/'), value),
+            };
+            urls.check(&url)?;
+
+            return Ok(MediaSource::Url(url));
+        }
+
+        let file = match path.contains(VALUE_PLACEHOLDER) {
+            true => PathBuf::from(path.replace(VALUE_PLACEHOLDER, value)),
+            false => Path::new(path).join(relative),
+        };
+
+        let file = resolve_under(root, &file).map_err(|err| match err {
+            AppError::HttpError(StatusCode::NOT_FOUND, _) => media_not_found(),
+            err => err,
+        })?;
+
+        Ok(MediaSource::File(file))
+    }
+
+    /// Makes a PNG thumbnail of the image, fitting in a `size` pixels square.
+    pub fn thumbnail(&self, size: u32) -> Result<Vec<u8>, AppError> {
+        let mut bytes = Vec::new();
+        let read = match self {
+            MediaSource::File(path) => std::fs::File::open(path)
+                .map_err(|_| media_not_found())?
+                .take(MAX_IMAGE_BYTES)
+                .read_to_end(&mut bytes),
+            MediaSource::Url(url) => fetch(url, None)?
+                .into_reader()
+                .take(MAX_IMAGE_BYTES)
+                .read_to_end(&mut bytes),
+        };
+        read.map_err(|err| AppError::ServerError(err.to_string()))?;
+
+        let image = image::load_from_memory(&bytes).map_err(|err| {
+            AppError::HttpError(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("Unable to read image: {err}"),
+            )
+        })?;
+
+        let mut thumbnail = Vec::new();
+        image
+            .thumbnail(size, size)
+            .write_to(&mut Cursor::new(&mut thumbnail), ImageFormat::Png)
+            .map_err(|err| AppError::ServerError(err.to_string()))?;
+
+        Ok(thumbnail)
+    }
+}
+
+/// Checks a thumbnail can be made for `column`.
+pub(crate) fn check_thumbnail(column: &SpecialColumn, size: u32) -> Result<(), AppError> {
+    if *column.special_type() != SpecialValueType::Image {
+        return Err(AppError::HttpError(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Thumbnails are only available for images, `{}` holds {:?} values",
+                column.name(),
+                column.special_type()
+            ),
+        ));
+    }
+
+    if size == 0 || size > MAX_THUMBNAIL_SIZE {
+        return Err(AppError::HttpError(
+            StatusCode::BAD_REQUEST,
+            format!("Thumbnail size must be between 1 and {MAX_THUMBNAIL_SIZE}"),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Requests `url`, forwarding the `Range` header of the client if any. Error statuses are
+/// returned as they are, so clients see a `404` or `416` from the media server. Redirects
+/// aren't followed.
+pub(crate) fn fetch(url: &str, range: Option<&str>) -> Result<ureq::Response, AppError> {
+    let mut request = remote::agent(TIMEOUT).get(url);
+    if let Some(range) = range {
+        request = request.set("Range", range);
+    }
+
+    match request.call() {
+        Ok(response) if (300..400).contains(&response.status()) => Err(AppError::HttpError(
+            StatusCode::BAD_GATEWAY,
+            "Media server redirected to another URL, which isn't followed".to_string(),
+        )),
+        Ok(response) => Ok(response),
+        Err(ureq::Error::Status(code, _)) => Err(AppError::HttpError(
+            StatusCode::from_u16(code).unwrap_or(StatusCode::BAD_GATEWAY),
+            format!("Media server responded with {code}"),
+        )),
+        Err(err) => Err(AppError::HttpError(
+            StatusCode::BAD_GATEWAY,
+            err.to_string(),
+        )),
+    }
+}
+
+pub(crate) fn media_not_found() -> AppError {
+    AppError::HttpError(StatusCode::NOT_FOUND, "Media not found".to_string())
+}
+
+/// Percent-encode a value for use in a URL path. `/` is kept, so values can hold subpaths.
+fn encode_url_value(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            b => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use axum::http::StatusCode;
+    use common::{data::table::SpecialColumn, error::AppError};
+
+    use crate::remote::{AllowedUrls, MEDIA_URLS_VAR};
+
+    use super::MediaSource;
+
+    fn column(path: &str) -> SpecialColumn {
+        serde_json::from_value(serde_json::json!({
+            "name": "avatar",
+            "special_type": "Image",
+            "path": path
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_resolve() {
+        let dir = std::env::temp_dir().join(format!("basable-test-{}", uuid::Uuid::new_v4()));
+        let root = dir.join("media");
+        std::fs::create_dir_all(root.join("users")).unwrap();
+        std::fs::write(root.join("users/1.png"), "").unwrap();
+        std::fs::write(dir.join("secret.png"), "").unwrap();
+        let urls = AllowedUrls::new(MEDIA_URLS_VAR, "https://cdn.test");
+
+        let file = MediaSource::File(root.canonicalize().unwrap().join("users/1.png"));
+        assert_eq!(
+            MediaSource::resolve(&column("users"), "1.png", &root, &urls).unwrap(),
+            file
+        );
+        assert_eq!(
+            MediaSource::resolve(&column("."), "users/1.png", &root, &urls).unwrap(),
+            file
+        );
+        assert_eq!(
+            MediaSource::resolve(&column("users/{value}"), "1.png", &root, &urls).unwrap(),
+            file
+        );
+        assert!(MediaSource::resolve(&column("users"), "../etc/passwd", &root, &urls).is_err());
+        assert!(MediaSource::resolve(&column("users"), "/etc/passwd", &root, &urls).is_err());
+
+        // The column path is part of the table config: it can't point outside the root either.
+        let status = |path: &str, value: &str| match MediaSource::resolve(
+            &column(path),
+            value,
+            &root,
+            &urls,
+        ) {
+            Err(AppError::HttpError(status, _)) => status,
+            other => panic!("unexpected {other:?}"),
+        };
+        assert_eq!(
+            status(&dir.to_string_lossy(), "secret.png"),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(status("..", "secret.png"), StatusCode::FORBIDDEN);
+        assert_eq!(
+            status("{value}/../../secret.png", "users"),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(status("users", "2.png"), StatusCode::NOT_FOUND);
+
+        assert_eq!(
+            MediaSource::resolve(&column("https://cdn.test/img/"), "a b.png", &root, &urls)
+                .unwrap(),
+            MediaSource::Url("https://cdn.test/img/a%20b.png".to_string())
+        );
+        assert_eq!(
+            MediaSource::resolve(
+                &column("https://cdn.test/{value}?w=1"),
+                "1.png",
+                &root,
+                &urls
+            )
+            .unwrap(),
+            MediaSource::Url("https://cdn.test/1.png?w=1".to_string())
+        );
+
+        // URLs must be allowed by the admin, and values can't climb out of their path.
+        assert_eq!(
+            status("https://169.254.169.254/{value}", "latest"),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            status("https://cdn.test/img/", "../../admin"),
+            StatusCode::BAD_REQUEST
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
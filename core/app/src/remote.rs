@@ -0,0 +1,183 @@
+//! Requests to the servers named in table configurations, like media servers and webhooks.
+//! Clients can save those, so the servers must be allowed by the admin, and requests never
+//! reach loopback, private or link-local addresses.
+
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, SocketAddr, ToSocketAddrs},
+    time::Duration,
+};
+
+use axum::http::StatusCode;
+use common::error::AppError;
+
+use crate::utils::get_env;
+
+/// Variable listing the URL prefixes media can be fetched from.
+pub(crate) const MEDIA_URLS_VAR: &str = "BASABLE_MEDIA_URLS";
+
+/// Variable listing the URL prefixes webhooks can be sent to.
+pub(crate) const WEBHOOK_URLS_VAR: &str = "BASABLE_WEBHOOK_URLS";
+
+/// URL prefixes requests are allowed to, as listed by the admin in a variable.
+pub(crate) struct AllowedUrls {
+    var: &'static str,
+    prefixes: Vec<String>,
+}
+
+impl AllowedUrls {
+    /// The comma separated prefixes of the `var` variable. None are allowed when it isn't set.
+    pub fn from_env(var: &'static str) -> Self {
+        AllowedUrls::new(var, &get_env(var).unwrap_or_default())
+    }
+
+    pub fn new(var: &'static str, prefixes: &str) -> Self {
+        let prefixes = prefixes
+            .split(',')
+            .map(str::trim)
+            .filter(|prefix| !prefix.is_empty())
+            .map(|prefix| prefix.to_string())
+            .collect();
+
+        AllowedUrls { var, prefixes }
+    }
+
+    /// Checks the http(s) `url` starts with one of the prefixes, failing with
+    /// `403 Forbidden` otherwise. A prefix must end at a `/`, `?` or `#` of the url, so
+    /// `https://cdn.example.com` doesn't allow `https://cdn.example.com.evil.com`.
+    pub fn check(&self, url: &str) -> Result<(), AppError> {
+        let is_http = url.starts_with("http://") || url.starts_with("https://");
+        let is_allowed = |prefix: &String| match url.strip_prefix(prefix.as_str()) {
+            Some(rest) => {
+                prefix.ends_with('/') || rest.is_empty() || rest.starts_with(['/', '?', '#'])
+            }
+            None => false,
+        };
+
+        if !is_http || !self.prefixes.iter().any(is_allowed) {
+            return Err(AppError::HttpError(
+                StatusCode::FORBIDDEN,
+                format!(
+                    "`{url}` isn't allowed: its server must be listed in `{}`",
+                    self.var
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// An agent that doesn't follow redirects, which could lead anywhere, and only connects to
+/// public addresses.
+pub(crate) fn agent(timeout: Duration) -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout(timeout)
+        .redirects(0)
+        .resolver(public_addrs)
+        .build()
+}
+
+/// Resolves `netloc`, leaving out the addresses that aren't public.
+fn public_addrs(netloc: &str) -> io::Result<Vec<SocketAddr>> {
+    let addrs: Vec<SocketAddr> = netloc
+        .to_socket_addrs()?
+        .filter(|addr| is_public(addr.ip()))
+        .collect();
+
+    if addrs.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("`{netloc}` doesn't resolve to a public address"),
+        ));
+    }
+
+    Ok(addrs)
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    let public_v4 = |ip: Ipv4Addr| {
+        let [a, b, ..] = ip.octets();
+        !(ip.is_unspecified()
+            || ip.is_loopback()
+            || ip.is_private()
+            || ip.is_link_local()
+            || ip.is_broadcast()
+            || ip.is_documentation()
+            || a == 0
+            // Carrier-grade NAT, 100.64.0.0/10.
+            || (a == 100 && (b & 0xc0) == 64)
+            // Multicast and reserved, 224.0.0.0/3.
+            || a >= 224)
+    };
+
+    match ip {
+        IpAddr::V4(ip) => public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => public_v4(ip),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_unspecified()
+                    || ip.is_loopback()
+                    || ip.is_multicast()
+                    // Unique local, fc00::/7.
+                    || (first & 0xfe00) == 0xfc00
+                    // Link-local, fe80::/10.
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{is_public, public_addrs, AllowedUrls, MEDIA_URLS_VAR};
+
+    #[test]
+    fn test_allowed_urls() {
+        let urls = AllowedUrls::new(
+            MEDIA_URLS_VAR,
+            "https://cdn.example.com, https://media.example.org/public/",
+        );
+        assert!(urls.check("https://cdn.example.com/a.png").is_ok());
+        assert!(urls.check("https://media.example.org/public/a.png").is_ok());
+        assert!(urls
+            .check("https://cdn.example.com.evil.com/a.png")
+            .is_err());
+        assert!(urls
+            .check("https://cdn.example.com@evil.com/a.png")
+            .is_err());
+        assert!(urls
+            .check("https://media.example.org/private/a.png")
+            .is_err());
+        assert!(urls.check("http://169.254.169.254/latest").is_err());
+        assert!(AllowedUrls::new(MEDIA_URLS_VAR, "")
+            .check("https://cdn.example.com/a.png")
+            .is_err());
+    }
+
+    #[test]
+    fn test_is_public() {
+        let public = |ip: &str| is_public(ip.parse().unwrap());
+        assert!(public("93.184.216.34"));
+        assert!(public("2606:2800:220:1::"));
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!public(ip), "{ip}");
+        }
+
+        assert!(public_addrs("127.0.0.1:80").is_err());
+        assert!(public_addrs("localhost:80").is_err());
+    }
+}
@@ -1,63 +1,247 @@
-use std::sync::{Arc, Mutex};
+use std::{path::Path, sync::Arc};
 
+use aes_gcm::aead::{rand_core::RngCore, OsRng};
 use axum::http::StatusCode;
+use base::config::{ConfigRaw, ConnectionProfile};
 use common::{data::table::TableConfig, error::AppError};
 use r2d2::{Pool, PooledConnection};
 use r2d2_sqlite::SqliteConnectionManager;
-use rusqlite::params;
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::RwLock;
 
-use crate::foundation::Basable;
+use crate::{
+    credentials::{key_not_set, Credentials, SALT_SIZE},
+    foundation::Basable,
+    import::ImportJobs,
+    utils::{data_dir, get_env, uploads_dir},
+    webhooks::WebhookDelivery,
+};
+
+/// Default location of the [`LocalDB`] file, used when `BASABLE_LOCAL_DB` isn't set.
+const DEFAULT_LOCAL_DB: &str = "basable.db";
+
+/// Schema migrations of the [`LocalDB`], applied in order. The number of migrations a file
+/// has seen is kept in its `user_version`, so existing entries must never be edited:
+/// schema changes go in a new entry at the end.
+const MIGRATIONS: &[&str] = &[
+    // 1: table configurations
+    "CREATE TABLE IF NOT EXISTS table_configs (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL,
+        conn_id TEXT NOT NULL,
+        label TEXT,
+        pk_column TEXT,
+        ipp INTEGER,
+        exclude_columns TEXT
+    )",
+    // 2: saved connection profiles
+    "CREATE TABLE IF NOT EXISTS connection_profiles (
+        id TEXT PRIMARY KEY,
+        user_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        source_type TEXT NOT NULL,
+        source TEXT NOT NULL,
+        username TEXT,
+        password TEXT,
+        host TEXT,
+        port INTEGER,
+        db_name TEXT,
+        path TEXT,
+        UNIQUE (user_id, name)
+    )",
+    // 3: the rest of the table configuration
+    "ALTER TABLE table_configs ADD COLUMN created_column TEXT;
+    ALTER TABLE table_configs ADD COLUMN updated_column TEXT;
+    ALTER TABLE table_configs ADD COLUMN special_columns TEXT;
+    ALTER TABLE table_configs ADD COLUMN events TEXT;",
+    // 4: webhook delivery log
+    "CREATE TABLE IF NOT EXISTS webhook_deliveries (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        conn_id TEXT NOT NULL,
+        table_name TEXT NOT NULL,
+        event TEXT NOT NULL,
+        trigger_time TEXT NOT NULL,
+        method TEXT NOT NULL,
+        url TEXT NOT NULL,
+        attempts INTEGER NOT NULL,
+        status INTEGER,
+        error TEXT,
+        delivered INTEGER NOT NULL,
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    )",
+    // 5: salt the credentials key is derived with
+    "CREATE TABLE IF NOT EXISTS credentials_salt (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        salt BLOB NOT NULL
+    )",
+];
+
+const SELECT_PROFILE: &str = "
+    SELECT id, name, source_type, source, username, password, host, port, db_name, path
+    FROM connection_profiles
+";
+
+fn profile_from_row(row: &Row) -> rusqlite::Result<ConnectionProfile> {
+    Ok(ConnectionProfile {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        config: ConfigRaw {
+            source_type: row.get(2)?,
+            source: row.get(3)?,
+            username: row.get(4)?,
+            password: row.get(5)?,
+            host: row.get(6)?,
+            port: row.get(7)?,
+            db_name: row.get(8)?,
+            path: row.get(9)?,
+        },
+    })
+}
+
+fn profile_err(err: rusqlite::Error) -> AppError {
+    match err {
+        rusqlite::Error::SqliteFailure(e, _) if e.code == rusqlite::ErrorCode::ConstraintViolation => {
+            AppError::HttpError(
+                StatusCode::CONFLICT,
+                "A connection profile with this name already exists".to_string(),
+            )
+        }
+        err => AppError::PersistentStorageError(err.to_string()),
+    }
+}
+
+fn encrypt_password(
+    password: &Option<String>,
+    credentials: Option<&Credentials>,
+) -> Result<Option<String>, AppError> {
+    match password {
+        Some(password) => Ok(Some(credentials.ok_or_else(key_not_set)?.encrypt(password)?)),
+        None => Ok(None),
+    }
+}
+
+fn profile_not_found() -> AppError {
+    AppError::HttpError(StatusCode::NOT_FOUND, "Connection profile not found".to_string())
+}
+
+/// The [`TableConfig`] fields stored as JSON.
+struct TableConfigJson {
+    exclude_columns: String,
+    created_column: String,
+    updated_column: String,
+    special_columns: String,
+    events: String,
+}
+
+impl TableConfigJson {
+    fn new(tc: &TableConfig) -> Result<Self, AppError> {
+        Ok(TableConfigJson {
+            exclude_columns: to_json(&tc.exclude_columns)?,
+            created_column: to_json(&tc.created_column)?,
+            updated_column: to_json(&tc.updated_column)?,
+            special_columns: to_json(&tc.special_columns)?,
+            events: to_json(&tc.events)?,
+        })
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, AppError> {
+    serde_json::to_string(value)
+        .map_err(|err| AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
+}
+
+/// Read a column holding JSON. Rows saved before the column was added hold `NULL`.
+fn from_json<T: DeserializeOwned + Default>(row: &Row, index: usize, name: &str) -> rusqlite::Result<T> {
+    let json: Option<String> = row.get(index)?;
+
+    match json {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|_| rusqlite::Error::InvalidColumnName(name.to_string())),
+        None => Ok(T::default()),
+    }
+}
 
 #[derive(Clone)]
 pub struct LocalDB(pub Pool<SqliteConnectionManager>);
 
 impl LocalDB {
+    /// Opens the SQLite file at `path`, creating it and its parent directories if needed.
+    pub fn open(path: &str) -> Result<Self, AppError> {
+        if let Some(dir) = Path::new(path).parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(|err| AppError::InitError(err.to_string()))?;
+        }
+
+        let manager = SqliteConnectionManager::file(path)
+            .with_init(|conn| conn.execute_batch("PRAGMA busy_timeout = 5000;"));
+        let pool = Pool::new(manager).map_err(|err| AppError::InitError(err.to_string()))?;
+
+        Ok(LocalDB(pool))
+    }
+
     fn pool(&self) -> Result<PooledConnection<SqliteConnectionManager>, AppError> {
         self.0
             .get()
             .map_err(|err| AppError::PersistentStorageError(err.to_string()))
     }
 
+    /// Brings the schema up to date by running the [`MIGRATIONS`] the file hasn't seen yet.
+    /// Each migration runs in its own transaction. Returns the number of migrations applied.
     pub fn setup(&self) -> Result<usize, AppError> {
-        let pool = self.pool()?;
+        let storage_err = |err: rusqlite::Error| AppError::PersistentStorageError(err.to_string());
 
-        pool.execute(
-            "CREATE TABLE IF NOT EXISTS table_configs (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                name TEXT NOT NULL,
-                conn_id TEXT NOT NULL,
-                label TEXT,
-                pk_column TEXT,
-                ipp INTEGER,
-                exclude_columns TEXT
-            )",
-            params![],
-        )
-        .map_err(|err| AppError::PersistentStorageError(err.to_string()))
+        let mut conn = self.pool()?;
+        let version: usize = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(storage_err)?;
+
+        if version > MIGRATIONS.len() {
+            return Err(AppError::InitError(format!(
+                "local database is at schema version {version}, which is newer than this build supports ({})",
+                MIGRATIONS.len()
+            )));
+        }
+
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let tx = conn.transaction().map_err(storage_err)?;
+            tx.execute_batch(migration).map_err(storage_err)?;
+            tx.pragma_update(None, "user_version", index + 1)
+                .map_err(storage_err)?;
+            tx.commit().map_err(storage_err)?;
+        }
+
+        Ok(MIGRATIONS.len() - version)
     }
 
     pub fn create_table_config(&self, conn_id: &str, tc: TableConfig) -> Result<usize, AppError> {
-        match self.pool() {
-            Ok(pool) => {
-                let exclude_columns =
-                    serde_json::to_string(&tc.exclude_columns).map_err(|err| {
-                        AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
-                    })?;
-
-                let exec = pool.execute(
-                    "
-                    INSERT INTO table_configs (conn_id, label, pk_column, name, ipp, exclude_columns)
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6)
-                ",
-                    params![conn_id, tc.label, tc.pk_column, tc.name, tc.items_per_page, exclude_columns],
-                );
-
-                exec.map_err(|err| {
-                    AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
-                })
-            }
-            Err(err) => Err(err.into()),
-        }
+        let pool = self.pool()?;
+        let json = TableConfigJson::new(&tc)?;
+
+        // Connections reopened from a saved profile keep their configs.
+        let exec = pool.execute(
+            "
+            INSERT INTO table_configs (
+                conn_id, label, pk_column, name, ipp, exclude_columns,
+                created_column, updated_column, special_columns, events
+            )
+            SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10
+            WHERE NOT EXISTS (SELECT 1 FROM table_configs WHERE conn_id = ?1 AND name = ?4)
+        ",
+            params![
+                conn_id,
+                tc.label,
+                tc.pk_column,
+                tc.name,
+                tc.items_per_page,
+                json.exclude_columns,
+                json.created_column,
+                json.updated_column,
+                json.special_columns,
+                json.events
+            ],
+        );
+
+        exec.map_err(|err| AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
     }
 
     pub fn update_table_config(
@@ -66,72 +250,538 @@ impl LocalDB {
         conn_id: &str,
         tc: TableConfig,
     ) -> Result<usize, AppError> {
-        match self.pool() {
-            Ok(pool) => {
-                let exclude_columns =
-                    serde_json::to_string(&tc.exclude_columns).map_err(|err| {
-                        AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
-                    })?;
-
-                let exec = pool.execute(
-                    "UPDATE table_configs SET name = ?, label = ?, pk_column = ?, ipp = ?, exclude_columns = ? WHERE name = ? AND conn_id = ?", 
-                    params![tc.name, tc.label, tc.pk_column, tc.items_per_page, exclude_columns, name, conn_id]
-                );
-
-                exec.map_err(|err| {
-                    AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
-                })
-            }
-            Err(err) => Err(err.into()),
-        }
+        let pool = self.pool()?;
+        let json = TableConfigJson::new(&tc)?;
+
+        let exec = pool.execute(
+            "
+            UPDATE table_configs
+            SET name = ?1, label = ?2, pk_column = ?3, ipp = ?4, exclude_columns = ?5,
+                created_column = ?6, updated_column = ?7, special_columns = ?8, events = ?9
+            WHERE name = ?10 AND conn_id = ?11
+        ",
+            params![
+                tc.name,
+                tc.label,
+                tc.pk_column,
+                tc.items_per_page,
+                json.exclude_columns,
+                json.created_column,
+                json.updated_column,
+                json.special_columns,
+                json.events,
+                name,
+                conn_id
+            ],
+        );
+
+        exec.map_err(|err| AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
     }
 
     pub fn get_table_config(&self, id: &str, conn_id: &str) -> Result<TableConfig, AppError> {
-        match self.pool() {
-            Ok(pool) => {
-                let tc = pool.query_row(
-                    "SELECT name, label, pk_column, ipp, exclude_columns FROM table_configs WHERE (name = ?1 OR label = ?1) AND conn_id = ?2 LIMIT 1",
-                    params![id, conn_id],
-                    |row| {
-                        let excs: String = row.get(4)?;
-                        let exclude_columns: Option<Vec<String>> = serde_json::from_str(&excs).map_err(|_| rusqlite::Error::InvalidColumnName("exclude_columns".to_string()))?;
-                        
-                        Ok(TableConfig {
-                            name: row.get(0)?,
-                            label: row.get(1)?,
-                            pk_column: row.get(2)?,
-                            items_per_page: row.get(3)?,
-                            exclude_columns,
-                            ..Default::default()
-                        })
-                    },
-                );
-
-                tc.map_err(|err| {
-                    AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+        self.find_table_config(id, conn_id)?.ok_or_else(|| {
+            AppError::HttpError(StatusCode::NOT_FOUND, "Table configuration not found".to_string())
+        })
+    }
+
+    /// The config of table `id` (its name or label), if one was saved.
+    pub fn find_table_config(&self, id: &str, conn_id: &str) -> Result<Option<TableConfig>, AppError> {
+        let pool = self.pool()?;
+
+        let tc = pool.query_row(
+            "
+            SELECT name, label, pk_column, ipp, exclude_columns,
+                created_column, updated_column, special_columns, events
+            FROM table_configs
+            WHERE (name = ?1 OR label = ?1) AND conn_id = ?2
+            LIMIT 1
+        ",
+            params![id, conn_id],
+            |row| {
+                Ok(TableConfig {
+                    name: row.get(0)?,
+                    label: row.get(1)?,
+                    pk_column: row.get(2)?,
+                    items_per_page: row.get(3)?,
+                    exclude_columns: from_json(row, 4, "exclude_columns")?,
+                    created_column: from_json(row, 5, "created_column")?,
+                    updated_column: from_json(row, 6, "updated_column")?,
+                    special_columns: from_json(row, 7, "special_columns")?,
+                    events: from_json(row, 8, "events")?,
                 })
+            },
+        )
+        .optional();
+
+        tc.map_err(|err| AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
+    }
+
+    /// Saves a new connection profile for `user_id` and returns it with its id.
+    pub fn create_profile(
+        &self,
+        user_id: &str,
+        profile: ConnectionProfile,
+        credentials: Option<&Credentials>,
+    ) -> Result<ConnectionProfile, AppError> {
+        let pool = self.pool()?;
+        let id = uuid::Uuid::new_v4().to_string();
+        let ConfigRaw {
+            source_type,
+            source,
+            username,
+            password,
+            host,
+            port,
+            db_name,
+            path,
+        } = &profile.config;
+        let password = encrypt_password(password, credentials)?;
+
+        pool.execute(
+            "
+            INSERT INTO connection_profiles
+                (id, user_id, name, source_type, source, username, password, host, port, db_name, path)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+        ",
+            params![id, user_id, profile.name, source_type, source, username, password, host, port, db_name, path],
+        )
+        .map_err(profile_err)?;
+
+        Ok(ConnectionProfile { id, ..profile })
+    }
+
+    /// Connection profiles saved by `user_id`, sorted by name. Passwords are left out.
+    pub fn list_profiles(&self, user_id: &str) -> Result<Vec<ConnectionProfile>, AppError> {
+        let pool = self.pool()?;
+        let mut stmt = pool
+            .prepare(&format!("{SELECT_PROFILE} WHERE user_id = ?1 ORDER BY name"))
+            .map_err(profile_err)?;
+
+        let profiles: Vec<ConnectionProfile> = stmt
+            .query_map(params![user_id], profile_from_row)
+            .and_then(|rows| rows.collect())
+            .map_err(profile_err)?;
+
+        let profiles = profiles
+            .into_iter()
+            .map(|mut profile| {
+                profile.config.password = None;
+                profile
+            })
+            .collect();
+
+        Ok(profiles)
+    }
+
+    /// The connection profile `id` of `user_id`, if it exists, without its password.
+    pub fn find_profile(&self, id: &str, user_id: &str) -> Result<Option<ConnectionProfile>, AppError> {
+        let pool = self.pool()?;
+
+        let profile = pool
+            .query_row(
+                &format!("{SELECT_PROFILE} WHERE id = ?1 AND user_id = ?2"),
+                params![id, user_id],
+                profile_from_row,
+            )
+            .optional()
+            .map_err(profile_err)?;
+
+        Ok(profile.map(|mut profile| {
+            profile.config.password = None;
+            profile
+        }))
+    }
+
+    /// The connection profile `id` of `user_id`, with its password decrypted.
+    pub fn get_profile(
+        &self,
+        id: &str,
+        user_id: &str,
+        credentials: Option<&Credentials>,
+    ) -> Result<ConnectionProfile, AppError> {
+        let pool = self.pool()?;
+
+        let mut profile = pool
+            .query_row(
+                &format!("{SELECT_PROFILE} WHERE id = ?1 AND user_id = ?2"),
+                params![id, user_id],
+                profile_from_row,
+            )
+            .optional()
+            .map_err(profile_err)?
+            .ok_or_else(profile_not_found)?;
+
+        if let Some(password) = &profile.config.password {
+            let plain = credentials.ok_or_else(key_not_set)?.decrypt(password)?;
+            profile.config.password = Some(plain);
+        }
+
+        Ok(profile)
+    }
+
+    /// The salt [`Credentials`] keys are derived with. It's generated the first time it's read.
+    pub fn credentials_salt(&self) -> Result<Vec<u8>, AppError> {
+        let storage_err = |err: rusqlite::Error| AppError::PersistentStorageError(err.to_string());
+        let pool = self.pool()?;
+
+        let mut salt = [0u8; SALT_SIZE];
+        OsRng.fill_bytes(&mut salt);
+        pool.execute(
+            "INSERT OR IGNORE INTO credentials_salt (id, salt) VALUES (1, ?1)",
+            params![salt.to_vec()],
+        )
+        .map_err(storage_err)?;
+
+        pool.query_row("SELECT salt FROM credentials_salt WHERE id = 1", [], |row| row.get(0))
+            .map_err(storage_err)
+    }
+
+    /// Re-encrypts every saved password that isn't encrypted with the current master key, and
+    /// encrypts any saved before passwords were encrypted. Returns the number updated.
+    pub fn rotate_credentials(&self, credentials: &Credentials) -> Result<usize, AppError> {
+        let mut pool = self.pool()?;
+        let tx = pool.transaction().map_err(profile_err)?;
+
+        let saved: Vec<(String, String)> = tx
+            .prepare("SELECT id, password FROM connection_profiles WHERE password IS NOT NULL")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                    .collect()
+            })
+            .map_err(profile_err)?;
+
+        let mut rotated = 0;
+        for (id, password) in saved {
+            if credentials.is_current(&password) {
+                continue;
             }
-            Err(err) => Err(err.into()),
+
+            let plain = if Credentials::is_plain(&password) {
+                password
+            } else {
+                credentials.decrypt(&password)?
+            };
+            let password = credentials.encrypt(&plain)?;
+            tx.execute(
+                "UPDATE connection_profiles SET password = ?1 WHERE id = ?2",
+                params![password, id],
+            )
+            .map_err(profile_err)?;
+            rotated += 1;
+        }
+
+        tx.commit().map_err(profile_err)?;
+        Ok(rotated)
+    }
+
+    /// Replaces the name and config of profile `id`. The saved password is kept when
+    /// `profile.config.password` is `None`, since it's never sent back to clients.
+    pub fn update_profile(
+        &self,
+        id: &str,
+        user_id: &str,
+        profile: ConnectionProfile,
+        credentials: Option<&Credentials>,
+    ) -> Result<ConnectionProfile, AppError> {
+        let pool = self.pool()?;
+        let ConfigRaw {
+            source_type,
+            source,
+            username,
+            password,
+            host,
+            port,
+            db_name,
+            path,
+        } = &profile.config;
+        let password = encrypt_password(password, credentials)?;
+
+        let updated = pool
+            .execute(
+                "
+                UPDATE connection_profiles
+                SET name = ?3, source_type = ?4, source = ?5, username = ?6,
+                    password = COALESCE(?7, password), host = ?8, port = ?9, db_name = ?10, path = ?11
+                WHERE id = ?1 AND user_id = ?2
+            ",
+                params![id, user_id, profile.name, source_type, source, username, password, host, port, db_name, path],
+            )
+            .map_err(profile_err)?;
+
+        if updated == 0 {
+            return Err(profile_not_found());
+        }
+
+        Ok(ConnectionProfile {
+            id: id.to_string(),
+            ..profile
+        })
+    }
+
+    /// Deletes profile `id` along with the table configs of its connections.
+    pub fn delete_profile(&self, id: &str, user_id: &str) -> Result<(), AppError> {
+        let mut pool = self.pool()?;
+        let tx = pool.transaction().map_err(profile_err)?;
+
+        let deleted = tx
+            .execute(
+                "DELETE FROM connection_profiles WHERE id = ?1 AND user_id = ?2",
+                params![id, user_id],
+            )
+            .map_err(profile_err)?;
+
+        if deleted == 0 {
+            return Err(profile_not_found());
         }
+
+        tx.execute("DELETE FROM table_configs WHERE conn_id = ?1", params![id])
+            .map_err(profile_err)?;
+        tx.commit().map_err(profile_err)
+    }
+
+    pub fn log_delivery(&self, conn_id: &str, delivery: &WebhookDelivery) -> Result<(), AppError> {
+        self.pool()?
+            .execute(
+                "INSERT INTO webhook_deliveries
+                (conn_id, table_name, event, trigger_time, method, url, attempts, status, error, delivered)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    conn_id,
+                    delivery.table_name,
+                    delivery.trigger,
+                    delivery.trigger_time,
+                    delivery.method,
+                    delivery.url,
+                    delivery.attempts,
+                    delivery.status,
+                    delivery.error,
+                    delivery.delivered,
+                ],
+            )
+            .map_err(|err| AppError::PersistentStorageError(err.to_string()))?;
+
+        Ok(())
+    }
+
+    /// The latest `limit` webhook deliveries of a table, newest first.
+    pub fn list_deliveries(
+        &self,
+        conn_id: &str,
+        table_name: &str,
+        limit: usize,
+    ) -> Result<Vec<WebhookDelivery>, AppError> {
+        let conn = self.pool()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT table_name, event, trigger_time, method, url, attempts, status, error, delivered, created_at
+                FROM webhook_deliveries
+                WHERE conn_id = ?1 AND table_name = ?2
+                ORDER BY id DESC
+                LIMIT ?3",
+            )
+            .map_err(|err| AppError::PersistentStorageError(err.to_string()))?;
+
+        let rows = stmt
+            .query_map(params![conn_id, table_name, limit], |row| {
+                Ok(WebhookDelivery {
+                    table_name: row.get(0)?,
+                    trigger: row.get(1)?,
+                    trigger_time: row.get(2)?,
+                    method: row.get(3)?,
+                    url: row.get(4)?,
+                    attempts: row.get(5)?,
+                    status: row.get(6)?,
+                    error: row.get(7)?,
+                    delivered: row.get(8)?,
+                    created_at: row.get(9)?,
+                })
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|err| AppError::PersistentStorageError(err.to_string()))?;
+
+        Ok(rows)
     }
 }
 
 #[derive(Clone)]
 pub(crate) struct AppState {
-    pub instance: Arc<Mutex<Basable>>,
+    pub instance: Arc<RwLock<Basable>>,
     pub local_db: LocalDB,
+    pub imports: ImportJobs,
+    /// Encrypts saved passwords. `None` if `BASABLE_CREDENTIALS_KEY` isn't set.
+    pub credentials: Option<Arc<Credentials>>,
+}
+
+/// Path of the [`LocalDB`] file, set with `BASABLE_LOCAL_DB`.
+pub(crate) fn local_db_path() -> String {
+    get_env("BASABLE_LOCAL_DB").unwrap_or_else(|_| DEFAULT_LOCAL_DB.to_string())
 }
 
 impl AppState {
     pub fn create() -> Result<Self, AppError> {
-        let manager = SqliteConnectionManager::memory();
-        let pool = r2d2::Pool::new(manager).map_err(|err| AppError::InitError(err.to_string()))?;
+        let path = local_db_path();
+        std::fs::create_dir_all(data_dir()).map_err(|err| AppError::InitError(err.to_string()))?;
+        if uploads_dir().exists() {
+            std::fs::remove_dir_all(uploads_dir())
+                .map_err(|err| AppError::InitError(err.to_string()))?;
+        }
 
         let s = Self {
             instance: Default::default(),
-            local_db: LocalDB(pool),
+            local_db: LocalDB::open(&path)?,
+            imports: Default::default(),
+            credentials: None,
         };
 
         Ok(s)
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use base::config::{ConfigRaw, ConnectionProfile};
+    use common::data::table::TableConfig;
+
+    use crate::credentials::Credentials;
+
+    use super::{LocalDB, MIGRATIONS};
+
+    pub(crate) fn temp_db() -> (LocalDB, String) {
+        let path = std::env::temp_dir().join(format!("basable-{}.db", uuid::Uuid::new_v4()));
+        let path = path.to_string_lossy().to_string();
+
+        (LocalDB::open(&path).unwrap(), path)
+    }
+
+    #[test]
+    fn test_setup_upgrades_in_place() {
+        let (db, path) = temp_db();
+
+        // A file written before migrations existed: the table is there, `user_version` is 0.
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(MIGRATIONS[0]).unwrap();
+        conn.execute(
+            "INSERT INTO table_configs (name, conn_id, label, ipp) VALUES ('users', 'c1', 'users', 100)",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+
+        assert_eq!(db.setup().unwrap(), MIGRATIONS.len());
+        assert_eq!(db.setup().unwrap(), 0);
+
+        let count: usize = db
+            .pool()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM table_configs", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+
+        // Columns added by later migrations are empty for the old row.
+        let tc = db.get_table_config("users", "c1").unwrap();
+        assert!(tc.created_column.is_none() && tc.events.is_none());
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_table_config_round_trip() {
+        let (db, path) = temp_db();
+        db.setup().unwrap();
+
+        let tc: TableConfig = serde_json::from_value(serde_json::json!({
+            "label": "Users",
+            "name": "users",
+            "pk_column": "id",
+            "items_per_page": 50,
+            "created_column": { "name": "created_at", "pattern": "%Y-%m-%d" },
+            "updated_column": null,
+            "special_columns": [{ "name": "avatar", "special_type": "Image", "path": "/media" }],
+            "events": [{
+                "trigger": "Create",
+                "trigger_time": "After",
+                "method": "Post",
+                "url": "http://localhost/hook",
+                "on_error": "Proceed"
+            }],
+            "exclude_columns": ["password"]
+        }))
+        .unwrap();
+
+        db.create_table_config("c1", tc.clone()).unwrap();
+        let saved = db.get_table_config("users", "c1").unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&saved).unwrap(),
+            serde_json::to_value(&tc).unwrap()
+        );
+        assert_eq!(saved.columns(), vec!["id", "created_at", "avatar", "password"]);
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_profiles() {
+        let (db, path) = temp_db();
+        db.setup().unwrap();
+
+        let salt = db.credentials_salt().unwrap();
+        assert_eq!(db.credentials_salt().unwrap(), salt);
+        let credentials = Credentials::new("test-key", &[], &salt).unwrap();
+        let key = Some(&credentials);
+
+        let profile = ConnectionProfile {
+            id: String::new(),
+            name: "local".to_string(),
+            config: ConfigRaw {
+                password: Some("secret".to_string()),
+                ..ConfigRaw::default()
+            },
+        };
+
+        assert!(db.create_profile("u1", profile.clone(), None).is_err());
+        let saved = db.create_profile("u1", profile.clone(), key).unwrap();
+        assert!(db.create_profile("u1", profile.clone(), key).is_err());
+        assert!(db.get_profile(&saved.id, "u2", key).is_err());
+
+        // The password isn't sent with updates, so the saved one is kept.
+        let mut update = saved.clone();
+        update.name = "renamed".to_string();
+        update.config.password = None;
+        db.update_profile(&saved.id, "u1", update, key).unwrap();
+
+        let reloaded = db.get_profile(&saved.id, "u1", key).unwrap();
+        assert_eq!(reloaded.name, "renamed");
+        assert_eq!(reloaded.config.password.as_deref(), Some("secret"));
+
+        let json = serde_json::to_string(&db.list_profiles("u1").unwrap()).unwrap();
+        assert!(!json.contains("secret"));
+
+        let stored: String = db
+            .pool()
+            .unwrap()
+            .query_row("SELECT password FROM connection_profiles", [], |row| row.get(0))
+            .unwrap();
+        assert!(!stored.contains("secret"));
+
+        let rotated = Credentials::new("next-key", &["test-key"], &salt).unwrap();
+        assert_eq!(db.rotate_credentials(&rotated).unwrap(), 1);
+        assert_eq!(db.rotate_credentials(&rotated).unwrap(), 0);
+
+        // Passwords saved before they were encrypted are encrypted once, then can't be read
+        // as they are.
+        db.pool()
+            .unwrap()
+            .execute("UPDATE connection_profiles SET password = 'plain'", [])
+            .unwrap();
+        assert!(db.get_profile(&saved.id, "u1", Some(&rotated)).is_err());
+        assert_eq!(db.rotate_credentials(&rotated).unwrap(), 1);
+        let migrated = db.get_profile(&saved.id, "u1", Some(&rotated)).unwrap();
+        assert_eq!(migrated.config.password.as_deref(), Some("plain"));
+
+        db.delete_profile(&saved.id, "u1").unwrap();
+        assert!(db.list_profiles("u1").unwrap().is_empty());
+
+        std::fs::remove_file(&path).ok();
+    }
+}
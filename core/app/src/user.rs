@@ -38,7 +38,7 @@ pub(crate) fn create_jwt(user: User) -> Result<JwtSession, AppError> {
 
     // Token expiration is two hours
     let exp = Utc::now()
-        .checked_add_signed(chrono::Duration::seconds(exp_time.clone()))
+        .checked_add_signed(chrono::Duration::seconds(exp_time))
         .expect("Invalid timestamp")
         .timestamp() as usize;
 
@@ -1,5 +1,203 @@
-use std::env::{self, VarError};
+use std::{
+    env::{self, VarError},
+    io::{self, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use axum::{body::Body, http::StatusCode};
+use common::error::AppError;
+use futures_util::StreamExt;
+use tokio::sync::mpsc::Sender;
+
+/// Size of the chunks [`stream_reader`] and [`stream_writer`] send.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Default [`data_dir`], used when `BASABLE_DATA_DIR` isn't set.
+const DEFAULT_DATA_DIR: &str = "data";
 
 pub(crate) fn get_env(key: &str) -> Result<String, VarError> {
     env::var(key)
-}
\ No newline at end of file
+}
+
+/// Directory the files of file sources, like SQLite databases and uploaded data files, must be
+/// in. Set with `BASABLE_DATA_DIR`.
+pub(crate) fn data_dir() -> PathBuf {
+    get_env("BASABLE_DATA_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(DEFAULT_DATA_DIR))
+}
+
+/// Directory the local media of special columns must be in. Set with `BASABLE_MEDIA_DIR`,
+/// defaults to `media` in the [`data_dir`].
+pub(crate) fn media_dir() -> PathBuf {
+    get_env("BASABLE_MEDIA_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| data_dir().join("media"))
+}
+
+/// Directory of [`data_dir`] uploaded data files are saved in. Uploads only last as long as
+/// their connection, so it's emptied on startup.
+pub(crate) fn uploads_dir() -> PathBuf {
+    data_dir().join("uploads")
+}
+
+/// Directory of [`data_dir`] the uploads of saved profiles are copied to, so they outlive
+/// their connection and restarts.
+pub(crate) fn profile_files_dir() -> PathBuf {
+    data_dir().join("profiles")
+}
+
+/// Resolves `path`, relative to `root` unless it's absolute, following symlinks. Fails with
+/// `403 Forbidden` if the file isn't inside `root`, and `404 Not Found` if it doesn't exist.
+pub(crate) fn resolve_under(root: &Path, path: &Path) -> Result<PathBuf, AppError> {
+    let not_found = || {
+        AppError::HttpError(
+            StatusCode::NOT_FOUND,
+            format!("`{}` doesn't exist", path.display()),
+        )
+    };
+
+    let root = root.canonicalize().map_err(|_| not_found())?;
+    let file = root.join(path).canonicalize().map_err(|_| not_found())?;
+    if !file.starts_with(&root) {
+        return Err(AppError::HttpError(
+            StatusCode::FORBIDDEN,
+            format!("`{}` is outside of the allowed directory", path.display()),
+        ));
+    }
+
+    Ok(file)
+}
+
+/// Runs `f` on tokio's blocking thread pool. Database drivers and local storage are
+/// synchronous, so handlers use this to keep slow queries from stalling the async workers.
+pub(crate) async fn blocking<T, F>(f: F) -> Result<T, AppError>
+where
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|err| AppError::ServerError(err.to_string()))?
+}
+
+/// Streams a synchronous reader as a response body. The reader is drained on the blocking
+/// thread pool, and stops as soon as the client goes away.
+pub(crate) fn stream_reader<R>(mut reader: R) -> Body
+where
+    R: Read + Send + 'static,
+{
+    let (tx, rx) = tokio::sync::mpsc::channel::<io::Result<Vec<u8>>>(4);
+
+    tokio::task::spawn_blocking(move || loop {
+        let mut chunk = vec![0; CHUNK_SIZE];
+        let chunk = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => {
+                chunk.truncate(n);
+                Ok(chunk)
+            }
+            Err(err) => Err(err),
+        };
+
+        let failed = chunk.is_err();
+        if tx.blocking_send(chunk).is_err() || failed {
+            break;
+        }
+    });
+
+    Body::from_stream(futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    }))
+}
+
+/// Sends what's written to it as body chunks of a [`stream_writer`].
+struct ChunkWriter(Sender<Result<Vec<u8>, AppError>>);
+
+impl Write for ChunkWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .blocking_send(Ok(buf.to_vec()))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "client went away"))?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Streams what `f` writes as a response body. `f` runs on the blocking thread pool, and its
+/// writes fail as soon as the client goes away.
+///
+/// This resolves once `f` wrote its first chunk, or returned: an error it returns before
+/// writing anything is returned here, to be sent as an error response. Later errors can only
+/// abort the download.
+pub(crate) async fn stream_writer<F>(f: F) -> Result<Body, AppError>
+where
+    F: FnOnce(&mut (dyn Write + Send)) -> Result<(), AppError> + Send + 'static,
+{
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<Result<Vec<u8>, AppError>>(4);
+
+    tokio::task::spawn_blocking(move || {
+        let mut out = BufWriter::with_capacity(CHUNK_SIZE, ChunkWriter(tx));
+        let written = f(&mut out).and_then(|_| {
+            out.flush()
+                .map_err(|err| AppError::ServerError(err.to_string()))
+        });
+
+        if let Err(err) = written {
+            // What's still buffered is dropped, so that if nothing was sent yet, the client
+            // gets the error rather than part of the body.
+            let (ChunkWriter(tx), _) = out.into_parts();
+            tx.blocking_send(Err(err)).ok();
+        }
+    });
+
+    let first = match rx.recv().await {
+        Some(Ok(chunk)) => chunk,
+        Some(Err(err)) => return Err(err),
+        None => return Ok(Body::empty()),
+    };
+
+    let rest = futures_util::stream::unfold(rx, |mut rx| async move {
+        let chunk = rx.recv().await?;
+        Some((chunk.map_err(|err| io::Error::other(err.to_string())), rx))
+    });
+
+    Ok(Body::from_stream(
+        futures_util::stream::once(async { Ok(first) }).chain(rest),
+    ))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::path::Path;
+
+    use axum::http::StatusCode;
+    use common::error::AppError;
+
+    use super::resolve_under;
+
+    #[test]
+    fn test_resolve_under() {
+        let root = std::env::temp_dir().join(format!("basable-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("sub/data.csv"), "id\n1\n").unwrap();
+
+        let file = root.canonicalize().unwrap().join("sub/data.csv");
+        assert_eq!(resolve_under(&root, Path::new("sub/data.csv")).unwrap(), file);
+        assert_eq!(resolve_under(&root, &file).unwrap(), file);
+
+        let status = |path: &str| match resolve_under(&root, Path::new(path)) {
+            Err(AppError::HttpError(status, _)) => status,
+            other => panic!("unexpected {other:?}"),
+        };
+        assert_eq!(status("sub/../../"), StatusCode::FORBIDDEN);
+        assert_eq!(status("/"), StatusCode::FORBIDDEN);
+        assert_eq!(status("missing.csv"), StatusCode::NOT_FOUND);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,402 @@
+use std::{
+    sync::{
+        mpsc::{self, SyncSender},
+        Arc, Mutex, OnceLock,
+    },
+    thread,
+    time::Duration,
+};
+
+use axum::http::StatusCode;
+use common::{
+    data::table::{NotifyEvent, NotifyEventMethod, NotifyTrigger, NotifyTriggerTime, OnNotifyError},
+    error::AppError,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::{remote, state::LocalDB};
+
+/// Number of times a webhook is tried before its delivery is given up.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Wait before the first retry. It doubles on each retry.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Threads sending the webhooks that requests don't wait on.
+const WORKERS: usize = 4;
+
+/// Webhooks waiting for a worker. Past this, new ones are dropped and logged as failed.
+const QUEUE_SIZE: usize = 1000;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Outcome of a webhook delivery, as kept in the [`LocalDB`] delivery log.
+#[derive(Serialize)]
+pub(crate) struct WebhookDelivery {
+    pub table_name: String,
+    pub trigger: String,
+    pub trigger_time: String,
+    pub method: String,
+    pub url: String,
+    pub attempts: u32,
+
+    /// HTTP status of the last attempt, if the server responded.
+    pub status: Option<u16>,
+
+    /// Why the last attempt failed.
+    pub error: Option<String>,
+    pub delivered: bool,
+    pub created_at: String,
+}
+
+impl WebhookDelivery {
+    /// A delivery of `event` that wasn't attempted yet.
+    fn new(table_name: &str, event: &NotifyEvent) -> Self {
+        let method = match event.method() {
+            NotifyEventMethod::Get => "GET",
+            NotifyEventMethod::Post => "POST",
+            NotifyEventMethod::Delete => "DELETE",
+            NotifyEventMethod::Put => "PUT",
+            NotifyEventMethod::Patch => "PATCH",
+        };
+
+        WebhookDelivery {
+            table_name: table_name.to_string(),
+            trigger: format!("{:?}", event.trigger()),
+            trigger_time: format!("{:?}", event.trigger_time()),
+            method: method.to_string(),
+            url: event.url().to_string(),
+            attempts: 0,
+            status: None,
+            error: None,
+            delivered: false,
+            created_at: String::new(),
+        }
+    }
+}
+
+/// Sends the [`NotifyEvent`]s configured for a table around its data changes.
+///
+/// Webhooks set to [`OnNotifyError::Fail`] are sent while the request waits on them. Others
+/// don't change how the request ends, so they're queued for a few worker threads.
+pub(crate) struct Notifier<'a> {
+    storage: &'a LocalDB,
+    conn_id: String,
+    table_name: String,
+    events: Vec<NotifyEvent>,
+}
+
+/// The value returned by an operation run by [`Notifier::around`].
+pub(crate) struct Notified<T> {
+    pub value: T,
+
+    /// Failed `After` webhooks set to [`OnNotifyError::Fail`]. The change is already made by
+    /// then, so the request still succeeds, with these as warnings.
+    pub warnings: Vec<String>,
+}
+
+impl<'a> Notifier<'a> {
+    /// Loads the events saved in the config of `table_name`.
+    pub fn new(storage: &'a LocalDB, conn_id: &str, table_name: &str) -> Result<Self, AppError> {
+        let events = storage
+            .find_table_config(table_name, conn_id)?
+            .and_then(|config| config.events)
+            .unwrap_or_default();
+
+        Ok(Notifier {
+            storage,
+            conn_id: conn_id.to_string(),
+            table_name: table_name.to_string(),
+            events,
+        })
+    }
+
+    /// Runs `operation` between the `Before` and `After` webhooks of `trigger`. `rows` are the
+    /// rows affected by the operation, sent as the webhook payload.
+    ///
+    /// A failing `Before` webhook set to [`OnNotifyError::Fail`] stops the operation from
+    /// running. A failing `After` one can't undo it, so it's returned in
+    /// [`Notified::warnings`].
+    pub fn around<T>(
+        &self,
+        trigger: NotifyTrigger,
+        rows: Vec<Value>,
+        operation: impl FnOnce() -> Result<T, AppError>,
+    ) -> Result<Notified<T>, AppError> {
+        self.around_all(vec![(trigger, rows)], operation)
+    }
+
+    /// Like [`Notifier::around`], with the affected rows read by `rows`: before the operation
+    /// for the `Before` webhooks, and once it's done for the `After` ones. They're only read
+    /// when there are webhooks to send them to.
+    pub fn around_read<T>(
+        &self,
+        trigger: NotifyTrigger,
+        rows: impl Fn(&NotifyTriggerTime) -> Result<Vec<Value>, AppError>,
+        operation: impl FnOnce() -> Result<T, AppError>,
+    ) -> Result<Notified<T>, AppError> {
+        let (before, after) = (NotifyTriggerTime::Before, NotifyTriggerTime::After);
+        let mut warnings = Vec::new();
+        if self.listens(&trigger, &before) {
+            let rows = rows(&before)?;
+            if let Some(failure) = self.notify(&trigger, before, &rows).pop() {
+                return Err(AppError::HttpError(StatusCode::BAD_GATEWAY, failure));
+            }
+        }
+
+        let value = operation()?;
+        if self.listens(&trigger, &after) {
+            match rows(&after) {
+                Ok(rows) => warnings.extend(self.notify(&trigger, after, &rows)),
+                Err(err) => warnings.push(format!(
+                    "After {trigger:?} webhooks weren't sent, reading their rows failed: {err}"
+                )),
+            }
+        }
+
+        Ok(Notified { value, warnings })
+    }
+
+    /// Whether there are webhooks for `trigger`, at any time.
+    pub fn listens_to(&self, trigger: &NotifyTrigger) -> bool {
+        self.events.iter().any(|e| e.trigger() == trigger)
+    }
+
+    fn listens(&self, trigger: &NotifyTrigger, time: &NotifyTriggerTime) -> bool {
+        self.events
+            .iter()
+            .any(|e| e.trigger() == trigger && e.trigger_time() == time)
+    }
+
+    /// Like [`Notifier::around`], for an operation making several kinds of changes: every
+    /// `Before` webhook is sent before it runs, and every `After` one once it's done.
+    pub fn around_all<T>(
+        &self,
+        changes: Vec<(NotifyTrigger, Vec<Value>)>,
+        operation: impl FnOnce() -> Result<T, AppError>,
+    ) -> Result<Notified<T>, AppError> {
+        let mut warnings = Vec::new();
+        if self.events.is_empty() {
+            return operation().map(|value| Notified { value, warnings });
+        }
+
+        for (trigger, rows) in &changes {
+            if let Some(failure) = self.notify(trigger, NotifyTriggerTime::Before, rows).pop() {
+                return Err(AppError::HttpError(StatusCode::BAD_GATEWAY, failure));
+            }
+        }
+        let value = operation()?;
+        for (trigger, rows) in &changes {
+            warnings.extend(self.notify(trigger, NotifyTriggerTime::After, rows));
+        }
+
+        Ok(Notified { value, warnings })
+    }
+
+    /// Sends the webhooks of `trigger` at `time`, returning why those set to
+    /// [`OnNotifyError::Fail`] failed.
+    fn notify(&self, trigger: &NotifyTrigger, time: NotifyTriggerTime, rows: &[Value]) -> Vec<String> {
+        let payload = json!({
+            "table": self.table_name,
+            "trigger": trigger,
+            "trigger_time": time,
+            "rows": rows,
+        });
+
+        let events = self
+            .events
+            .iter()
+            .filter(|e| e.trigger() == trigger && *e.trigger_time() == time);
+
+        let mut failures = Vec::new();
+        for event in events {
+            if *event.on_error() != OnNotifyError::Fail {
+                let (storage, conn_id) = (self.storage.clone(), self.conn_id.clone());
+                let (table_name, queued, payload) = (self.table_name.clone(), event.clone(), payload.clone());
+                let job: Job = Box::new(move || {
+                    let delivery = deliver(&table_name, &queued, &payload);
+                    log_delivery(&storage, &conn_id, &delivery);
+                });
+
+                if queue().try_send(job).is_err() {
+                    let mut delivery = WebhookDelivery::new(&self.table_name, event);
+                    delivery.error = Some("Too many webhooks waiting to be sent".to_string());
+                    log_delivery(self.storage, &self.conn_id, &delivery);
+                }
+                continue;
+            }
+
+            let delivery = deliver(&self.table_name, event, &payload);
+            log_delivery(self.storage, &self.conn_id, &delivery);
+
+            if !delivery.delivered {
+                failures.push(format!(
+                    "{:?} {:?} webhook to {} failed: {}",
+                    time,
+                    trigger,
+                    event.url(),
+                    delivery.error.unwrap_or_default()
+                ));
+            }
+        }
+
+        failures
+    }
+}
+
+/// The queue of the webhooks requests don't wait on, and its workers, started on first use.
+fn queue() -> &'static SyncSender<Job> {
+    static QUEUE: OnceLock<SyncSender<Job>> = OnceLock::new();
+
+    QUEUE.get_or_init(|| {
+        let (tx, rx) = mpsc::sync_channel::<Job>(QUEUE_SIZE);
+        let rx = Arc::new(Mutex::new(rx));
+        for _ in 0..WORKERS {
+            let rx = rx.clone();
+            thread::spawn(move || loop {
+                // The lock is released once a job is received, so others can wait for the next.
+                let job = match rx.lock() {
+                    Ok(rx) => rx.recv(),
+                    Err(_) => return,
+                };
+                match job {
+                    Ok(job) => job(),
+                    Err(_) => return,
+                }
+            });
+        }
+
+        tx
+    })
+}
+
+/// Saves `delivery` in the delivery log. The webhook was already sent, so a failure to log it
+/// is only reported.
+fn log_delivery(storage: &LocalDB, conn_id: &str, delivery: &WebhookDelivery) {
+    if let Err(err) = storage.log_delivery(conn_id, delivery) {
+        tracing::warn!("failed to log webhook delivery to {}: {err}", delivery.url);
+    }
+}
+
+/// Sends `payload` to the event's url, retrying failed attempts.
+/// Redirects aren't followed and only public addresses are reached, see [`remote::agent`].
+fn deliver(table_name: &str, event: &NotifyEvent, payload: &Value) -> WebhookDelivery {
+    let agent = remote::agent(TIMEOUT);
+    let mut delivery = WebhookDelivery::new(table_name, event);
+
+    let mut delay = RETRY_DELAY;
+    while delivery.attempts < MAX_ATTEMPTS {
+        if delivery.attempts > 0 {
+            thread::sleep(delay);
+            delay *= 2;
+        }
+        delivery.attempts += 1;
+
+        let request = agent.request(&delivery.method, event.url());
+        let response = match event.method() {
+            NotifyEventMethod::Get => request.call(),
+            _ => request.send_json(payload),
+        };
+
+        match response {
+            Ok(resp) if (300..400).contains(&resp.status()) => {
+                delivery.status = Some(resp.status());
+                delivery.error = Some("server redirected to another URL, which isn't followed".to_string());
+            }
+            Ok(resp) => {
+                delivery.status = Some(resp.status());
+                delivery.error = None;
+                delivery.delivered = true;
+                break;
+            }
+            Err(ureq::Error::Status(code, _)) => {
+                delivery.status = Some(code);
+                delivery.error = Some(format!("server responded with {code}"));
+            }
+            Err(err) => {
+                delivery.status = None;
+                delivery.error = Some(err.to_string());
+            }
+        }
+    }
+
+    delivery
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use common::data::table::{NotifyTrigger, TableConfig};
+
+    use crate::state::tests::temp_db;
+
+    use super::Notifier;
+
+    #[test]
+    fn test_failed_delivery() {
+        let (db, path) = temp_db();
+        db.setup().unwrap();
+
+        // Nothing listens on port 1, so every attempt is refused.
+        let tc: TableConfig = serde_json::from_value(serde_json::json!({
+            "label": "users",
+            "name": "users",
+            "pk_column": null,
+            "items_per_page": 100,
+            "created_column": null,
+            "updated_column": null,
+            "special_columns": null,
+            "events": [{
+                "trigger": "Delete",
+                "trigger_time": "Before",
+                "method": "Post",
+                "url": "http://127.0.0.1:1/hook",
+                "on_error": "Fail"
+            }, {
+                "trigger": "Update",
+                "trigger_time": "After",
+                "method": "Post",
+                "url": "http://127.0.0.1:1/hook",
+                "on_error": "Fail"
+            }],
+            "exclude_columns": null
+        }))
+        .unwrap();
+        db.create_table_config("c1", tc).unwrap();
+
+        let notifier = Notifier::new(&db, "c1", "users").unwrap();
+        let mut ran = false;
+        let rows = vec![serde_json::json!({ "id": "1" })];
+
+        assert!(notifier
+            .around(NotifyTrigger::Delete, rows.clone(), || {
+                ran = true;
+                Ok(())
+            })
+            .is_err());
+        assert!(!ran);
+
+        // Other triggers aren't affected.
+        let notified = notifier
+            .around(NotifyTrigger::Create, rows.clone(), || {
+                ran = true;
+                Ok(())
+            })
+            .unwrap();
+        assert!(ran && notified.warnings.is_empty());
+
+        // The change is made by the time an `After` webhook fails, so it's only a warning.
+        let notified = notifier.around(NotifyTrigger::Update, rows, || Ok(1)).unwrap();
+        assert_eq!(notified.value, 1);
+        assert_eq!(notified.warnings.len(), 1);
+
+        let log = db.list_deliveries("c1", "users", 10).unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].attempts, super::MAX_ATTEMPTS);
+        assert!(!log[0].delivered && log[0].error.is_some());
+
+        std::fs::remove_file(&path).ok();
+    }
+}
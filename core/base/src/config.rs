@@ -1,5 +1,5 @@
 use axum::http::StatusCode;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use urlencoding::encode;
 
 use common::error::AppError;
@@ -8,8 +8,9 @@ use common::error::AppError;
 pub enum DatabaseType {
     Mysql,
     Postgres,
-    MarianDB,
-    Mongo
+    MariaDB,
+    Mongo,
+    Sqlite,
 }
 
 impl TryFrom<&str> for DatabaseType {
@@ -19,20 +20,58 @@ impl TryFrom<&str> for DatabaseType {
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value {
             "postgres" => Ok(Self::Postgres),
-            "mariandb" => Ok(Self::MarianDB),
+            "mariadb" => Ok(Self::MariaDB),
             "mysql" => Ok(Self::Mysql),
             "mongo" => Ok(Self::Mongo),
+            "sqlite" => Ok(Self::Sqlite),
             &_ => Err(AppError::HttpError(StatusCode::EXPECTATION_FAILED, "Invalid database source type".to_string())),
         }
     }
 }
 
+impl DatabaseType {
+    /// The port a server of this type listens on by default. `None` for file databases.
+    pub fn default_port(&self) -> Option<u16> {
+        match self {
+            DatabaseType::Mysql | DatabaseType::MariaDB => Some(3306),
+            DatabaseType::Postgres => Some(5432),
+            DatabaseType::Mongo => Some(27017),
+            DatabaseType::Sqlite => None,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub enum FileType {
+    Csv,
+    Tsv,
+    Ndjson,
+    Parquet,
+    Arrow,
+}
+
+impl TryFrom<&str> for FileType {
+
+    type Error = AppError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "csv" => Ok(Self::Csv),
+            "tsv" => Ok(Self::Tsv),
+            "ndjson" => Ok(Self::Ndjson),
+            "parquet" => Ok(Self::Parquet),
+            "arrow" => Ok(Self::Arrow),
+            &_ => Err(AppError::HttpError(StatusCode::EXPECTATION_FAILED, "Invalid file source type".to_string())),
+        }
+    }
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub enum Cloud { Firebase }
 
 #[derive(Deserialize, Clone, Debug)]
 pub enum SourceType {
-    Database(DatabaseType), Cloud, File
+    Database(DatabaseType), Cloud, File(FileType)
 }
 
 impl SourceType {
@@ -40,22 +79,37 @@ impl SourceType {
         match src_type {
             "database" => Ok(Self::Database(src.try_into()?)),
             "cloud" => Ok(Self::Cloud),
-            "file" => Ok(Self::File),
+            "file" => Ok(Self::File(src.try_into()?)),
             &_ => Err(AppError::HttpError(StatusCode::EXPECTATION_FAILED, "Invalid source type".to_string()))
         }
     }
 }
 
-/// Configuration options for a new `BasableConnection`.
-#[derive(Deserialize, Clone, Debug)]
+/// Configuration options for a new `BasableConnection`. The password is never serialized.
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ConfigRaw {
     pub source_type: String,
     pub source: String,
     pub username: Option<String>,
+    #[serde(skip_serializing)]
     pub password: Option<String>,
     pub host: Option<String>,
     pub port: Option<u16>,
     pub db_name: Option<String>,
+
+    /// Path to the file holding the data, for sources that are read from disk (e.g. SQLite, CSV).
+    pub path: Option<String>,
+}
+
+/// A [`ConfigRaw`] saved under a name so its user can reconnect to it later. Connections
+/// opened from a profile use its `id`, so anything stored against them outlives restarts.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ConnectionProfile {
+    /// Set by the server when the profile is created.
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub config: ConfigRaw,
 }
 
 impl Default for ConfigRaw {
@@ -66,6 +120,7 @@ impl Default for ConfigRaw {
             host: None,
             port: None,
             db_name: None,
+            path: None,
             source_type: String::from("database"),
             source: String::from("mysql")
         }
@@ -77,13 +132,44 @@ impl ConfigRaw {
         let src_type = SourceType::from_str(&self.source_type, &self.source)?;
 
         match src_type {
-            SourceType::Database(_) => {
-                let dbtype = &self.source;
+            SourceType::Database(DatabaseType::Sqlite) | SourceType::File(_) => {
+                self.path.clone().ok_or_else(|| {
+                    AppError::HttpError(
+                        StatusCode::EXPECTATION_FAILED,
+                        format!("A file path is required for {} sources", self.source),
+                    )
+                })
+            }
+
+            // Mongo servers commonly run without authentication, so credentials are optional.
+            SourceType::Database(DatabaseType::Mongo) => {
+                let host = self.host.clone().unwrap_or("localhost".to_string());
+                let port = self.port.or(DatabaseType::Mongo.default_port()).unwrap_or_default();
+                let db = self.db_name.clone().unwrap_or_default();
+
+                let credentials = match &self.username {
+                    Some(username) => format!(
+                        "{}:{}@",
+                        encode(username),
+                        encode(&self.password.clone().unwrap_or_default())
+                    ),
+                    None => String::new(),
+                };
+
+                Ok(format!("mongodb://{credentials}{host}:{port}/{db}"))
+            }
+
+            SourceType::Database(db_type) => {
+                // MariaDB is reached through the MySQL driver, which only accepts `mysql://` urls.
+                let dbtype = match db_type {
+                    DatabaseType::MariaDB => "mysql",
+                    _ => &self.source,
+                };
 
                 let username = self.username.clone().unwrap_or("root".to_string());
                 let password = self.password.clone().unwrap_or_default();
                 let host = self.host.clone().unwrap_or("localhost".to_string());
-                let port = self.port.unwrap_or(3306);
+                let port = self.port.or(db_type.default_port()).unwrap_or_default();
                 let db = self.db_name.clone().unwrap_or_default();
 
                 let url = format!(
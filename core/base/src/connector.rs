@@ -1,6 +1,24 @@
+use axum::http::StatusCode;
 use common::error::AppError;
 
-use crate::config::ConfigRaw;
+use crate::{config::ConfigRaw, row::ColumnValue};
+
+/// A query and the values bound to its placeholders.
+pub type Statement = (String, Vec<ColumnValue>);
+
+/// Why a [`Connector::exec_transaction`] failed. The whole transaction was rolled back.
+#[derive(Debug)]
+pub struct TransactionError {
+    /// Position of the statement that failed.
+    pub index: usize,
+    pub error: AppError,
+}
+
+impl From<TransactionError> for AppError {
+    fn from(value: TransactionError) -> Self {
+        value.error
+    }
+}
 
 /// Facilitates connection and run queries between `Basable` instance and a databse server
 pub trait Connector: Send + Sync {
@@ -11,8 +29,76 @@ pub trait Connector: Send + Sync {
     where
         Self: Sized;
 
-    /// Execute a database query and return results
-    fn exec_query(&self, query: &str) -> Result<Vec<Self::Row>, AppError>;
+    /// Execute a database query and return results. `params` are bound, in order, to the
+    /// placeholders of `query` (see [`QueryParams`](crate::db::QueryParams)).
+    fn exec_query(&self, query: &str, params: &[ColumnValue]) -> Result<Vec<Self::Row>, AppError>;
+
+    /// Like [`Connector::exec_query`], but hands the rows to `f` one at a time as they're read
+    /// instead of loading them all. Stops at the first error returned by `f`.
+    fn for_each_row(
+        &self,
+        query: &str,
+        params: &[ColumnValue],
+        f: &mut (dyn FnMut(Self::Row) -> Result<(), AppError> + Send),
+    ) -> Result<(), AppError> {
+        self.exec_query(query, params)?.into_iter().try_for_each(f)
+    }
+
+    /// Execute `statements` in order on a single connection and in a single transaction,
+    /// returning the results of each. Nothing is committed unless they all succeed.
+    fn exec_transaction(
+        &self,
+        _statements: &[Statement],
+    ) -> Result<Vec<Vec<Self::Row>>, TransactionError> {
+        Err(TransactionError {
+            index: 0,
+            error: AppError::HttpError(
+                StatusCode::NOT_IMPLEMENTED,
+                "Transactions are not supported by this source".to_string(),
+            ),
+        })
+    }
 
     fn config(&self) -> &ConfigRaw;
 }
+
+/// Runs a blocking database call from wherever a [`Connector`] is used.
+///
+/// Some drivers (e.g. `postgres`) drive their own runtime and panic if they are blocked on from
+/// a thread that is already running async tasks, e.g. when called straight from an axum handler
+/// rather than through `spawn_blocking`. `block_in_place` panics on a current thread runtime,
+/// so there `f` runs on a thread of its own.
+#[cfg(any(feature = "postgres", feature = "mongo"))]
+pub(crate) fn run_blocking<T: Send>(f: impl FnOnce() -> T + Send) -> T {
+    use tokio::runtime::{Handle, RuntimeFlavor};
+
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::CurrentThread => {
+            std::thread::scope(|scope| {
+                scope
+                    .spawn(f)
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+        }
+        Ok(_) => tokio::task::block_in_place(f),
+        Err(_) => f(),
+    }
+}
+
+#[cfg(test)]
+#[cfg(any(feature = "postgres", feature = "mongo"))]
+pub(crate) mod tests {
+    use super::run_blocking;
+
+    #[test]
+    fn test_run_blocking() {
+        let current_thread = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        assert_eq!(current_thread.block_on(async { run_blocking(|| 1) }), 1);
+
+        let multi_thread = tokio::runtime::Builder::new_multi_thread().build().unwrap();
+        assert_eq!(multi_thread.block_on(async { run_blocking(|| 2) }), 2);
+
+        assert_eq!(run_blocking(|| 3), 3);
+    }
+}
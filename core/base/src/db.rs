@@ -1,14 +1,14 @@
+use axum::http::StatusCode;
 use common::error::AppError;
 use common::DbServerDetails;
 use uuid::Uuid;
 
 use common::data::table::{TableSearchOpts, TableSummaries};
-use common::query::filter::{Filter, FilterChain};
-use common::query::{BasableQuery, QueryCommand};
-
-use crate::mysql::db::MySqlDB;
+use common::query::filter::{Filter, FilterChain, FilterCombinator, FilterExpression};
+use common::query::{BasableQuery, QueryCommand, QueryJoin, QueryOrder};
 
 use super::graphs::VisualizeDB;
+use super::row::ColumnValue;
 use super::{ConnectorType, SharedTable};
 
 pub type DBQueryResult<R, E> = Result<Vec<R>, E>;
@@ -19,6 +19,10 @@ pub trait DB: VisualizeDB + QuerySqlParser + Send + Sync {
 
     fn id(&self) -> &Uuid;
 
+    /// Replace the id generated for this connection, e.g. with the id of the saved
+    /// connection profile it was opened from.
+    fn set_id(&mut self, id: Uuid);
+
     fn user_id(&self) -> &str;
 
     /// Get the [`ConnectorType`] instance for [`DB`].
@@ -51,29 +55,247 @@ pub trait DB: VisualizeDB + QuerySqlParser + Send + Sync {
 
     /// Drop and delete a table
     fn drop_table(&self, table_name: &str) -> Result<(), AppError>;
+
+    /// Check that `table` is one of the loaded tables and has each of `columns`.
+    fn check_columns(&self, table: &str, columns: &[&str]) -> Result<(), AppError> {
+        match self.get_table(table) {
+            Some(t) => t.check_columns(columns),
+            None => Err(AppError::HttpError(
+                StatusCode::NOT_FOUND,
+                format!("Unknown table `{table}`"),
+            )),
+        }
+    }
+
+    /// Check every table and column named by `query`. Call this before
+    /// [`QuerySqlParser::generate_sql`] for queries built from a request.
+    fn check_query(&self, query: &BasableQuery) -> Result<(), AppError> {
+        let mut columns: Vec<&str> = Vec::new();
+
+        let QueryCommand::SelectData(selection) = &query.command;
+        if let Some(cols) = selection {
+            let cols = cols.iter().filter(|col| col.to_lowercase() != "count(*)");
+            columns.extend(cols.map(|col| col.as_str()));
+        }
+
+        let filters = query.filters.all().iter().chain(query.having.all());
+        columns.extend(filters.map(|f| f.column.as_str()));
+
+        if let Some(group_by) = &query.group_by {
+            columns.extend(group_by.iter().map(|col| col.as_str()));
+        }
+
+        columns.extend(query.order_by.iter().map(QueryOrder::column));
+
+        if let Some(opts) = &query.search_opts {
+            columns.extend(opts.search_cols.iter().map(|col| col.as_str()));
+        }
+
+        if let Some(join) = &query.left_join {
+            columns.push(&join.column);
+            self.check_columns(&join.table, &[&join.foreign_column])?;
+        }
+
+        self.check_columns(&query.table, &columns)
+    }
 }
 
+/// Values bound to the placeholders of a query, collected while the query is generated.
+/// User input is only ever passed to the database through these, never as SQL text.
+#[derive(Default)]
+pub struct QueryParams {
+    values: Vec<ColumnValue>,
+    numbered: bool,
+}
+
+impl QueryParams {
+    /// Parameters written as `?`, as MySQL and SQLite expect.
+    pub fn new() -> Self {
+        QueryParams::default()
+    }
+
+    /// Parameters written as `$1`, `$2`, ..., as PostgreSQL expects.
+    pub fn numbered() -> Self {
+        QueryParams {
+            values: Vec::new(),
+            numbered: true,
+        }
+    }
+
+    /// Bind `value` and return the placeholder to write in its place.
+    pub fn bind(&mut self, value: impl Into<ColumnValue>) -> String {
+        self.values.push(value.into());
+
+        if self.numbered {
+            format!("${}", self.values.len())
+        } else {
+            "?".to_string()
+        }
+    }
+
+    /// Bind each of `values`, returning their placeholders separated by commas.
+    pub fn bind_list<V: Into<ColumnValue>>(&mut self, values: impl IntoIterator<Item = V>) -> String {
+        let placeholders: Vec<String> = values.into_iter().map(|v| self.bind(v)).collect();
+        placeholders.join(", ")
+    }
+
+    pub fn into_values(self) -> Vec<ColumnValue> {
+        self.values
+    }
+}
+
+/// Quote an identifier with MySQL's backticks.
+fn backtick_ident(name: &str) -> String {
+    format!("`{}`", name.replace('`', "``"))
+}
+
+/// Builds queries from a [`BasableQuery`]. Values from the query are bound to
+/// [`QueryParams`] and the generated SQL only holds their placeholders. Identifiers are quoted
+/// with [`QuerySqlParser::quote_ident`], but they should be checked with [`DB::check_query`]
+/// first.
 pub trait QuerySqlParser {
-    fn parse_filter(filter: &Filter) -> String
-    where
-        Self: Sized,
-    {
-        filter.to_string()
+    /// Quote a table or column name. Defaults to MySQL's backticks.
+    fn quote_ident(&self, name: &str) -> String {
+        backtick_ident(name)
+    }
+
+    /// Create the parameter list for a query. Defaults to `?` placeholders.
+    fn new_params(&self) -> QueryParams {
+        QueryParams::new()
+    }
+
+    /// The regular expression operator, or its negation when `negated`. Defaults to MySQL's
+    /// `REGEXP`.
+    fn regex_operator(&self, negated: bool) -> &'static str {
+        match negated {
+            true => "NOT REGEXP",
+            false => "REGEXP",
+        }
     }
 
-    fn parse_filter_chain(filters: &FilterChain) -> String
-    where
-        Self: Sized,
-    {
+    /// Render a filter expression, binding its values to `params`. Defaults to MySQL syntax.
+    fn parse_expression(&self, expression: &FilterExpression, params: &mut QueryParams) -> String {
+        match expression {
+            FilterExpression::Eq(v) => format!("= {}", params.bind(v.as_str())),
+            FilterExpression::NotEq(v) => format!("!= {}", params.bind(v.as_str())),
+            FilterExpression::Gt(v) => format!("> {}", params.bind(v.as_str())),
+            FilterExpression::Lt(v) => format!("< {}", params.bind(v.as_str())),
+            FilterExpression::Gte(v) => format!(">= {}", params.bind(v.as_str())),
+            FilterExpression::Lte(v) => format!("<= {}", params.bind(v.as_str())),
+            FilterExpression::Contains(v) => format!("LIKE {}", params.bind(format!("%{v}%"))),
+            FilterExpression::NotContains(v) => format!("NOT LIKE {}", params.bind(format!("%{v}%"))),
+            FilterExpression::Regex(v) => {
+                format!("{} {}", self.regex_operator(false), params.bind(v.as_str()))
+            }
+            FilterExpression::NotRegex(v) => {
+                format!("{} {}", self.regex_operator(true), params.bind(v.as_str()))
+            }
+            FilterExpression::Btw(start, end) => format!(
+                "BETWEEN {} AND {}",
+                params.bind(start.as_str()),
+                params.bind(end.as_str())
+            ),
+            FilterExpression::NotBtw(start, end) => format!(
+                "NOT BETWEEN {} AND {}",
+                params.bind(start.as_str()),
+                params.bind(end.as_str())
+            ),
+            FilterExpression::Includes(values) => {
+                format!("IN ({})", params.bind_list(values.iter().map(|v| v.as_str())))
+            }
+            FilterExpression::NotInclude(values) => {
+                format!("NOT IN ({})", params.bind_list(values.iter().map(|v| v.as_str())))
+            }
+            FilterExpression::Null => "IS NULL".to_string(),
+            FilterExpression::NotNull => "IS NOT NULL".to_string(),
+        }
+    }
+
+    /// Render a [`Filter`], binding its values to `params`. Defaults to MySQL syntax.
+    fn parse_filter(&self, filter: &Filter, params: &mut QueryParams) -> String {
+        let comb = match filter.combinator {
+            FilterCombinator::AND => "AND ",
+            FilterCombinator::OR => "OR ",
+            FilterCombinator::BASE => "",
+        };
+
+        format!(
+            "{comb}{} {}",
+            self.quote_ident(&filter.column),
+            self.parse_expression(&filter.expression, params)
+        )
+    }
+
+    /// Render a [`FilterChain`] with [`QuerySqlParser::parse_filter`].
+    fn parse_filters(&self, filters: &FilterChain, params: &mut QueryParams) -> String {
         let filters: Vec<String> = filters
             .all()
             .iter()
-            .map(|f| Self::parse_filter(f))
+            .map(|f| self.parse_filter(f, params))
             .collect();
         filters.join(" ")
     }
 
-    fn generate_sql(&self, query: BasableQuery) -> Result<String, AppError> {
+    /// Render the full-text search condition for [`TableSearchOpts`]. Defaults to MySQL's
+    /// natural language `MATCH ... AGAINST`, which needs a `FULLTEXT` index over `search_cols`.
+    fn parse_search(&self, opts: &TableSearchOpts, params: &mut QueryParams) -> String {
+        let wrap_cols: Vec<String> = opts.search_cols.iter().map(|col| self.quote_ident(col)).collect();
+
+        format!("MATCH({}) AGAINST({})", wrap_cols.join(","), params.bind(opts.query.as_str()))
+    }
+
+    /// Render the selected columns, leaving `COUNT(*)` as is.
+    fn parse_selection(&self, cols: Option<Vec<String>>) -> String {
+        match cols {
+            Some(list) if !list.is_empty() => {
+                let s: Vec<String> = list
+                    .iter()
+                    .map(|col| {
+                        if col.to_lowercase() == "count(*)" {
+                            return col.to_string();
+                        }
+
+                        self.quote_ident(col)
+                    })
+                    .collect();
+                s.join(", ")
+            }
+            _ => "*".to_string(),
+        }
+    }
+
+    /// Render a `LEFT JOIN` onto `table`.
+    fn parse_join(&self, table: &str, join: &QueryJoin) -> String {
+        let foreign = self.quote_ident(&join.table);
+
+        format!(
+            " LEFT JOIN {foreign} ON {}.{} = {foreign}.{}",
+            self.quote_ident(table),
+            self.quote_ident(&join.column),
+            self.quote_ident(&join.foreign_column)
+        )
+    }
+
+    /// Render an `ORDER BY` clause over the sort keys, in order.
+    fn parse_order(&self, order: &[QueryOrder]) -> String {
+        let keys: Vec<String> = order
+            .iter()
+            .map(|key| match key {
+                QueryOrder::ASC(col) => format!("{} ASC", self.quote_ident(col)),
+                QueryOrder::DESC(col) => format!("{} DESC", self.quote_ident(col)),
+            })
+            .collect();
+
+        format!(" ORDER BY {}", keys.join(", "))
+    }
+
+    /// Render a `LIMIT` clause. Defaults to MySQL's `LIMIT offset, row_count`.
+    fn parse_limit(&self, row_count: usize, offset: usize) -> String {
+        format!(" LIMIT {offset}, {row_count}")
+    }
+
+    /// Generate the query and the values bound to its placeholders.
+    fn generate_sql(&self, query: BasableQuery) -> Result<(String, Vec<ColumnValue>), AppError> {
         let is_search_mode = query.is_search_mode();
 
         let BasableQuery {
@@ -89,85 +311,181 @@ pub trait QuerySqlParser {
             search_opts,
         } = query;
 
+        let mut params = self.new_params();
+
         // Parse query operation type
         let mut sql = match operation {
             QueryCommand::SelectData(cols) => {
-                let select_cols = cols.map_or_else(
-                    || "*".to_string(),
-                    |list| {
-                        if list.is_empty() {
-                            return "*".to_string();
-                        }
-
-                        let s: Vec<String> = list
-                            .iter()
-                            .map(|s| {
-                                if s.to_lowercase() == "count(*)" {
-                                    return format!("{s}");
-                                }
-
-                                format!("`{s}`")
-                            })
-                            .collect();
-                        s.join(", ")
-                    },
-                );
-
-                format!("SELECT {select_cols} FROM {table}")
+                format!("SELECT {} FROM {}", self.parse_selection(cols), self.quote_ident(&table))
             }
         };
 
         // Parse left join
         if let Some(left_join) = left_join {
-            sql.push_str(format!(" LEFT JOIN {left_join}").as_str())
+            sql.push_str(&self.parse_join(&table, &left_join));
         }
 
         // Parse query filters
         if filters.not_empty() && !is_search_mode {
-            let filter_chain = <MySqlDB as QuerySqlParser>::parse_filter_chain(&filters);
+            let filter_chain = self.parse_filters(&filters, &mut params);
             sql.push_str(format!(" WHERE {filter_chain}").as_str())
         }
 
         // parse fulltext search mode
         if is_search_mode {
             if let Some(opts) = search_opts {
-                let TableSearchOpts {
-                    search_cols, query, ..
-                } = opts;
-
-                let wrap_cols: Vec<String> =
-                    search_cols.iter().map(|col| format!("`{col}`")).collect();
-
-                let search_query =
-                    format!(" WHERE MATCH({}) AGAINST('{}')", wrap_cols.join(","), query);
-
+                let search_query = format!(" WHERE {}", self.parse_search(&opts, &mut params));
                 sql.push_str(&search_query);
             }
         }
 
         // Parse GROUP BY
         if let Some(group_by) = group_by {
-            let cols = group_by.join(", ");
-            sql.push_str(format!(" GROUP BY {cols}").as_str());
+            let cols: Vec<String> = group_by.iter().map(|col| self.quote_ident(col)).collect();
+            sql.push_str(format!(" GROUP BY {}", cols.join(", ")).as_str());
         }
 
         // Parse HAVING
         if having.not_empty() {
-            let filter_chain = <MySqlDB as QuerySqlParser>::parse_filter_chain(&having);
+            let filter_chain = self.parse_filters(&having, &mut params);
             sql.push_str(format!(" HAVING {filter_chain}").as_str())
         }
 
         // Parse ORDER BY
-        if let Some(order) = order_by {
-            sql.push_str(format!(" ORDER BY {order}").as_str());
+        if !order_by.is_empty() {
+            sql.push_str(&self.parse_order(&order_by));
         }
 
         // Parse LIMIT
         if let Some(row_count) = row_count {
-            let offset = offset.unwrap_or_default();
-            sql.push_str(format!(" LIMIT {offset}, {row_count}").as_str());
+            sql.push_str(&self.parse_limit(row_count, offset.unwrap_or_default()));
         }
 
-        Ok(sql)
+        Ok((sql, params.into_values()))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use common::query::{
+        filter::{Filter, FilterChain, FilterCombinator, FilterExpression},
+        BasableQuery, QueryOrder,
+    };
+
+    use crate::row::ColumnValue;
+
+    use super::{QueryParams, QuerySqlParser};
+
+    struct Parser;
+    impl QuerySqlParser for Parser {}
+
+    #[test]
+    fn test_generate_sql_binds_values() {
+        let filters = FilterChain::prefill(vec![
+            Filter {
+                combinator: FilterCombinator::BASE,
+                column: "name".to_string(),
+                expression: FilterExpression::Eq("x' OR '1'='1".to_string()),
+            },
+            Filter {
+                combinator: FilterCombinator::AND,
+                column: "age".to_string(),
+                expression: FilterExpression::Includes(vec!["1".to_string(), "2".to_string()]),
+            },
+        ]);
+
+        let query = BasableQuery {
+            table: "users".to_string(),
+            filters,
+            ..Default::default()
+        };
+
+        let (sql, params) = Parser.generate_sql(query).unwrap();
+
+        assert_eq!(sql, "SELECT * FROM `users` WHERE `name` = ? AND `age` IN (?, ?)");
+        assert_eq!(
+            params,
+            vec![
+                ColumnValue::Text("x' OR '1'='1".to_string()),
+                ColumnValue::Text("1".to_string()),
+                ColumnValue::Text("2".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quote_identifiers() {
+        let query = BasableQuery {
+            table: "user`s".to_string(),
+            order_by: vec![
+                QueryOrder::DESC("created at".to_string()),
+                QueryOrder::ASC("id".to_string()),
+            ],
+            ..Default::default()
+        };
+
+        let (sql, _) = Parser.generate_sql(query).unwrap();
+
+        assert_eq!(sql, "SELECT * FROM `user``s` ORDER BY `created at` DESC, `id` ASC");
+    }
+
+    #[test]
+    fn test_dialect_hooks() {
+        struct Numbered;
+        impl QuerySqlParser for Numbered {
+            fn quote_ident(&self, name: &str) -> String {
+                format!("\"{name}\"")
+            }
+
+            fn new_params(&self) -> QueryParams {
+                QueryParams::numbered()
+            }
+
+            fn parse_limit(&self, row_count: usize, offset: usize) -> String {
+                format!(" LIMIT {row_count} OFFSET {offset}")
+            }
+
+            fn regex_operator(&self, negated: bool) -> &'static str {
+                match negated {
+                    true => "!~",
+                    false => "~",
+                }
+            }
+        }
+
+        let query = BasableQuery {
+            table: "users".to_string(),
+            filters: FilterChain::prefill(vec![
+                Filter {
+                    combinator: FilterCombinator::BASE,
+                    column: "name".to_string(),
+                    expression: FilterExpression::Eq("x".to_string()),
+                },
+                Filter {
+                    combinator: FilterCombinator::AND,
+                    column: "email".to_string(),
+                    expression: FilterExpression::NotRegex("@test$".to_string()),
+                },
+            ]),
+            row_count: Some(10),
+            offset: Some(20),
+            ..Default::default()
+        };
+
+        let (sql, _) = Numbered.generate_sql(query).unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT * FROM \"users\" WHERE \"name\" = $1 AND \"email\" !~ $2 LIMIT 10 OFFSET 20"
+        );
+    }
+
+    #[test]
+    fn test_numbered_params() {
+        let mut params = QueryParams::numbered();
+
+        assert_eq!(params.bind("a"), "$1");
+        assert_eq!(params.bind_list(["b", "c"]), "$2, $3");
+        assert_eq!(params.into_values().len(), 3);
+    }
+}
@@ -0,0 +1,639 @@
+//! Writers for the formats tables are exported to. They work on [`ColumnValue`]s, so rows of
+//! every backend export the same way.
+
+use std::io::{self, Write};
+
+use axum::http::StatusCode;
+use common::{
+    data::table::{TableExportFormat, XmlExportOpts},
+    error::AppError,
+};
+
+use crate::row::{ColumnValue, FromColumnValue, Row};
+#[cfg(any(
+    feature = "mysql",
+    feature = "postgres",
+    feature = "sqlite",
+    feature = "mongo"
+))]
+use crate::ConnectorType;
+#[cfg(feature = "xlsx")]
+use rust_xlsxwriter::{ExcelDateTime, Format, Workbook, Worksheet, XlsxError};
+
+/// Rows of an XLSX sheet, header included.
+#[cfg(feature = "xlsx")]
+const XLSX_MAX_ROWS: usize = 1_048_576;
+
+/// Cells an XLSX export holds at most. The workbook is kept in memory until it's finished,
+/// so this bounds the memory an export takes.
+#[cfg(feature = "xlsx")]
+const XLSX_MAX_CELLS: usize = 2_000_000;
+
+/// Largest integer an XLSX number holds exactly. Larger ones are written as text.
+#[cfg(feature = "xlsx")]
+const XLSX_MAX_INT: u64 = 1 << 53;
+
+/// Writes the rows of an export to `out` as they're read.
+///
+/// - CSV, PSV and TEXT follow RFC 4180: fields holding the delimiter, a quote or a line break
+///   are quoted, quotes are doubled and records end with CRLF.
+/// - TSV can't quote, so backslashes, tabs and line breaks in fields are escaped as `\\`, `\t`,
+///   `\n` and `\r`.
+/// - JSON is an array of objects and NDJSON an object per line. Numbers and NULL keep their
+///   type, dates and times are strings.
+/// - HTML is a `<table>`, with the columns as its header.
+/// - XML has a root element holding an element per row, which holds an element per column.
+///   Column elements have a `type` attribute, and a `name` attribute with the column name when
+///   it had to be sanitised into a valid element name. NULL is an empty element with
+///   `null="true"`.
+/// - XLSX is a workbook with a sheet, whose first row is the columns. Numbers, dates and
+///   times are typed cells. As the workbook is compressed as a whole, it's only written to
+///   `out` once finished, and holds at most 2,000,000 cells. It needs the `xlsx` feature.
+///
+/// NULL is an empty field in every format but JSON, NDJSON and XML, and an empty cell in XLSX.
+pub struct ExportWriter<'a> {
+    format: TableExportFormat,
+    columns: Vec<String>,
+    out: &'a mut (dyn Write + Send),
+    rows: usize,
+    xml: XmlNames,
+    #[cfg(feature = "xlsx")]
+    xlsx: Option<XlsxSheet>,
+}
+
+/// The sheet of an XLSX export and the formats of its cells.
+#[cfg(feature = "xlsx")]
+struct XlsxSheet {
+    sheet: Worksheet,
+
+    /// Rows the sheet holds at most, header excluded.
+    max_rows: usize,
+    header: Format,
+    date: Format,
+    datetime: Format,
+    time: Format,
+}
+
+#[cfg(feature = "xlsx")]
+impl XlsxSheet {
+    fn new(columns: usize) -> Self {
+        XlsxSheet {
+            sheet: Worksheet::new(),
+            max_rows: (XLSX_MAX_CELLS / columns.max(1)).min(XLSX_MAX_ROWS - 1),
+            header: Format::new().set_bold(),
+            date: Format::new().set_num_format("yyyy-mm-dd"),
+            datetime: Format::new().set_num_format("yyyy-mm-dd hh:mm:ss"),
+            time: Format::new().set_num_format("[h]:mm:ss"),
+        }
+    }
+
+    fn write_header(&mut self, columns: &[String]) -> Result<(), XlsxError> {
+        for (col, name) in columns.iter().enumerate() {
+            self.sheet
+                .write_string_with_format(0, xlsx_col(col)?, name, &self.header)?;
+        }
+        self.sheet.set_freeze_panes(1, 0)?;
+
+        Ok(())
+    }
+
+    fn write_row(&mut self, row: usize, values: &[ColumnValue]) -> Result<(), XlsxError> {
+        let row = u32::try_from(row).map_err(|_| XlsxError::RowColumnLimitError)?;
+
+        for (col, value) in values.iter().enumerate() {
+            let col = xlsx_col(col)?;
+            match value {
+                ColumnValue::NULL => continue,
+                ColumnValue::Int(v) if v.unsigned_abs() <= XLSX_MAX_INT => {
+                    self.sheet.write_number(row, col, *v as f64)?
+                }
+                ColumnValue::UInt(v) if *v <= XLSX_MAX_INT => {
+                    self.sheet.write_number(row, col, *v as f64)?
+                }
+                ColumnValue::Float(v) if v.is_finite() => self.sheet.write_number(row, col, *v)?,
+                ColumnValue::Double(v) if v.is_finite() => self.sheet.write_number(row, col, *v)?,
+                ColumnValue::Date(y, m, d, h, min, s, us) => {
+                    let time = (*h, *min, *s, *us) != (0, 0, 0, 0);
+                    let date = ExcelDateTime::from_ymd(*y, *m, *d).and_then(|date| match time {
+                        true => {
+                            date.and_hms(u16::from(*h), *min, f64::from(*s) + f64::from(*us) / 1e6)
+                        }
+                        false => Ok(date),
+                    });
+
+                    match date {
+                        Ok(date) => {
+                            let format = if time { &self.datetime } else { &self.date };
+                            self.sheet
+                                .write_datetime_with_format(row, col, date, format)?
+                        }
+                        // Like MySQL zero dates, which Excel can't hold.
+                        Err(_) => self.sheet.write_string(row, col, text(value))?,
+                    }
+                }
+                ColumnValue::Time(false, d, h, min, s, us) => {
+                    let seconds = f64::from(*d) * 86_400.0
+                        + f64::from(*h) * 3_600.0
+                        + f64::from(*min) * 60.0
+                        + f64::from(*s)
+                        + f64::from(*us) / 1e6;
+                    self.sheet
+                        .write_number_with_format(row, col, seconds / 86_400.0, &self.time)?
+                }
+                // Negative times, and numbers Excel would round or can't hold.
+                v => self.sheet.write_string(row, col, text(v))?,
+            };
+        }
+
+        Ok(())
+    }
+
+    fn save(self, out: &mut (dyn Write + Send)) -> Result<(), AppError> {
+        let mut workbook = Workbook::new();
+        workbook.push_worksheet(self.sheet);
+        let buffer = workbook.save_to_buffer().map_err(xlsx_error)?;
+
+        out.write_all(&buffer).map_err(write_error)
+    }
+}
+
+#[cfg(feature = "xlsx")]
+fn xlsx_col(col: usize) -> Result<u16, XlsxError> {
+    u16::try_from(col).map_err(|_| XlsxError::RowColumnLimitError)
+}
+
+#[cfg(feature = "xlsx")]
+fn xlsx_error(err: XlsxError) -> AppError {
+    AppError::ServerError(format!("Failed to write XLSX: {err}"))
+}
+
+/// Element names of an XML export.
+struct XmlNames {
+    root: String,
+    row: String,
+    columns: Vec<String>,
+}
+
+impl XmlNames {
+    fn new(opts: XmlExportOpts, columns: &[String]) -> Self {
+        XmlNames {
+            root: element_name(opts.root.as_deref().unwrap_or("rows")),
+            row: element_name(opts.row.as_deref().unwrap_or("row")),
+            columns: columns.iter().map(|col| element_name(col)).collect(),
+        }
+    }
+}
+
+impl<'a> ExportWriter<'a> {
+    /// Create a writer, writing the start of the export, like the header, to `out`. `xml` is
+    /// only used by XML exports.
+    pub fn new(
+        format: TableExportFormat,
+        xml: XmlExportOpts,
+        columns: Vec<String>,
+        out: &'a mut (dyn Write + Send),
+    ) -> Result<Self, AppError> {
+        #[cfg(not(feature = "xlsx"))]
+        if matches!(format, TableExportFormat::XLSX) {
+            return Err(AppError::HttpError(
+                StatusCode::NOT_IMPLEMENTED,
+                "XLSX exports need the `xlsx` feature".to_string(),
+            ));
+        }
+
+        let mut writer = ExportWriter {
+            xml: XmlNames::new(xml, &columns),
+            #[cfg(feature = "xlsx")]
+            xlsx: matches!(format, TableExportFormat::XLSX).then(|| XlsxSheet::new(columns.len())),
+            format,
+            columns,
+            out,
+            rows: 0,
+        };
+        #[cfg(feature = "xlsx")]
+        if let Some(xlsx) = &mut writer.xlsx {
+            xlsx.write_header(&writer.columns).map_err(xlsx_error)?;
+        }
+        writer.begin().map_err(write_error)?;
+
+        Ok(writer)
+    }
+
+    fn begin(&mut self) -> io::Result<()> {
+        match self.format {
+            TableExportFormat::CSV
+            | TableExportFormat::PSV
+            | TableExportFormat::TSV
+            | TableExportFormat::TEXT => {
+                let header: Vec<ColumnValue> = self
+                    .columns
+                    .iter()
+                    .map(|col| ColumnValue::Text(col.clone()))
+                    .collect();
+                self.write_record(&header)
+            }
+            TableExportFormat::JSON => self.out.write_all(b"["),
+            TableExportFormat::HTML => {
+                self.out.write_all(b"<table>\n<thead>\n<tr>")?;
+                for col in &self.columns {
+                    write!(self.out, "<th>{}</th>", escape_html(col))?;
+                }
+                self.out.write_all(b"</tr>\n</thead>\n<tbody>\n")
+            }
+            TableExportFormat::XML => write!(
+                self.out,
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<{}>\n",
+                self.xml.root
+            ),
+            TableExportFormat::NDJSON | TableExportFormat::XLSX => Ok(()),
+        }
+    }
+
+    /// Write a row, taking the values of the export's columns from it. Columns the row
+    /// doesn't have are NULL.
+    pub fn write_row(&mut self, row: &Row) -> Result<(), AppError> {
+        let values: Vec<ColumnValue> = self
+            .columns
+            .iter()
+            .map(|col| row.get(col.as_str()).unwrap_or(ColumnValue::NULL))
+            .collect();
+
+        self.write_values(&values)
+    }
+
+    /// Write a row given its values, in the order of the export's columns.
+    pub fn write_values(&mut self, values: &[ColumnValue]) -> Result<(), AppError> {
+        #[cfg(feature = "xlsx")]
+        if let Some(xlsx) = &mut self.xlsx {
+            if self.rows >= xlsx.max_rows {
+                return Err(AppError::HttpError(
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    format!(
+                        "XLSX exports of {} columns hold at most {} rows, trim the export or use another format",
+                        self.columns.len(),
+                        xlsx.max_rows
+                    ),
+                ));
+            }
+
+            xlsx.write_row(self.rows + 1, values).map_err(xlsx_error)?;
+            self.rows += 1;
+
+            return Ok(());
+        }
+
+        match self.format {
+            TableExportFormat::CSV
+            | TableExportFormat::PSV
+            | TableExportFormat::TSV
+            | TableExportFormat::TEXT => self.write_record(values),
+            TableExportFormat::JSON => {
+                let separator: &[u8] = if self.rows == 0 { b"\n" } else { b",\n" };
+                self.out
+                    .write_all(separator)
+                    .and_then(|_| self.write_object(values))
+            }
+            TableExportFormat::NDJSON => self
+                .write_object(values)
+                .and_then(|_| self.out.write_all(b"\n")),
+            TableExportFormat::HTML => self.write_html_row(values),
+            TableExportFormat::XML => self.write_xml_row(values),
+            TableExportFormat::XLSX => Ok(()),
+        }
+        .map_err(write_error)?;
+        self.rows += 1;
+
+        Ok(())
+    }
+
+    /// Write the end of the export.
+    pub fn finish(self) -> Result<(), AppError> {
+        #[cfg(feature = "xlsx")]
+        if let Some(xlsx) = self.xlsx {
+            return xlsx.save(self.out);
+        }
+
+        let end = match self.format {
+            TableExportFormat::JSON if self.rows > 0 => "\n]\n".to_string(),
+            TableExportFormat::JSON => "]\n".to_string(),
+            TableExportFormat::HTML => "</tbody>\n</table>\n".to_string(),
+            TableExportFormat::XML => format!("</{}>\n", self.xml.root),
+            _ => String::new(),
+        };
+
+        self.out.write_all(end.as_bytes()).map_err(write_error)
+    }
+
+    fn write_record(&mut self, values: &[ColumnValue]) -> io::Result<()> {
+        let delimiter = self.format.field_delimiter().unwrap_or(',');
+        let tsv = matches!(self.format, TableExportFormat::TSV);
+
+        for (i, value) in values.iter().enumerate() {
+            if i > 0 {
+                write!(self.out, "{delimiter}")?;
+            }
+
+            let field = text(value);
+            let field = if tsv {
+                escape_tsv(&field)
+            } else {
+                quote_csv(&field, delimiter)
+            };
+            self.out.write_all(field.as_bytes())?;
+        }
+
+        let end: &[u8] = if tsv { b"\n" } else { b"\r\n" };
+        self.out.write_all(end)
+    }
+
+    fn write_object(&mut self, values: &[ColumnValue]) -> io::Result<()> {
+        self.out.write_all(b"{")?;
+        for (i, (col, value)) in self.columns.iter().zip(values).enumerate() {
+            if i > 0 {
+                self.out.write_all(b",")?;
+            }
+            serde_json::to_writer(&mut self.out, col)?;
+            self.out.write_all(b":")?;
+            self.out.write_all(json(value).as_bytes())?;
+        }
+
+        self.out.write_all(b"}")
+    }
+
+    fn write_html_row(&mut self, values: &[ColumnValue]) -> io::Result<()> {
+        self.out.write_all(b"<tr>")?;
+        for value in values {
+            write!(self.out, "<td>{}</td>", escape_html(&text(value)))?;
+        }
+
+        self.out.write_all(b"</tr>\n")
+    }
+
+    fn write_xml_row(&mut self, values: &[ColumnValue]) -> io::Result<()> {
+        writeln!(self.out, "  <{}>", self.xml.row)?;
+        for ((element, col), value) in self.xml.columns.iter().zip(&self.columns).zip(values) {
+            write!(self.out, "    <{element}")?;
+            if element != col {
+                write!(self.out, " name=\"{}\"", escape_xml(col, true))?;
+            }
+
+            let (ty, content) = match value {
+                ColumnValue::NULL => {
+                    writeln!(self.out, " null=\"true\"/>")?;
+                    continue;
+                }
+                ColumnValue::Text(v) => ("string", escape_xml(v, false)),
+                ColumnValue::Int(v) => ("integer", v.to_string()),
+                ColumnValue::UInt(v) => ("integer", v.to_string()),
+                ColumnValue::Float(v) => ("double", xml_double(f64::from(*v), v.to_string())),
+                ColumnValue::Double(v) => ("double", xml_double(*v, v.to_string())),
+                ColumnValue::Date(..) => ("datetime", text(value)),
+                ColumnValue::Time(..) => ("time", text(value)),
+            };
+            writeln!(self.out, " type=\"{ty}\">{content}</{element}>")?;
+        }
+
+        writeln!(self.out, "  </{}>", self.xml.row)
+    }
+}
+
+fn write_error(err: io::Error) -> AppError {
+    AppError::ServerError(err.to_string())
+}
+
+/// `value` as text, empty for NULL.
+fn text(value: &ColumnValue) -> String {
+    String::from_value(value).unwrap_or_default()
+}
+
+/// `value` as a JSON value. Floats that aren't finite are `null`, as JSON has no such numbers.
+fn json(value: &ColumnValue) -> String {
+    match value {
+        ColumnValue::NULL => "null".to_string(),
+        ColumnValue::Int(v) => v.to_string(),
+        ColumnValue::UInt(v) => v.to_string(),
+        ColumnValue::Float(v) if v.is_finite() => v.to_string(),
+        ColumnValue::Double(v) if v.is_finite() => v.to_string(),
+        ColumnValue::Float(_) | ColumnValue::Double(_) => "null".to_string(),
+        v => serde_json::Value::String(text(v)).to_string(),
+    }
+}
+
+fn quote_csv(field: &str, delimiter: char) -> String {
+    if field.contains([delimiter, '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn escape_tsv(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// `name` made into a valid XML element name: characters names can't have become `_`, and
+/// names that can't start with their first character, or start with the reserved `xml`, get a
+/// `_` prefix.
+fn element_name(name: &str) -> String {
+    let mut element: String = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' | '.' => c,
+            c if c.is_alphanumeric() => c,
+            _ => '_',
+        })
+        .collect();
+
+    let starts_ok = element
+        .chars()
+        .next()
+        .is_some_and(|c| c == '_' || c.is_alphabetic());
+    if !starts_ok || element.to_lowercase().starts_with("xml") {
+        element.insert(0, '_');
+    }
+
+    element
+}
+
+/// `text` escaped for XML content, or attribute values with `attribute`. Characters XML
+/// doesn't allow, like most control characters, become U+FFFD.
+fn escape_xml(text: &str, attribute: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if attribute => escaped.push_str("&quot;"),
+            '\'' if attribute => escaped.push_str("&apos;"),
+            // Line breaks in attributes would be normalised to spaces by parsers.
+            '\t' | '\n' | '\r' if attribute => escaped.push_str(&format!("&#{};", c as u32)),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c < ' ' || c == '\u{FFFE}' || c == '\u{FFFF}' => escaped.push('\u{FFFD}'),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// A double as written by XML Schema, which spells infinities and NaN differently than Rust.
+fn xml_double(value: f64, text: String) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "INF" } else { "-INF" }.to_string()
+    } else {
+        text
+    }
+}
+
+/// Runs `query` and writes its rows to `out` in `format`, as they're read.
+#[cfg(any(
+    feature = "mysql",
+    feature = "postgres",
+    feature = "sqlite",
+    feature = "mongo"
+))]
+pub(crate) fn write_export(
+    conn: &ConnectorType,
+    format: TableExportFormat,
+    xml: XmlExportOpts,
+    columns: Vec<String>,
+    query: &str,
+    params: &[ColumnValue],
+    out: &mut (dyn Write + Send),
+) -> Result<(), AppError> {
+    let mut writer = ExportWriter::new(format, xml, columns, out)?;
+    conn.for_each_row(query, params, &mut |row| writer.write_row(&row))?;
+
+    writer.finish()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use common::data::table::{TableExportFormat, XmlExportOpts};
+
+    use crate::row::ColumnValue;
+
+    use super::ExportWriter;
+
+    fn export(format: TableExportFormat, rows: &[Vec<ColumnValue>]) -> String {
+        let columns = vec!["id".to_string(), "name".to_string(), "score".to_string()];
+        export_columns(format, XmlExportOpts::default(), columns, rows)
+    }
+
+    fn export_columns(
+        format: TableExportFormat,
+        xml: XmlExportOpts,
+        columns: Vec<String>,
+        rows: &[Vec<ColumnValue>],
+    ) -> String {
+        let mut out = Vec::new();
+        let mut writer = ExportWriter::new(format, xml, columns, &mut out).unwrap();
+        for row in rows {
+            writer.write_values(row).unwrap();
+        }
+        writer.finish().unwrap();
+
+        String::from_utf8(out).unwrap()
+    }
+
+    fn rows() -> Vec<Vec<ColumnValue>> {
+        vec![
+            vec![
+                ColumnValue::Int(1),
+                ColumnValue::Text("Doe, \"Jane\"\n<b>".to_string()),
+                ColumnValue::Double(2.5),
+            ],
+            vec![
+                ColumnValue::UInt(2),
+                ColumnValue::Text("a\tb\\c".to_string()),
+                ColumnValue::NULL,
+            ],
+        ]
+    }
+
+    #[test]
+    fn test_export_formats() {
+        assert_eq!(
+            export(TableExportFormat::CSV, &rows()),
+            "id,name,score\r\n1,\"Doe, \"\"Jane\"\"\n<b>\",2.5\r\n2,a\tb\\c,\r\n"
+        );
+        assert_eq!(
+            export(TableExportFormat::TSV, &rows()),
+            "id\tname\tscore\n1\tDoe, \"Jane\"\\n<b>\t2.5\n2\ta\\tb\\\\c\t\n"
+        );
+        assert_eq!(
+            export(TableExportFormat::JSON, &rows()),
+            "[\n{\"id\":1,\"name\":\"Doe, \\\"Jane\\\"\\n<b>\",\"score\":2.5},\n\
+             {\"id\":2,\"name\":\"a\\tb\\\\c\",\"score\":null}\n]\n"
+        );
+        assert_eq!(export(TableExportFormat::JSON, &[]), "[]\n");
+        assert_eq!(
+            export(TableExportFormat::NDJSON, &rows()[1..]),
+            "{\"id\":2,\"name\":\"a\\tb\\\\c\",\"score\":null}\n"
+        );
+        assert_eq!(
+            export(TableExportFormat::HTML, &rows()[..1]),
+            "<table>\n<thead>\n<tr><th>id</th><th>name</th><th>score</th></tr>\n</thead>\n<tbody>\n\
+             <tr><td>1</td><td>Doe, &quot;Jane&quot;\n&lt;b&gt;</td><td>2.5</td></tr>\n\
+             </tbody>\n</table>\n"
+        );
+    }
+
+    #[test]
+    fn test_export_xml() {
+        let xml = XmlExportOpts {
+            root: Some("users".to_string()),
+            row: Some("user".to_string()),
+        };
+        let columns = vec![
+            "id".to_string(),
+            "2nd name".to_string(),
+            "score".to_string(),
+        ];
+        let rows = vec![vec![
+            ColumnValue::Int(1),
+            ColumnValue::Text("<Jane> & \"Jo\"\u{1}".to_string()),
+            ColumnValue::NULL,
+        ]];
+
+        assert_eq!(
+            export_columns(TableExportFormat::XML, xml, columns, &rows),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<users>\n  <user>\n\
+             \x20   <id type=\"integer\">1</id>\n\
+             \x20   <_2nd_name name=\"2nd name\" type=\"string\">&lt;Jane&gt; &amp; \"Jo\"\u{FFFD}</_2nd_name>\n\
+             \x20   <score null=\"true\"/>\n  </user>\n</users>\n"
+        );
+        assert_eq!(super::element_name("XmlData"), "_XmlData");
+        assert_eq!(super::element_name("prix_€"), "prix__");
+    }
+}
@@ -0,0 +1,312 @@
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+use arrow::{
+    array::{Array, ArrayRef, AsArray, RecordBatch},
+    datatypes::{
+        DataType, Float16Type, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type,
+        Int8Type, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+    },
+    error::ArrowError,
+    ipc::reader::{FileReader, StreamReader},
+    util::display::{ArrayFormatter, FormatOptions},
+};
+use common::error::AppError;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use rusqlite::types::Value;
+
+use crate::config::FileType;
+
+use super::{
+    reader::{invalid_file, unique_names},
+    table_name, ColumnKind, FileTable,
+};
+
+/// Hive writes NULL partition values under this name.
+const HIVE_NULL_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+fn extensions(file_type: &FileType) -> &'static [&'static str] {
+    match file_type {
+        FileType::Parquet => &["parquet", "parq"],
+        FileType::Arrow => &["arrow", "ipc", "feather"],
+        _ => &[],
+    }
+}
+
+fn is_data_file(path: &Path, file_type: &FileType) -> bool {
+    let hidden = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .is_some_and(|name| name.starts_with('.') || name.starts_with('_'));
+
+    let ext = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    !hidden && path.is_file() && extensions(file_type).contains(&ext.as_str())
+}
+
+/// Key and value of a Hive-style partition directory like `year=2024`.
+fn partition(dir: &Path) -> Option<(String, String)> {
+    let name = dir.file_name()?.to_string_lossy();
+    let (key, value) = name.split_once('=')?;
+
+    Some((key.to_string(), value.to_string()))
+}
+
+fn sorted_entries(dir: &Path) -> Result<Vec<PathBuf>, AppError> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|err| invalid_file(err.to_string()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .collect();
+    entries.sort();
+
+    Ok(entries)
+}
+
+/// Find data files under `dir`, along with the partition values taken from their path.
+fn collect_files(
+    dir: &Path,
+    file_type: &FileType,
+    partitions: &[(String, String)],
+    files: &mut Vec<(PathBuf, Vec<(String, String)>)>,
+) -> Result<(), AppError> {
+    for entry in sorted_entries(dir)? {
+        if entry.is_dir() {
+            let mut partitions = partitions.to_vec();
+            if let Some(p) = partition(&entry) {
+                partitions.push(p);
+            }
+
+            collect_files(&entry, file_type, &partitions, files)?;
+        } else if is_data_file(&entry, file_type) {
+            files.push((entry, partitions.to_vec()));
+        }
+    }
+
+    Ok(())
+}
+
+fn read_batches(path: &Path, file_type: &FileType) -> Result<Vec<RecordBatch>, AppError> {
+    let to_app_error = |err: String| invalid_file(format!("{}: {err}", path.display()));
+    let open = || File::open(path).map_err(|err| to_app_error(err.to_string()));
+
+    let batches: Result<Vec<RecordBatch>, ArrowError> = match file_type {
+        FileType::Parquet => ParquetRecordBatchReaderBuilder::try_new(open()?)
+            .and_then(|builder| builder.build())
+            .map_err(|err| to_app_error(err.to_string()))?
+            .collect(),
+
+        // Arrow data comes either in the IPC file format or as a stream.
+        _ => match FileReader::try_new(open()?, None) {
+            Ok(reader) => reader.collect(),
+            Err(_) => StreamReader::try_new(open()?, None)
+                .map_err(|err| to_app_error(err.to_string()))?
+                .collect(),
+        },
+    };
+
+    batches.map_err(|err| to_app_error(err.to_string()))
+}
+
+/// Position of `col` in `columns`, adding it if it's new.
+fn column_index(columns: &mut Vec<(String, &'static str)>, col: &str, sql_type: &'static str) -> usize {
+    columns.iter().position(|(c, _)| c == col).unwrap_or_else(|| {
+        columns.push((col.to_string(), sql_type));
+        columns.len() - 1
+    })
+}
+
+fn sql_type(data_type: &DataType) -> &'static str {
+    match data_type {
+        DataType::Boolean => ColumnKind::Boolean.sql_type(),
+        DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::UInt64 => ColumnKind::Integer.sql_type(),
+        DataType::Float16
+        | DataType::Float32
+        | DataType::Float64
+        | DataType::Decimal128(_, _)
+        | DataType::Decimal256(_, _) => ColumnKind::Real.sql_type(),
+        DataType::Date32 | DataType::Date64 => ColumnKind::Date.sql_type(),
+        DataType::Timestamp(_, _) => ColumnKind::DateTime.sql_type(),
+        _ => ColumnKind::Text.sql_type(),
+    }
+}
+
+fn cell(array: &ArrayRef, formatter: &ArrayFormatter, row: usize) -> Value {
+    if array.is_null(row) {
+        return Value::Null;
+    }
+
+    match array.data_type() {
+        DataType::Boolean => Value::Integer(array.as_boolean().value(row) as i64),
+        DataType::Int8 => Value::Integer(array.as_primitive::<Int8Type>().value(row).into()),
+        DataType::Int16 => Value::Integer(array.as_primitive::<Int16Type>().value(row).into()),
+        DataType::Int32 => Value::Integer(array.as_primitive::<Int32Type>().value(row).into()),
+        DataType::Int64 => Value::Integer(array.as_primitive::<Int64Type>().value(row)),
+        DataType::UInt8 => Value::Integer(array.as_primitive::<UInt8Type>().value(row).into()),
+        DataType::UInt16 => Value::Integer(array.as_primitive::<UInt16Type>().value(row).into()),
+        DataType::UInt32 => Value::Integer(array.as_primitive::<UInt32Type>().value(row).into()),
+        DataType::UInt64 => {
+            let v = array.as_primitive::<UInt64Type>().value(row);
+            i64::try_from(v).map_or(Value::Real(v as f64), Value::Integer)
+        }
+        DataType::Float16 => Value::Real(array.as_primitive::<Float16Type>().value(row).to_f64()),
+        DataType::Float32 => Value::Real(array.as_primitive::<Float32Type>().value(row).into()),
+        DataType::Float64 => Value::Real(array.as_primitive::<Float64Type>().value(row)),
+        DataType::Decimal128(_, _) | DataType::Decimal256(_, _) => formatter
+            .value(row)
+            .to_string()
+            .parse()
+            .map_or(Value::Null, Value::Real),
+        // SQLite date functions expect a space between date and time.
+        DataType::Date64 | DataType::Timestamp(_, _) => {
+            Value::Text(formatter.value(row).to_string().replacen('T', " ", 1))
+        }
+        _ => Value::Text(formatter.value(row).to_string()),
+    }
+}
+
+/// Read a single file, or every file under a directory, into one table. Partition keys found
+/// in the directory names become columns of their own.
+fn read_dataset(root: &Path, name: String, file_type: &FileType) -> Result<FileTable, AppError> {
+    let mut files = Vec::new();
+    if root.is_dir() {
+        collect_files(root, file_type, &[], &mut files)?;
+    } else {
+        files.push((root.to_path_buf(), vec![]));
+    }
+
+    let mut columns: Vec<(String, &'static str)> = Vec::new();
+    let mut partition_cols: Vec<usize> = Vec::new();
+    let mut rows: Vec<Vec<Value>> = Vec::new();
+
+    for (path, partitions) in files {
+        for batch in read_batches(&path, file_type)? {
+            let schema = batch.schema();
+            let options = FormatOptions::default();
+
+            let mut targets = Vec::with_capacity(batch.num_columns());
+            for (field, array) in schema.fields().iter().zip(batch.columns()) {
+                let idx = column_index(&mut columns, field.name(), sql_type(field.data_type()));
+                let formatter = ArrayFormatter::try_new(array.as_ref(), &options)
+                    .map_err(|err| invalid_file(err.to_string()))?;
+
+                targets.push((idx, array, formatter));
+            }
+
+            let mut partition_targets = Vec::with_capacity(partitions.len());
+            for (key, value) in &partitions {
+                let idx = column_index(&mut columns, key, ColumnKind::Text.sql_type());
+                if !partition_cols.contains(&idx) {
+                    partition_cols.push(idx);
+                }
+
+                partition_targets.push((idx, value));
+            }
+
+            for row in 0..batch.num_rows() {
+                let mut values = vec![Value::Null; columns.len()];
+
+                for (idx, array, formatter) in &targets {
+                    values[*idx] = cell(array, formatter, row);
+                }
+
+                for (idx, value) in &partition_targets {
+                    if value.as_str() != HIVE_NULL_PARTITION {
+                        values[*idx] = Value::Text(value.to_string());
+                    }
+                }
+
+                rows.push(values);
+            }
+        }
+    }
+
+    // Columns that only show up in later files are missing from earlier rows.
+    for row in rows.iter_mut() {
+        row.resize(columns.len(), Value::Null);
+    }
+
+    // Partition values are plain text in directory names, so their type is inferred.
+    for idx in partition_cols {
+        let kind = ColumnKind::infer(rows.iter().filter_map(|row| match &row[idx] {
+            Value::Text(v) => Some(v.as_str()),
+            _ => None,
+        }));
+
+        for row in rows.iter_mut() {
+            if let Value::Text(v) = &row[idx] {
+                row[idx] = kind.to_sql_value(&v.clone());
+            }
+        }
+
+        columns[idx].1 = kind.sql_type();
+    }
+
+    Ok(FileTable {
+        name,
+        columns,
+        rows,
+    })
+}
+
+/// Read Parquet or Arrow IPC data at `path`.
+///
+/// A file becomes a single table. A directory partitioned Hive-style (`key=value` subdirectories)
+/// is read as one table, otherwise each data file and each subdirectory is its own table.
+pub(crate) fn read_tables(path: &str, file_type: &FileType) -> Result<Vec<FileTable>, AppError> {
+    let path = Path::new(path);
+
+    if path.is_file() {
+        return Ok(vec![read_dataset(path, table_name(path), file_type)?]);
+    }
+
+    if !path.is_dir() {
+        return Err(invalid_file(format!("{} does not exist", path.display())));
+    }
+
+    let entries = sorted_entries(path)?;
+    let is_partitioned = entries
+        .iter()
+        .any(|entry| entry.is_dir() && partition(entry).is_some());
+
+    if is_partitioned {
+        return Ok(vec![read_dataset(path, table_name(path), file_type)?]);
+    }
+
+    let sources: Vec<&PathBuf> = entries
+        .iter()
+        .filter(|entry| entry.is_dir() || is_data_file(entry, file_type))
+        .collect();
+
+    let names = unique_names(sources.iter().map(|entry| table_name(entry)).collect());
+
+    let mut tables = Vec::with_capacity(sources.len());
+    for (entry, name) in sources.into_iter().zip(names) {
+        let table = read_dataset(entry, name, file_type)?;
+
+        // Skip directories that hold no data files.
+        if !table.columns.is_empty() {
+            tables.push(table);
+        }
+    }
+
+    if tables.is_empty() {
+        return Err(invalid_file(format!(
+            "No data files found in {}",
+            path.display()
+        )));
+    }
+
+    Ok(tables)
+}
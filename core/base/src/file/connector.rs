@@ -0,0 +1,139 @@
+use axum::http::StatusCode;
+use common::error::AppError;
+use crate::row::{ColumnValue, Row};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params_from_iter, Connection};
+
+use crate::{
+    config::{ConfigRaw, SourceType},
+    connector::{Connector, Statement, TransactionError},
+    sqlite::{
+        connector::{for_each_pool_row, query_pool, register_regexp, transaction_pool},
+        quote_ident,
+    },
+};
+
+use super::{read_tables, FileTable};
+
+/// Connector for data files (CSV, TSV, NDJSON, Parquet and Arrow IPC).
+///
+/// The file is read once and loaded into an in-memory SQLite database, so tables, filters and
+/// graphs work the same way as for the `sqlite` source. The loaded data is read-only.
+#[derive(Clone)]
+pub struct FileConnector {
+    /// Pool holding the single in-memory database connection
+    pub pool: Pool<SqliteConnectionManager>,
+
+    /// Connection options
+    pub config: ConfigRaw,
+}
+
+fn load(conn: &mut Connection, tables: Vec<FileTable>) -> Result<(), rusqlite::Error> {
+    let tx = conn.transaction()?;
+
+    for table in tables {
+        let FileTable {
+            name,
+            columns,
+            rows,
+        } = table;
+
+        let definitions: Vec<String> = columns
+            .iter()
+            .map(|(col, sql_type)| format!("{} {sql_type}", quote_ident(col)))
+            .collect();
+
+        tx.execute(
+            &format!("CREATE TABLE {} ({})", quote_ident(&name), definitions.join(", ")),
+            [],
+        )?;
+
+        let names: Vec<String> = columns.iter().map(|(col, _)| quote_ident(col)).collect();
+        let placeholders = vec!["?"; columns.len()];
+
+        let mut stmt = tx.prepare(&format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            quote_ident(&name),
+            names.join(", "),
+            placeholders.join(", ")
+        ))?;
+
+        for row in rows {
+            stmt.execute(params_from_iter(row))?;
+        }
+    }
+
+    tx.commit()
+}
+
+impl Connector for FileConnector {
+    type Row = Row;
+
+    fn new(config: ConfigRaw) -> Result<Self, AppError> {
+        let path = config.build_url()?;
+
+        let SourceType::File(file_type) = config.get_source()? else {
+            return Err(AppError::HttpError(
+                StatusCode::EXPECTATION_FAILED,
+                "Invalid file source type".to_string(),
+            ));
+        };
+
+        let tables = read_tables(&path, &file_type)?;
+        if tables.iter().any(|t| t.columns.is_empty()) {
+            return Err(AppError::HttpError(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "The file has no columns".to_string(),
+            ));
+        }
+
+        // Every connection to `:memory:` opens a separate database, so the pool must keep
+        // exactly one connection alive for as long as the connector lives.
+        let manager = SqliteConnectionManager::memory().with_init(register_regexp);
+        let pool = Pool::builder()
+            .max_size(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .build(manager)
+            .map_err(|err| AppError::ServerError(err.to_string()))?;
+
+        let to_app_error = |err: rusqlite::Error| AppError::ServerError(err.to_string());
+
+        let mut conn = pool
+            .get()
+            .map_err(|err| AppError::ServerError(err.to_string()))?;
+
+        load(&mut conn, tables).map_err(to_app_error)?;
+        conn.execute_batch("PRAGMA query_only = ON")
+            .map_err(to_app_error)?;
+
+        drop(conn);
+
+        Ok(FileConnector { pool, config })
+    }
+
+    fn exec_query(&self, query: &str, params: &[ColumnValue]) -> Result<Vec<Self::Row>, AppError> {
+        query_pool(&self.pool, query, params)
+    }
+
+    fn for_each_row(
+        &self,
+        query: &str,
+        params: &[ColumnValue],
+        f: &mut (dyn FnMut(Self::Row) -> Result<(), AppError> + Send),
+    ) -> Result<(), AppError> {
+        for_each_pool_row(&self.pool, query, params, f)
+    }
+
+    fn exec_transaction(
+        &self,
+        statements: &[Statement],
+    ) -> Result<Vec<Vec<Self::Row>>, TransactionError> {
+        transaction_pool(&self.pool, statements)
+    }
+
+    fn config(&self) -> &ConfigRaw {
+        &self.config
+    }
+}
@@ -0,0 +1,154 @@
+use std::path::Path;
+
+use common::{
+    data::table::TableSummaries,
+    error::AppError,
+    query::{
+        filter::Filter,
+        BasableQuery,
+    },
+    DbServerDetails,
+};
+use crate::row::{ColumnValue, Row};
+use uuid::Uuid;
+
+use crate::{
+    db::{QueryParams, QuerySqlParser, DB},
+    graphs::{
+        category::CategoryGraphOpts, chrono::ChronoAnalysisOpts, geo::GeoGraphOpts,
+        trend::TrendGraphOpts, AnalysisResults, VisualizeDB,
+    },
+    sqlite::db::SqliteDB,
+    ConnectorType, SharedTable,
+};
+
+/// A data file (or directory of files) loaded through [`FileConnector`](super::connector::FileConnector).
+///
+/// Queries run against the in-memory copy of the file, so this delegates to [`SqliteDB`] and
+/// only reports details about the file itself.
+pub struct FileDB {
+    inner: SqliteDB,
+}
+
+impl FileDB {
+    pub fn new(connector: ConnectorType, user_id: String) -> Self {
+        FileDB {
+            inner: SqliteDB::new(connector, user_id),
+        }
+    }
+}
+
+/// Size in bytes of a file, or of every file under a directory.
+fn disk_size(path: &Path) -> u64 {
+    if path.is_dir() {
+        std::fs::read_dir(path)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| disk_size(&entry.path()))
+                    .sum()
+            })
+            .unwrap_or_default()
+    } else {
+        std::fs::metadata(path).map(|meta| meta.len()).unwrap_or_default()
+    }
+}
+
+impl DB for FileDB {
+    type Row = Row;
+
+    fn id(&self) -> &Uuid {
+        self.inner.id()
+    }
+
+    fn set_id(&mut self, id: Uuid) {
+        self.inner.set_id(id);
+    }
+
+    fn user_id(&self) -> &str {
+        self.inner.user_id()
+    }
+
+    fn connector(&self) -> &ConnectorType {
+        self.inner.connector()
+    }
+
+    fn load_tables(&mut self, connector: ConnectorType) -> Result<(), AppError> {
+        self.inner.load_tables(connector)
+    }
+
+    fn tables(&self) -> &Vec<SharedTable> {
+        self.inner.tables()
+    }
+
+    fn query_tables(&self) -> Result<Vec<Row>, AppError> {
+        self.inner.query_tables()
+    }
+
+    fn build_table_list(&self) -> Result<TableSummaries, AppError> {
+        self.inner.build_table_list()
+    }
+
+    fn query_column_count(&self, tb_name: &str) -> Result<u32, AppError> {
+        self.inner.query_column_count(tb_name)
+    }
+
+    fn get_table(&self, name: &str) -> Option<&SharedTable> {
+        self.inner.get_table(name)
+    }
+
+    fn details(&self) -> Result<DbServerDetails, AppError> {
+        let config = self.connector().config();
+        let path = config.path.clone().unwrap_or_default();
+
+        // db size is returned in MB
+        let size = disk_size(Path::new(&path)) as f64 / 1024.0 / 1024.0;
+        let size = (size * 10.0).round() / 10.0;
+
+        Ok(DbServerDetails {
+            version: String::new(),
+            os: std::env::consts::OS.to_string(),
+            comment: Some(format!("{} file {path}", config.source.to_uppercase())),
+            db_size: size,
+        })
+    }
+
+    fn drop_table(&self, _: &str) -> Result<(), AppError> {
+        Err(AppError::HttpError(
+            axum::http::StatusCode::FORBIDDEN,
+            "File sources are read-only".to_string(),
+        ))
+    }
+}
+
+impl QuerySqlParser for FileDB {
+    fn quote_ident(&self, name: &str) -> String {
+        self.inner.quote_ident(name)
+    }
+
+    fn parse_filter(&self, filter: &Filter, params: &mut QueryParams) -> String {
+        self.inner.parse_filter(filter, params)
+    }
+
+    fn generate_sql(&self, query: BasableQuery) -> Result<(String, Vec<ColumnValue>), AppError> {
+        self.inner.generate_sql(query)
+    }
+}
+
+impl VisualizeDB for FileDB {
+    fn chrono_graph(&self, opts: ChronoAnalysisOpts) -> Result<AnalysisResults, AppError> {
+        self.inner.chrono_graph(opts)
+    }
+
+    fn trend_graph(&self, opts: TrendGraphOpts) -> Result<AnalysisResults, AppError> {
+        self.inner.trend_graph(opts)
+    }
+
+    fn category_graph(&self, opts: CategoryGraphOpts) -> Result<AnalysisResults, AppError> {
+        self.inner.category_graph(opts)
+    }
+
+    fn geo_graph(&self, opts: GeoGraphOpts) -> Result<AnalysisResults, AppError> {
+        self.inner.geo_graph(opts)
+    }
+}
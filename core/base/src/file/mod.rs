@@ -0,0 +1,156 @@
+use std::{path::Path, sync::OnceLock};
+
+use common::error::AppError;
+use regex::Regex;
+use rusqlite::types::Value;
+
+use crate::config::FileType;
+
+pub(crate) mod columnar;
+pub mod connector;
+pub mod db;
+pub(crate) mod reader;
+
+/// A table read from a file, ready to be loaded into SQLite.
+pub(crate) struct FileTable {
+    pub name: String,
+
+    /// Column names with their SQLite type
+    pub columns: Vec<(String, &'static str)>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// Name of the table a file is loaded into, taken from the file name.
+pub(crate) fn table_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .filter(|stem| !stem.is_empty())
+        .unwrap_or_else(|| "data".to_string())
+}
+
+/// Read the tables held at `path`. Text formats hold a single table, while columnar formats
+/// may point at a directory of files.
+pub(crate) fn read_tables(path: &str, file_type: &FileType) -> Result<Vec<FileTable>, AppError> {
+    let data = match file_type {
+        FileType::Csv => reader::read_delimited(path, b',', true)?,
+        FileType::Tsv => reader::read_delimited(path, b'\t', false)?,
+        FileType::Ndjson => reader::read_ndjson(path)?,
+        FileType::Parquet | FileType::Arrow => return columnar::read_tables(path, file_type),
+    };
+
+    Ok(vec![data.into_table(table_name(Path::new(path)))])
+}
+
+/// The type of a file column, inferred from the values it holds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ColumnKind {
+    Integer,
+    Real,
+    Boolean,
+    Date,
+    DateTime,
+    Text,
+}
+
+fn date_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^\d{4}-\d{2}-\d{2}$").unwrap())
+}
+
+fn datetime_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^\d{4}-\d{2}-\d{2}[ T]\d{2}:\d{2}(:\d{2}(\.\d+)?)?(Z|[+-]\d{2}:?\d{2})?$")
+            .unwrap()
+    })
+}
+
+impl ColumnKind {
+    /// Column type used when the file is loaded into SQLite.
+    pub(crate) fn sql_type(&self) -> &'static str {
+        match self {
+            ColumnKind::Integer => "INTEGER",
+            ColumnKind::Real => "REAL",
+            ColumnKind::Boolean => "BOOLEAN",
+            ColumnKind::Date => "DATE",
+            ColumnKind::DateTime => "DATETIME",
+            ColumnKind::Text => "TEXT",
+        }
+    }
+
+    fn of(value: &str) -> Self {
+        // Values like zip codes ("007") would lose their leading zeros as numbers.
+        let leading_zero = value.len() > 1 && value.starts_with('0') && !value.starts_with("0.");
+
+        if !leading_zero && value.parse::<i64>().is_ok() {
+            ColumnKind::Integer
+        } else if !leading_zero && value.parse::<f64>().is_ok_and(|v| v.is_finite()) {
+            ColumnKind::Real
+        } else if value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false") {
+            ColumnKind::Boolean
+        } else if date_regex().is_match(value) {
+            ColumnKind::Date
+        } else if datetime_regex().is_match(value) {
+            ColumnKind::DateTime
+        } else {
+            ColumnKind::Text
+        }
+    }
+
+    fn merge(self, other: Self) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (ColumnKind::Integer, ColumnKind::Real) | (ColumnKind::Real, ColumnKind::Integer) => {
+                ColumnKind::Real
+            }
+            (ColumnKind::Date, ColumnKind::DateTime) | (ColumnKind::DateTime, ColumnKind::Date) => {
+                ColumnKind::DateTime
+            }
+            _ => ColumnKind::Text,
+        }
+    }
+
+    /// Infer the narrowest kind that fits every value. Empty values are treated as NULL
+    /// and don't take part; a column with no values at all is text.
+    pub(crate) fn infer<'a>(values: impl Iterator<Item = &'a str>) -> Self {
+        values
+            .filter(|v| !v.is_empty())
+            .map(ColumnKind::of)
+            .reduce(ColumnKind::merge)
+            .unwrap_or(ColumnKind::Text)
+    }
+
+    /// Convert a raw file value into the SQLite value stored for this kind.
+    pub(crate) fn to_sql_value(self, value: &str) -> Value {
+        if value.is_empty() {
+            return Value::Null;
+        }
+
+        match self {
+            ColumnKind::Integer => value.parse().map_or(Value::Null, Value::Integer),
+            ColumnKind::Real => value.parse().map_or(Value::Null, Value::Real),
+            ColumnKind::Boolean => Value::Integer(value.eq_ignore_ascii_case("true") as i64),
+            // SQLite date functions expect a space between date and time.
+            ColumnKind::DateTime => Value::Text(value.replacen('T', " ", 1)),
+            ColumnKind::Date | ColumnKind::Text => Value::Text(value.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::ColumnKind;
+
+    #[test]
+    fn test_infer_column_kind() {
+        let infer = |values: &[&str]| ColumnKind::infer(values.iter().copied());
+
+        assert_eq!(infer(&["1", "-20", ""]), ColumnKind::Integer);
+        assert_eq!(infer(&["1", "2.5"]), ColumnKind::Real);
+        assert_eq!(infer(&["007", "12"]), ColumnKind::Text);
+        assert_eq!(infer(&["TRUE", "false"]), ColumnKind::Boolean);
+        assert_eq!(infer(&["2024-01-31", "2024-02-01T10:30:00Z"]), ColumnKind::DateTime);
+        assert_eq!(infer(&["2024-01-31", "soon"]), ColumnKind::Text);
+        assert_eq!(infer(&["", ""]), ColumnKind::Text);
+    }
+}
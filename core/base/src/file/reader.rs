@@ -0,0 +1,161 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+};
+
+use axum::http::StatusCode;
+use common::error::AppError;
+use serde_json::Value;
+
+use super::{ColumnKind, FileTable};
+
+/// Raw contents of a data file: column names and one entry per row. `None` marks a value
+/// that is missing from the row.
+pub(crate) struct FileData {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Option<String>>>,
+}
+
+impl FileData {
+    /// Infer the type of each column and convert the values accordingly.
+    pub(crate) fn into_table(self, name: String) -> FileTable {
+        let FileData { columns, rows } = self;
+
+        let kinds: Vec<ColumnKind> = (0..columns.len())
+            .map(|idx| ColumnKind::infer(rows.iter().filter_map(|r| r[idx].as_deref())))
+            .collect();
+
+        let rows = rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(&kinds)
+                    .map(|(value, kind)| kind.to_sql_value(value.as_deref().unwrap_or_default()))
+                    .collect()
+            })
+            .collect();
+
+        let columns = columns
+            .into_iter()
+            .zip(&kinds)
+            .map(|(col, kind)| (col, kind.sql_type()))
+            .collect();
+
+        FileTable {
+            name,
+            columns,
+            rows,
+        }
+    }
+}
+
+pub(crate) fn invalid_file(msg: String) -> AppError {
+    AppError::HttpError(StatusCode::UNPROCESSABLE_ENTITY, msg)
+}
+
+/// Give blank and repeated names (columns, tables) a usable name.
+pub(crate) fn unique_names(headers: Vec<String>) -> Vec<String> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    headers
+        .into_iter()
+        .enumerate()
+        .map(|(idx, header)| {
+            let header = header.trim();
+            let name = if header.is_empty() {
+                format!("column_{}", idx + 1)
+            } else {
+                header.to_string()
+            };
+
+            let count = seen.entry(name.clone()).or_default();
+            *count += 1;
+
+            if *count > 1 {
+                format!("{name}_{count}")
+            } else {
+                name
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn read_delimited(path: &str, delimiter: u8, quoting: bool) -> Result<FileData, AppError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .quoting(quoting)
+        .flexible(true)
+        .from_path(path)
+        .map_err(|err| invalid_file(err.to_string()))?;
+
+    let headers = reader
+        .headers()
+        .map_err(|err| invalid_file(err.to_string()))?
+        .iter()
+        .map(|h| h.to_string())
+        .collect();
+    let columns = unique_names(headers);
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|err| invalid_file(err.to_string()))?;
+
+        let row = (0..columns.len())
+            .map(|idx| record.get(idx).map(|v| v.to_string()))
+            .collect();
+        rows.push(row);
+    }
+
+    Ok(FileData { columns, rows })
+}
+
+pub(crate) fn read_ndjson(path: &str) -> Result<FileData, AppError> {
+    let file = File::open(path).map_err(|err| invalid_file(err.to_string()))?;
+    let reader = BufReader::new(file);
+
+    let mut columns: Vec<String> = Vec::new();
+    let mut objects = Vec::new();
+
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line.map_err(|err| invalid_file(err.to_string()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let value: Value = serde_json::from_str(&line)
+            .map_err(|err| invalid_file(format!("line {}: {err}", idx + 1)))?;
+
+        let Value::Object(object) = value else {
+            return Err(invalid_file(format!(
+                "line {}: expected a JSON object",
+                idx + 1
+            )));
+        };
+
+        for key in object.keys() {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+
+        objects.push(object);
+    }
+
+    let rows = objects
+        .iter()
+        .map(|object| {
+            columns
+                .iter()
+                .map(|col| match object.get(col) {
+                    None | Some(Value::Null) => None,
+                    Some(Value::String(s)) => Some(s.clone()),
+                    // Numbers, booleans and nested values keep their JSON text.
+                    Some(v) => Some(v.to_string()),
+                })
+                .collect()
+        })
+        .collect();
+
+    Ok(FileData { columns, rows })
+}
@@ -1,10 +1,11 @@
 use std::{collections::HashMap, fmt::Display};
 
 use axum::http::StatusCode;
-use common::{error::AppError, query::{BasableQuery, QueryCommand}};
+use common::error::AppError;
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 use super::FromQueryParams;
+use crate::DbType;
 
 #[derive(EnumIter)]
 pub enum CategoryAnalysis {
@@ -51,6 +52,13 @@ pub struct CategoryGraphOpts {
     pub limit: Option<usize>,
 }
 
+impl CategoryGraphOpts {
+    /// Check that the table and column exist in `db`.
+    pub fn check(&self, db: &DbType) -> Result<(), AppError> {
+        db.check_columns(&self.table, &[&self.target_column])
+    }
+}
+
 impl FromQueryParams for CategoryGraphOpts {
     fn from_query_params(params: HashMap<String, String>) -> Result<Self, AppError>
     where
@@ -89,25 +97,3 @@ impl FromQueryParams for CategoryGraphOpts {
         }
     }
 }
-
-impl From<CategoryGraphOpts> for BasableQuery {
-    fn from(value: CategoryGraphOpts) -> Self {
-        let CategoryGraphOpts {
-            table,
-            analysis,
-            target_column,
-            limit,
-        } = value;
-
-        let selections = vec!["COUNT(*) as COUNT".to_string(), target_column.clone()];
-        let operation = QueryCommand::SelectData(Some(selections));
-
-        BasableQuery {
-            table,
-            command: operation,
-            group_by: Some(vec![target_column]),
-            row_count: limit,
-            ..Default::default()
-        }
-    }
-}
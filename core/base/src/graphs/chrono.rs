@@ -1,11 +1,11 @@
 use std::{collections::HashMap, fmt::Display};
 
 use axum::http::StatusCode;
-use common::{error::AppError, query::{filter::{Filter, FilterChain, FilterCombinator, FilterExpression}, BasableQuery, QueryCommand, QueryOrder}};
+use common::error::AppError;
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 use super::FromQueryParams;
-use crate::globals::BASABLE_CHRONO_XCOL;
+use crate::DbType;
 
 #[derive(Clone, EnumIter)]
 pub enum ChronoAnalysisBasis {
@@ -65,8 +65,8 @@ impl TryFrom<String> for ChronoAnalysisRange {
     fn try_from(value: String) -> Result<Self, Self::Error> {
         let spl: Vec<&str> = value.split("range").collect();
         if spl.len() == 2 {
-            let start = spl.get(0).unwrap_or(&&"");
-            let end = spl.get(1).unwrap_or(&&"");
+            let start = spl.first().unwrap_or(&"");
+            let end = spl.get(1).unwrap_or(&"");
 
             let range = ChronoAnalysisRange(start.trim().to_string(), end.trim().to_string());
             return Ok(range);
@@ -83,6 +83,13 @@ pub struct ChronoAnalysisOpts {
     pub range: ChronoAnalysisRange,
 }
 
+impl ChronoAnalysisOpts {
+    /// Check that the table and column exist in `db`.
+    pub fn check(&self, db: &DbType) -> Result<(), AppError> {
+        db.check_columns(&self.table, &[&self.chrono_col])
+    }
+}
+
 impl FromQueryParams for ChronoAnalysisOpts {
     fn from_query_params(params: HashMap<String, String>) -> Result<Self, AppError>
     where
@@ -125,47 +132,3 @@ impl FromQueryParams for ChronoAnalysisOpts {
         }
     }
 }
-
-impl From<ChronoAnalysisOpts> for BasableQuery {
-    fn from(value: ChronoAnalysisOpts) -> Self {
-        let ChronoAnalysisOpts {
-            table,
-            chrono_col,
-            basis,
-            range,
-        } = value;
-
-        // create query operation type
-        let selections = Some(vec![
-            format!("{basis}({chrono_col}) AS {BASABLE_CHRONO_XCOL}"),
-            format!("COUNT(*) AS {BASABLE_CHRONO_XCOL}"),
-        ]);
-
-        let operation = QueryCommand::SelectData(selections);
-
-        // create query filters
-        let filter = Filter {
-            combinator: FilterCombinator::BASE,
-            column: chrono_col.clone(),
-            expression: FilterExpression::Btw(range.start().to_string(), range.end().to_string()),
-        };
-
-        let mut filters = FilterChain::new();
-        filters.add_one(filter);
-
-        // creating grouping
-        let group_columns = vec![format!("{basis}({chrono_col})")];
-        let group_by = Some(group_columns);
-
-        let order_by = Some(QueryOrder::ASC(BASABLE_CHRONO_XCOL.to_string()));
-
-        BasableQuery {
-            table,
-            filters,
-            command: operation,
-            group_by,
-            order_by,
-            ..Default::default()
-        }
-    }
-}
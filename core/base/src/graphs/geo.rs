@@ -1,11 +1,12 @@
 use std::{collections::HashMap, fmt::Display};
 
 use axum::http::StatusCode;
-use common::{error::AppError, query::{BasableQuery, QueryCommand}};
+use common::error::AppError;
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
 use super::FromQueryParams;
+use crate::DbType;
 
 #[derive(EnumIter)]
 pub enum GeoGraphScope {
@@ -54,22 +55,10 @@ pub struct GeoGraphOpts {
     pub target_column: String,
 }
 
-impl From<GeoGraphOpts> for BasableQuery {
-    fn from(value: GeoGraphOpts) -> Self {
-        let GeoGraphOpts {
-            table,
-            scope,
-            target_column,
-        } = value;
-
-        let selections = vec!["COUNT(*) as COUNT".to_string(), target_column];
-        let operation = QueryCommand::SelectData(Some(selections));
-
-        BasableQuery {
-            table,
-            command: operation,
-            ..Default::default()
-        }
+impl GeoGraphOpts {
+    /// Check that the table and column exist in `db`.
+    pub fn check(&self, db: &DbType) -> Result<(), AppError> {
+        db.check_columns(&self.table, &[&self.target_column])
     }
 }
 
@@ -8,11 +8,12 @@ use category::CategoryGraphOpts;
 use chrono::ChronoAnalysisOpts;
 use common::error::AppError;
 use geo::GeoGraphOpts;
-use mysql::Value as MysqlValue;
 use serde::{ser::SerializeTuple, Serialize};
 use time::Date;
 use trend::TrendGraphOpts;
 
+use crate::row::ColumnValue;
+
 pub mod category;
 pub mod chrono;
 pub mod geo;
@@ -69,32 +70,27 @@ impl Display for AnalysisValue {
     }
 }
 
-impl TryFrom<MysqlValue> for AnalysisValue {
+impl TryFrom<ColumnValue> for AnalysisValue {
     type Error = AppError;
 
-    fn try_from(value: MysqlValue) -> Result<Self, Self::Error> {
+    fn try_from(value: ColumnValue) -> Result<Self, Self::Error> {
         let s = match value {
-            MysqlValue::NULL => AnalysisValue::NULL,
-            MysqlValue::Bytes(v) => {
-                let v = String::from_utf8(v).map_err(|err| {
-                    AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
-                })?;
-                AnalysisValue::Text(v)
-            }
-            MysqlValue::UInt(v) => {
+            ColumnValue::NULL => AnalysisValue::NULL,
+            ColumnValue::Text(v) => AnalysisValue::Text(v),
+            ColumnValue::UInt(v) => {
                 let v = usize::try_from(v).map_err(|err| {
                     AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
                 })?;
                 AnalysisValue::UInt(v)
             }
-            MysqlValue::Int(v) => {
+            ColumnValue::Int(v) => {
                 let v = isize::try_from(v).map_err(|err| {
                     AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
                 })?;
                 AnalysisValue::Int(v)
             }
-            MysqlValue::Float(v) => AnalysisValue::Float(v),
-            MysqlValue::Double(v) => AnalysisValue::Double(v),
+            ColumnValue::Float(v) => AnalysisValue::Float(v),
+            ColumnValue::Double(v) => AnalysisValue::Double(v),
             _ => AnalysisValue::NULL,
         };
 
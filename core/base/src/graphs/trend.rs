@@ -1,11 +1,12 @@
 use std::{collections::HashMap, fmt::Display};
 
 use axum::http::StatusCode;
-use common::{error::AppError, query::{filter::{Filter, FilterChain, FilterCombinator, FilterExpression}, BasableQuery, QueryCommand, QueryOrder}};
+use common::error::AppError;
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
 use super::FromQueryParams;
+use crate::DbType;
 
 #[derive(Clone)]
 pub enum TrendGraphType {
@@ -96,6 +97,19 @@ pub struct TrendGraphOpts {
     pub cross: Option<CrossOptions>,
 }
 
+impl TrendGraphOpts {
+    /// Check that the tables and columns exist in `db`.
+    pub fn check(&self, db: &DbType) -> Result<(), AppError> {
+        match &self.cross {
+            Some(cross) => {
+                db.check_columns(&self.table, &[&self.xcol, &cross.target_col])?;
+                db.check_columns(&cross.foreign_table, &[&self.ycol])
+            }
+            None => db.check_columns(&self.table, &[&self.xcol, &self.ycol]),
+        }
+    }
+}
+
 impl FromQueryParams for TrendGraphOpts {
     fn from_query_params(params: HashMap<String, String>) -> Result<Self, AppError> {
         let table = params.get("table");
@@ -152,9 +166,7 @@ impl FromQueryParams for TrendGraphOpts {
                 }
 
                 // if insufficient parameters are supplied for cross analysis, return error
-                if let Err(err) = cross_err {
-                    return Err(err);
-                }
+                cross_err?;
 
                 let opts = TrendGraphOpts {
                     table: String::from(table),
@@ -178,100 +190,3 @@ impl FromQueryParams for TrendGraphOpts {
         }
     }
 }
-
-impl TryFrom<TrendGraphOpts> for BasableQuery {
-    type Error = AppError;
-
-    fn try_from(value: TrendGraphOpts) -> Result<Self, Self::Error> {
-        let TrendGraphOpts {
-            table,
-            graph_type: analysis_type,
-            xcol,
-            ycol,
-            order,
-            limit,
-            cross,
-        } = value;
-
-        match analysis_type {
-            TrendGraphType::IntraModel => {
-                let operation = QueryCommand::SelectData(Some(vec![xcol, ycol.clone()]));
-
-                let order = match order {
-                    Some(order) => match order {
-                        TrendGraphOrder::DESC => QueryOrder::DESC(ycol),
-                        TrendGraphOrder::ASC => QueryOrder::ASC(ycol),
-                    },
-                    None => QueryOrder::DESC(ycol),
-                };
-
-                let order_by = Some(order);
-
-                let q = BasableQuery {
-                    table,
-                    command: operation,
-                    order_by,
-                    row_count: limit,
-                    ..Default::default()
-                };
-
-                Ok(q)
-            }
-
-            TrendGraphType::CrossModel => match cross {
-                Some(cross) => {
-                    let CrossOptions {
-                        foreign_table,
-                        target_col,
-                    } = cross;
-
-                    let select_columns = vec![
-                        format!("x.{xcol} AS {xcol}"),
-                        format!("COUNT(y.{ycol}) AS {ycol}"),
-                    ];
-
-                    let operation = QueryCommand::SelectData(Some(select_columns));
-                    let left_join = format!("{foreign_table} y ON x.{target_col} = y.{ycol}");
-
-                    let mut having = FilterChain::new();
-                    having.add_one(Filter{
-                        combinator: FilterCombinator::BASE,
-                        column: ycol.clone(),
-                        expression: FilterExpression::Gt("0".to_string())
-                    });
-
-                    let order = match order {
-                        Some(order) => match order {
-                            TrendGraphOrder::DESC => QueryOrder::DESC(ycol),
-                            TrendGraphOrder::ASC => QueryOrder::ASC(ycol),
-                        },
-                        None => QueryOrder::DESC(ycol),
-                    };
-
-                    let order_by = Some(order);
-
-                    let q = BasableQuery {
-                        command: operation,
-                        having,
-                        table: format!("{table} x"),
-                        left_join: Some(left_join),
-                        group_by: Some(vec![xcol]),
-                        order_by,
-                        row_count: limit,
-                        ..Default::default()
-                    };
-
-                    Ok(q)
-                }
-                None => {
-                    let err = AppError::HttpError(
-                        StatusCode::EXPECTATION_FAILED,
-                        "You must provide cross model options.".to_string(),
-                    );
-
-                    Err(err)
-                }
-            },
-        }
-    }
-}
@@ -4,31 +4,42 @@ use connector::Connector;
 use db::DB;
 use table::Table;
 
-use mysql::{connector::MysqlConnector, db::MySqlDB, table::MySqlTable};
+use row::Row;
 
 
 pub mod db;
 pub mod graphs;
 pub mod connector;
 pub mod table;
+pub mod export;
 pub mod config;
+pub mod row;
+pub mod registry;
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite", feature = "mongo"))]
 mod globals;
 
-// we need to find a way to seperate mysql as an independent plugin
+// Backends, each behind the cargo feature of the same name.
+#[cfg(feature = "mysql")]
 pub mod mysql;
+#[cfg(feature = "mariadb")]
+pub mod mariadb;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "file")]
+pub mod file;
+#[cfg(feature = "mongo")]
+pub mod mongo;
 
 /// Dynamic [`DB`] type to be implemented across the app.
-pub type DbType = dyn DB<
-    Row = <MySqlDB as DB>::Row,
->;
+pub type DbType = dyn DB<Row = Row>;
 
 /// Dynamic [`Connector`] type implemented across the app.
-pub type ConnectorType = Arc<dyn Connector<Row = <MysqlConnector as Connector>::Row>>;
+pub type ConnectorType = Arc<dyn Connector<Row = Row>>;
 
 /// Dynamic [`Table`] type implemented across the app.
-pub type TableType = dyn Table<
-    Row = <MySqlTable as Table>::Row,
->;
+pub type TableType = dyn Table<Row = Row>;
 
 /// A thread-safe sharable DB instance
 pub type SharedDB = Arc<DbType>;
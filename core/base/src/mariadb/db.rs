@@ -0,0 +1,169 @@
+use std::sync::Arc;
+
+use common::{
+    data::table::{TableSearchOpts, TableSummaries},
+    error::AppError,
+    query::filter::Filter,
+    DbServerDetails,
+};
+use crate::row::Row;
+use uuid::Uuid;
+
+use crate::{
+    db::{QueryParams, QuerySqlParser, DB},
+    graphs::{
+        category::CategoryGraphOpts, chrono::ChronoAnalysisOpts, geo::GeoGraphOpts,
+        trend::TrendGraphOpts, AnalysisResults, VisualizeDB,
+    },
+    mysql::db::MySqlDB,
+    ConnectorType, SharedTable,
+};
+
+use super::{boolean_search_query, table::MariaDbTable, TableKind};
+
+/// A MariaDB database, reached through [`MysqlConnector`](crate::mysql::connector::MysqlConnector).
+///
+/// MariaDB speaks the MySQL protocol and dialect, so most calls go to [`MySqlDB`].
+pub struct MariaDB {
+    inner: MySqlDB,
+}
+
+impl MariaDB {
+    pub fn new(connector: ConnectorType, user_id: String) -> Self {
+        MariaDB {
+            inner: MySqlDB::new(connector, user_id),
+        }
+    }
+
+    fn table_kind(&self, table_name: &str) -> Result<TableKind, AppError> {
+        let query = "
+            SELECT table_type AS TABLE_TYPE
+            FROM information_schema.tables
+            WHERE table_schema = DATABASE() AND table_name = ?
+        ";
+
+        let rows = self.connector().exec_query(query, &[table_name.into()])?;
+        let kind = rows
+            .first()
+            .and_then(|r| r.get::<String, &str>("TABLE_TYPE"))
+            .map_or(TableKind::Base, |t| t.as_str().into());
+
+        Ok(kind)
+    }
+}
+
+impl DB for MariaDB {
+    type Row = Row;
+
+    fn id(&self) -> &Uuid {
+        self.inner.id()
+    }
+
+    fn set_id(&mut self, id: Uuid) {
+        self.inner.set_id(id);
+    }
+
+    fn user_id(&self) -> &str {
+        self.inner.user_id()
+    }
+
+    fn connector(&self) -> &ConnectorType {
+        self.inner.connector()
+    }
+
+    fn load_tables(&mut self, connector: ConnectorType) -> Result<(), AppError> {
+        let tables = self.query_tables()?;
+
+        tables.iter().for_each(|t| {
+            let connector = connector.clone();
+            let name: String = t.get("TABLE_NAME").unwrap();
+            let kind: Option<String> = t.get("TABLE_TYPE");
+            let kind = kind.map_or(TableKind::Base, |k| k.as_str().into());
+
+            let table = MariaDbTable::with_kind(name, connector, kind);
+            self.inner.tables.push(Arc::new(table));
+        });
+
+        Ok(())
+    }
+
+    fn tables(&self) -> &Vec<SharedTable> {
+        self.inner.tables()
+    }
+
+    /// Lists sequences along with tables and views. Their `TABLE_TYPE` tells them apart.
+    fn query_tables(&self) -> Result<Vec<Row>, AppError> {
+        self.inner.query_tables()
+    }
+
+    fn build_table_list(&self) -> Result<TableSummaries, AppError> {
+        self.inner.build_table_list()
+    }
+
+    fn query_column_count(&self, tb_name: &str) -> Result<u32, AppError> {
+        self.inner.query_column_count(tb_name)
+    }
+
+    fn get_table(&self, name: &str) -> Option<&SharedTable> {
+        self.inner.get_table(name)
+    }
+
+    fn details(&self) -> Result<DbServerDetails, AppError> {
+        let mut details = self.inner.details()?;
+        details.comment = details.comment.map(|c| format!("MariaDB ({c})"));
+
+        Ok(details)
+    }
+
+    fn drop_table(&self, table_name: &str) -> Result<(), AppError> {
+        self.check_columns(table_name, &[])?;
+
+        let object = match self.table_kind(table_name)? {
+            TableKind::Sequence => "SEQUENCE",
+            TableKind::View => "VIEW",
+            TableKind::Base | TableKind::SystemVersioned => "TABLE",
+        };
+
+        let query = format!("DROP {object} {}", self.quote_ident(table_name));
+        self.connector().exec_query(&query, &[])?;
+
+        Ok(())
+    }
+}
+
+impl QuerySqlParser for MariaDB {
+    fn parse_filter(&self, filter: &Filter, params: &mut QueryParams) -> String {
+        self.inner.parse_filter(filter, params)
+    }
+
+    /// MariaDB's natural language search ignores words found in more than half of the rows, so
+    /// small tables often return nothing. Boolean mode has no such threshold, and prefix matching
+    /// lets partial words match, which is closer to what users expect from a search box.
+    fn parse_search(&self, opts: &TableSearchOpts, params: &mut QueryParams) -> String {
+        let wrap_cols: Vec<String> = opts.search_cols.iter().map(|col| self.quote_ident(col)).collect();
+
+        format!(
+            "MATCH({}) AGAINST({} IN BOOLEAN MODE)",
+            wrap_cols.join(","),
+            params.bind(boolean_search_query(&opts.query))
+        )
+    }
+}
+
+impl VisualizeDB for MariaDB {
+    fn chrono_graph(&self, opts: ChronoAnalysisOpts) -> Result<AnalysisResults, AppError> {
+        self.inner.chrono_graph(opts)
+    }
+
+    fn trend_graph(&self, opts: TrendGraphOpts) -> Result<AnalysisResults, AppError> {
+        self.inner.trend_graph(opts)
+    }
+
+    fn category_graph(&self, opts: CategoryGraphOpts) -> Result<AnalysisResults, AppError> {
+        self.inner.category_graph(opts)
+    }
+
+    fn geo_graph(&self, opts: GeoGraphOpts) -> Result<AnalysisResults, AppError> {
+        self.inner.geo_graph(opts)
+    }
+}
@@ -0,0 +1,61 @@
+pub mod db;
+pub mod table;
+
+/// The kind of a relation listed in `information_schema.tables`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TableKind {
+    Base,
+    View,
+    Sequence,
+    /// A table created `WITH SYSTEM VERSIONING`, which keeps the history of its rows.
+    SystemVersioned,
+}
+
+impl From<&str> for TableKind {
+    fn from(table_type: &str) -> Self {
+        match table_type {
+            "VIEW" => TableKind::View,
+            "SEQUENCE" => TableKind::Sequence,
+            "SYSTEM VERSIONED" => TableKind::SystemVersioned,
+            _ => TableKind::Base,
+        }
+    }
+}
+
+/// Build a boolean mode full-text query that matches rows containing every word of `query`,
+/// as a prefix. Boolean mode has no 50% threshold, so it also works on small tables.
+///
+/// The result is meant to be bound as a query parameter, so it isn't escaped.
+pub(crate) fn boolean_search_query(query: &str) -> String {
+    let words: Vec<String> = query
+        .split_whitespace()
+        .map(|word| word.replace(['+', '-', '<', '>', '(', ')', '~', '*', '"', '@'], ""))
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("+{word}*"))
+        .collect();
+
+    words.join(" ")
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::mysql::db::mariadb_version;
+
+    use super::boolean_search_query;
+
+    #[test]
+    fn test_mariadb_version() {
+        assert_eq!(
+            mariadb_version("10.11.6-MariaDB-1:10.11.6+maria~ubu2204"),
+            Some("10.11.6".to_string())
+        );
+        assert_eq!(mariadb_version("5.5.5-10.3.39-MariaDB"), Some("10.3.39".to_string()));
+        assert_eq!(mariadb_version("8.0.36"), None);
+    }
+
+    #[test]
+    fn test_boolean_search_query() {
+        assert_eq!(boolean_search_query("  o'brien  +lagos* "), "+o'brien* +lagos*");
+        assert_eq!(boolean_search_query("- ()"), "");
+    }
+}
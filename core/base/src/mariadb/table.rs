@@ -0,0 +1,216 @@
+use std::{collections::HashMap, io::Write};
+
+use axum::http::StatusCode;
+use common::{
+    data::{
+        columns::ColumnList,
+        table::{
+            DataQueryResult, TableConfig, TableEdit, TableExportOpts, TableQueryOpts,
+            UpdateTableData,
+        },
+    },
+    error::AppError,
+};
+use regex::Regex;
+
+use crate::{
+    mysql::table::{MySqlTable, SYNTAX as MYSQL_SYNTAX},
+    row::{ColumnValue, Row},
+    table::{
+        write_sql_rows, EditBatchError, EditOutcome, RowOutcome, SqlSyntax, Table, TableCRUD,
+        WriteOpts,
+    },
+    ConnectorType, SharedDB,
+};
+
+use super::TableKind;
+
+/// MariaDB has no row alias for the inserted values of an upsert, only `VALUES()`.
+const SYNTAX: SqlSyntax = SqlSyntax {
+    row_alias: false,
+    ..MYSQL_SYNTAX
+};
+
+/// A MariaDB table, view or sequence. Reads go through [`MySqlTable`]; this only deals with
+/// the things MariaDB does differently.
+pub struct MariaDbTable {
+    inner: MySqlTable,
+    pub kind: TableKind,
+}
+
+impl MariaDbTable {
+    pub fn with_kind(name: String, conn: ConnectorType, kind: TableKind) -> Self {
+        MariaDbTable {
+            inner: MySqlTable::new(name, conn),
+            kind,
+        }
+    }
+
+    /// MariaDB has no JSON type: `JSON` is an alias for `LONGTEXT` with a `json_valid` check.
+    /// Returns the columns guarded by such a check.
+    fn json_columns(&self) -> Result<Vec<String>, AppError> {
+        let query = "
+            SELECT check_clause AS CHECK_CLAUSE
+            FROM information_schema.check_constraints
+            WHERE constraint_schema = DATABASE() AND table_name = ?
+        ";
+
+        let rows = self.connector().exec_query(query, &[self.name().into()])?;
+        let re = Regex::new(r"^json_valid\(`([^`]+)`\)$").unwrap();
+
+        let cols = rows
+            .iter()
+            .filter_map(|r| r.get::<String, &str>("CHECK_CLAUSE"))
+            .filter_map(|clause| re.captures(&clause).map(|c| c[1].to_string()))
+            .collect();
+
+        Ok(cols)
+    }
+
+    /// The `ROW START` and `ROW END` columns of a system-versioned table. Only the server
+    /// may write them.
+    fn period_columns(&self) -> Result<Vec<String>, AppError> {
+        if self.kind != TableKind::SystemVersioned {
+            return Ok(vec![]);
+        }
+
+        let query = "
+            SELECT column_name AS COLUMN_NAME
+            FROM information_schema.columns
+            WHERE table_schema = DATABASE() AND table_name = ?
+            AND (extra LIKE '%ROW START%' OR extra LIKE '%ROW END%')
+        ";
+
+        let rows = self.connector().exec_query(query, &[self.name().into()])?;
+        let cols = rows
+            .iter()
+            .filter_map(|r| r.get::<String, &str>("COLUMN_NAME"))
+            .collect();
+
+        Ok(cols)
+    }
+
+    /// Check that a write touching `columns` is allowed on this table.
+    fn check_writable<'a>(&self, columns: impl IntoIterator<Item = &'a String>) -> Result<(), AppError> {
+        if self.kind == TableKind::Sequence {
+            return Err(AppError::HttpError(
+                StatusCode::FORBIDDEN,
+                format!("`{}` is a sequence and can't be edited", self.name()),
+            ));
+        }
+
+        let period_cols = self.period_columns()?;
+        if let Some(col) = columns.into_iter().find(|col| period_cols.contains(col)) {
+            return Err(AppError::HttpError(
+                StatusCode::BAD_REQUEST,
+                format!("`{col}` is set by system versioning and can't be written"),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Table for MariaDbTable {
+    type Row = Row;
+
+    fn new(name: String, conn: ConnectorType) -> Self
+    where
+        Self: Sized,
+    {
+        MariaDbTable::with_kind(name, conn, TableKind::Base)
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn connector(&self) -> &ConnectorType {
+        self.inner.connector()
+    }
+
+    /// `JSON` columns are reported as `json` rather than the `longtext` they are stored as.
+    fn query_columns(&self) -> Result<ColumnList, AppError> {
+        let mut cols = self.inner.query_columns()?;
+
+        // check_constraints is missing before MariaDB 10.2.22.
+        let json_cols = self.json_columns().unwrap_or_default();
+        cols.iter_mut()
+            .filter(|col| col.col_type == "longtext" && json_cols.contains(&col.name))
+            .for_each(|col| col.col_type = "json".to_string());
+
+        Ok(cols)
+    }
+
+    fn init_config(&self) -> Option<TableConfig> {
+        self.inner.init_config()
+    }
+}
+
+impl TableCRUD for MariaDbTable {
+    fn insert_data(&self, input: HashMap<String, String>) -> Result<(), AppError> {
+        self.check_writable(input.keys())?;
+        self.inner.insert_data(input)
+    }
+
+    fn insert_batch(&self, columns: &[String], rows: Vec<Vec<ColumnValue>>) -> Result<usize, AppError> {
+        self.check_writable(columns)?;
+        self.inner.insert_batch(columns, rows)
+    }
+
+    fn write_rows(
+        &self,
+        columns: &[String],
+        rows: Vec<Vec<ColumnValue>>,
+        opts: WriteOpts,
+    ) -> Result<Vec<RowOutcome>, AppError> {
+        self.check_writable(columns)?;
+        write_sql_rows(&self.inner, &SYNTAX, columns, rows, opts)
+    }
+
+    fn apply_edits(
+        &self,
+        unique_key: &str,
+        edits: Vec<TableEdit>,
+    ) -> Result<Vec<EditOutcome>, EditBatchError> {
+        let columns = edits.iter().flat_map(|edit| match edit {
+            TableEdit::Insert { values } | TableEdit::Update { values, .. } => Some(values.keys()),
+            TableEdit::Delete { .. } => None,
+        });
+        self.check_writable(columns.flatten())?;
+
+        self.inner.apply_edits(unique_key, edits)
+    }
+
+    fn query_data(
+        &self,
+        filter: TableQueryOpts,
+        db: &SharedDB,
+    ) -> DataQueryResult<ColumnValue, AppError> {
+        self.inner.query_data(filter, db)
+    }
+
+    fn query_result_count(&self, filter: TableQueryOpts, db: &SharedDB) -> Result<usize, AppError> {
+        self.inner.query_result_count(filter, db)
+    }
+
+    fn update_data(&self, input: UpdateTableData) -> Result<(), AppError> {
+        self.check_writable(&input.columns)?;
+        self.inner.update_data(input)
+    }
+
+    fn delete_data(&self, col: &str, values: Vec<&str>) -> Result<(), AppError> {
+        self.check_writable(&[])?;
+        self.inner.delete_data(col, values)
+    }
+
+    fn export(&self, opts: TableExportOpts, db: &SharedDB, out: &mut (dyn Write + Send)) -> Result<(), AppError> {
+        self.inner.export(opts, db, out)
+    }
+
+    /// Deleted rows of a system-versioned table are kept in its history.
+    fn clear(&self) -> Result<(), AppError> {
+        self.check_writable(&[])?;
+        self.inner.clear()
+    }
+}
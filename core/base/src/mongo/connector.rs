@@ -0,0 +1,169 @@
+use axum::http::StatusCode;
+use common::error::AppError;
+use mongodb::{
+    bson::{doc, Bson, Document},
+    sync::{Client, Database},
+};
+use crate::row::{ColumnValue, Row};
+
+use crate::{
+    config::ConfigRaw,
+    connector::{run_blocking, Connector},
+};
+
+use super::documents_to_rows;
+
+/// MongoDB implementation of `BasableConnection`.
+///
+/// Mongo has no query language to send as a string, so [`Connector::exec_query`] takes a
+/// database command (e.g. `{"find": "users", "filter": {...}}`) as extended JSON. Commands that
+/// return a cursor are read to the end and each document becomes a row; other commands return
+/// their reply as a single row.
+///
+/// Values are part of the command document, typed as BSON, so there are no parameters to bind.
+#[derive(Clone)]
+pub struct MongoConnector {
+    pub client: Client,
+
+    /// The database named by `db_name`
+    pub database: Database,
+
+    /// Connection options
+    pub config: ConfigRaw,
+}
+
+fn to_app_error(err: mongodb::error::Error) -> AppError {
+    AppError::ServerError(err.to_string())
+}
+
+impl MongoConnector {
+    /// Run `command` and return the documents it produces.
+    pub fn run_command(&self, command: Document) -> Result<Vec<Document>, AppError> {
+        let mut docs = Vec::new();
+        self.for_each_batch(command, &mut |batch| {
+            docs.extend(batch);
+            Ok(())
+        })?;
+
+        Ok(docs)
+    }
+
+    /// Run `command`, handing the documents it produces to `f` a cursor batch at a time.
+    fn for_each_batch(
+        &self,
+        command: Document,
+        f: &mut (dyn FnMut(Vec<Document>) -> Result<(), AppError> + Send),
+    ) -> Result<(), AppError> {
+        run_blocking(|| {
+            let reply = self
+                .database
+                .run_command(command, None)
+                .map_err(to_app_error)?;
+
+            let Ok(cursor) = reply.get_document("cursor") else {
+                return f(vec![reply]);
+            };
+
+            let batch = cursor
+                .get_array("firstBatch")
+                .map(|batch| batch.iter().filter_map(Bson::as_document).cloned().collect())
+                .unwrap_or_default();
+            f(batch)?;
+
+            let mut cursor_id = cursor.get_i64("id").unwrap_or_default();
+            let collection = cursor
+                .get_str("ns")
+                .ok()
+                .and_then(|ns| ns.split_once('.'))
+                .map(|(_, coll)| coll.to_string())
+                .unwrap_or_default();
+
+            while cursor_id != 0 {
+                let reply = self
+                    .database
+                    .run_command(doc! { "getMore": cursor_id, "collection": &collection }, None)
+                    .map_err(to_app_error)?;
+
+                let cursor = reply
+                    .get_document("cursor")
+                    .map_err(|err| AppError::ServerError(err.to_string()))?;
+
+                if let Ok(batch) = cursor.get_array("nextBatch") {
+                    f(batch.iter().filter_map(Bson::as_document).cloned().collect())?;
+                }
+
+                cursor_id = cursor.get_i64("id").unwrap_or_default();
+            }
+
+            Ok(())
+        })
+    }
+}
+
+/// Parse a command given to [`MongoConnector::exec_query`] as extended JSON.
+fn parse_command(query: &str, params: &[ColumnValue]) -> Result<Document, AppError> {
+    let invalid = |err: String| AppError::HttpError(StatusCode::BAD_REQUEST, err);
+
+    if !params.is_empty() {
+        return Err(invalid("Mongo commands don't take query parameters".to_string()));
+    }
+
+    let json: serde_json::Value =
+        serde_json::from_str(query).map_err(|err| invalid(err.to_string()))?;
+
+    match Bson::try_from(json).map_err(|err| invalid(err.to_string()))? {
+        Bson::Document(command) => Ok(command),
+        _ => Err(invalid("a Mongo command must be a document".to_string())),
+    }
+}
+
+impl Connector for MongoConnector {
+    type Row = Row;
+
+    fn new(config: ConfigRaw) -> Result<Self, AppError> {
+        let url = config.build_url()?;
+        let db_name = config.db_name.clone().ok_or_else(|| {
+            AppError::HttpError(
+                StatusCode::EXPECTATION_FAILED,
+                "A database name is required for mongo sources".to_string(),
+            )
+        })?;
+
+        let client = run_blocking(|| Client::with_uri_str(url)).map_err(to_app_error)?;
+        let database = client.database(&db_name);
+
+        let conn = MongoConnector {
+            client,
+            database,
+            config,
+        };
+
+        // The driver connects lazily, so we make sure the server is reachable.
+        conn.run_command(doc! { "ping": 1 })?;
+
+        Ok(conn)
+    }
+
+    fn exec_query(&self, query: &str, params: &[ColumnValue]) -> Result<Vec<Self::Row>, AppError> {
+        let docs = self.run_command(parse_command(query, params)?)?;
+
+        Ok(documents_to_rows(&docs))
+    }
+
+    /// Rows are read a cursor batch at a time. Documents don't share a schema, so each batch
+    /// has its own columns.
+    fn for_each_row(
+        &self,
+        query: &str,
+        params: &[ColumnValue],
+        f: &mut (dyn FnMut(Self::Row) -> Result<(), AppError> + Send),
+    ) -> Result<(), AppError> {
+        self.for_each_batch(parse_command(query, params)?, &mut |batch| {
+            documents_to_rows(&batch).into_iter().try_for_each(&mut *f)
+        })
+    }
+
+    fn config(&self) -> &ConfigRaw {
+        &self.config
+    }
+}
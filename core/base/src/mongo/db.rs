@@ -0,0 +1,275 @@
+use std::sync::Arc;
+
+use common::{
+    data::table::{TableSearchOpts, TableSummaries, TableSummary},
+    error::AppError,
+    query::{
+        filter::{Filter, FilterChain},
+        BasableQuery, QueryCommand, QueryOrder,
+    },
+    DbServerDetails,
+};
+use mongodb::bson::{doc, Bson, Document, Regex};
+use crate::row::{ColumnValue, Row};
+use uuid::Uuid;
+
+use crate::{
+    db::{QueryParams, QuerySqlParser, DB},
+    table::Table,
+    ConnectorType, SharedTable,
+};
+
+use super::{
+    contains_regex, filter_chain_document, filter_document, number, table::MongoTable,
+    to_command,
+};
+
+pub struct MongoDB {
+    pub connector: ConnectorType,
+    pub tables: Vec<SharedTable>,
+    user_id: String,
+    id: Uuid,
+}
+
+impl MongoDB {
+    pub fn new(connector: ConnectorType, user_id: String) -> Self {
+        MongoDB {
+            connector,
+            tables: Vec::new(),
+            user_id,
+            id: Uuid::new_v4(),
+        }
+    }
+
+    fn run(&self, command: Document) -> Result<Vec<Row>, AppError> {
+        self.connector.exec_query(&to_command(command), &[])
+    }
+}
+
+impl DB for MongoDB {
+    type Row = Row;
+
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+
+    fn set_id(&mut self, id: Uuid) {
+        self.id = id;
+    }
+
+    fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    fn connector(&self) -> &ConnectorType {
+        &self.connector
+    }
+
+    fn load_tables(&mut self, connector: ConnectorType) -> Result<(), AppError> {
+        let tables = self.query_tables()?;
+
+        tables.iter().for_each(|t| {
+            let connector = connector.clone();
+            let name: String = t.get("name").unwrap();
+
+            let table = MongoTable::new(name, connector);
+            self.tables.push(Arc::new(table));
+        });
+
+        Ok(())
+    }
+
+    fn tables(&self) -> &Vec<SharedTable> {
+        &self.tables
+    }
+
+    /// Collections are the tables of a Mongo database. Views and system collections are left out.
+    fn query_tables(&self) -> Result<Vec<Row>, AppError> {
+        let system = Regex {
+            pattern: "^system\\.".to_string(),
+            options: String::new(),
+        };
+
+        self.run(doc! {
+            "listCollections": 1,
+            "nameOnly": true,
+            "filter": { "type": "collection", "name": { "$not": system } },
+        })
+    }
+
+    fn build_table_list(&self) -> Result<TableSummaries, AppError> {
+        let results = self.query_tables()?;
+        let mut tables = Vec::with_capacity(results.len());
+
+        for res in results {
+            let name: String = res.get("name").unwrap();
+            let col_count = self.query_column_count(&name)?;
+
+            let count = self.run(doc! { "count": &name })?;
+            let row_count = count.first().map_or(0.0, |r| number(r, "n"));
+
+            tables.push(TableSummary {
+                name,
+                col_count,
+                row_count: row_count as u32,
+                created: None,
+                updated: None,
+            });
+        }
+
+        Ok(tables)
+    }
+
+    /// Mongo collections have no fixed schema, so this counts the fields of sampled documents.
+    fn query_column_count(&self, tb_name: &str) -> Result<u32, AppError> {
+        let table = MongoTable::new(tb_name.to_string(), self.connector.clone());
+        let cols = table.query_columns()?;
+
+        Ok(cols.len() as u32)
+    }
+
+    fn get_table(&self, name: &str) -> Option<&SharedTable> {
+        self.tables.iter().find(|t| t.name() == name)
+    }
+
+    fn details(&self) -> Result<DbServerDetails, AppError> {
+        let info = self.run(doc! { "buildInfo": 1 })?;
+        let info = info.first();
+
+        let version: String = info
+            .and_then(|r| r.get("version"))
+            .unwrap_or_default();
+
+        // buildEnvironment is a nested document, which comes back as JSON.
+        let os = info
+            .and_then(|r| r.get::<String, &str>("buildEnvironment"))
+            .and_then(|env| serde_json::from_str::<serde_json::Value>(&env).ok())
+            .and_then(|env| env["target_os"].as_str().map(|os| os.to_string()))
+            .unwrap_or_default();
+
+        // db size is returned in MB
+        let stats = self.run(doc! { "dbStats": 1, "scale": 1024 * 1024 })?;
+        let size = stats.first().map_or(0.0, |r| number(r, "dataSize"));
+
+        Ok(DbServerDetails {
+            comment: Some(format!("MongoDB {version}")),
+            version,
+            os,
+            db_size: (size * 10.0).round() / 10.0,
+        })
+    }
+
+    fn drop_table(&self, table_name: &str) -> Result<(), AppError> {
+        self.check_columns(table_name, &[])?;
+
+        self.run(doc! { "drop": table_name })?;
+
+        Ok(())
+    }
+}
+
+impl QuerySqlParser for MongoDB {
+    /// Renders the filter as a Mongo query document in extended JSON.
+    fn parse_filter(&self, filter: &Filter, _: &mut QueryParams) -> String {
+        to_command(filter_document(filter))
+    }
+
+    fn parse_filters(&self, filters: &FilterChain, _: &mut QueryParams) -> String {
+        to_command(filter_chain_document(filters))
+    }
+
+    /// Builds an `aggregate` command rather than SQL. It is run with
+    /// [`Connector::exec_query`](crate::connector::Connector::exec_query) like any other query,
+    /// without parameters.
+    fn generate_sql(&self, query: BasableQuery) -> Result<(String, Vec<ColumnValue>), AppError> {
+        let is_search_mode = query.is_search_mode();
+
+        let BasableQuery {
+            table,
+            command: operation,
+            filters,
+            row_count,
+            offset,
+            order_by,
+            group_by,
+            left_join,
+            having,
+            search_opts,
+        } = query;
+
+        if left_join.is_some() || group_by.is_some() || having.not_empty() {
+            return Err(AppError::not_implemented());
+        }
+
+        let mut pipeline: Vec<Document> = Vec::new();
+
+        // Parse query filters
+        if filters.not_empty() && !is_search_mode {
+            pipeline.push(doc! { "$match": filter_chain_document(&filters) });
+        }
+
+        // Mongo's $text search needs a text index, so we match the query in any search column.
+        if is_search_mode {
+            if let Some(opts) = search_opts {
+                let TableSearchOpts { search_cols, query } = opts;
+
+                let matches: Vec<Document> = search_cols
+                    .iter()
+                    .map(|col| doc! { col: { "$regex": contains_regex(&query) } })
+                    .collect();
+
+                pipeline.push(doc! { "$match": { "$or": matches } });
+            }
+        }
+
+        // Parse ORDER BY
+        if !order_by.is_empty() {
+            let mut sort = Document::new();
+            for order in order_by {
+                match order {
+                    QueryOrder::ASC(col) => sort.insert(col, 1),
+                    QueryOrder::DESC(col) => sort.insert(col, -1),
+                };
+            }
+
+            pipeline.push(doc! { "$sort": sort });
+        }
+
+        // Parse LIMIT
+        if let Some(row_count) = row_count {
+            let offset = offset.unwrap_or_default();
+            if offset > 0 {
+                pipeline.push(doc! { "$skip": offset as i64 });
+            }
+
+            pipeline.push(doc! { "$limit": row_count as i64 });
+        }
+
+        // Parse query operation type
+        match operation {
+            QueryCommand::SelectData(Some(cols)) if !cols.is_empty() => {
+                if let Some(count) = cols.iter().find(|c| c.to_lowercase() == "count(*)") {
+                    pipeline.push(doc! { "$count": count });
+                } else {
+                    let mut projection = doc! { "_id": 0 };
+                    for col in cols {
+                        projection.insert(col, 1);
+                    }
+
+                    pipeline.push(doc! { "$project": projection });
+                }
+            }
+            QueryCommand::SelectData(_) => {}
+        }
+
+        let pipeline: Vec<Bson> = pipeline.into_iter().map(Bson::Document).collect();
+
+        let command = to_command(doc! {
+            "aggregate": table,
+            "pipeline": pipeline,
+            "cursor": {},
+        });
+
+        Ok((command, Vec::new()))
+    }
+}
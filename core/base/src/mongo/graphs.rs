@@ -0,0 +1,213 @@
+use common::error::AppError;
+use mongodb::bson::{doc, Bson, Document};
+use crate::row::{ColumnValue, Row};
+use time::Date;
+
+use crate::{
+    globals::{BASABLE_CHRONO_XCOL, BASABLE_CHRONO_YCOL},
+    graphs::{
+        category::CategoryGraphOpts,
+        chrono::{ChronoAnalysisBasis, ChronoAnalysisOpts},
+        geo::GeoGraphOpts,
+        trend::{CrossOptions, TrendGraphOpts, TrendGraphOrder, TrendGraphType},
+        AnalysisResult, AnalysisResults, AnalysisValue, VisualizeDB,
+    },
+};
+
+use super::{db::MongoDB, literal, number, to_command};
+
+impl MongoDB {
+    fn aggregate(&self, table: &str, pipeline: Vec<Document>) -> Result<Vec<Row>, AppError> {
+        let pipeline: Vec<Bson> = pipeline.into_iter().map(Bson::Document).collect();
+        let command = doc! { "aggregate": table, "pipeline": pipeline, "cursor": {} };
+
+        self.connector.exec_query(&to_command(command), &[])
+    }
+
+    /// Count documents per distinct value of `target_col`.
+    fn count_by_column(
+        &self,
+        table: &str,
+        target_col: &str,
+        limit: Option<usize>,
+    ) -> Result<AnalysisResults, AppError> {
+        let mut pipeline = vec![
+            doc! { "$group": { "_id": format!("${target_col}"), "COUNT": { "$sum": 1 } } },
+            doc! { "$sort": { "COUNT": -1 } },
+        ];
+
+        if let Some(limit) = limit.filter(|l| *l > 0) {
+            pipeline.push(doc! { "$limit": limit as i64 });
+        }
+
+        let rows = self.aggregate(table, pipeline)?;
+
+        let results: AnalysisResults = rows
+            .iter()
+            .map(|r| {
+                let x_value: ColumnValue = r.get("_id").unwrap_or(ColumnValue::NULL);
+                let x = x_value.try_into().unwrap_or_default();
+
+                let y = AnalysisValue::UInt(number(r, "COUNT") as usize);
+
+                AnalysisResult::new(x, y)
+            })
+            .collect();
+
+        Ok(results)
+    }
+}
+
+impl VisualizeDB for MongoDB {
+    /// `chrono_col` is expected to hold BSON dates.
+    fn chrono_graph(&self, opts: ChronoAnalysisOpts) -> Result<AnalysisResults, AppError> {
+        opts.check(self)?;
+
+        let ChronoAnalysisOpts {
+            table,
+            chrono_col,
+            basis,
+            range,
+        } = opts;
+
+        let col = format!("${chrono_col}");
+        let basis_expr = match basis {
+            ChronoAnalysisBasis::Daily => Bson::Document(doc! {
+                "$dateFromParts": {
+                    "year": { "$year": &col },
+                    "month": { "$month": &col },
+                    "day": { "$dayOfMonth": &col },
+                }
+            }),
+            ChronoAnalysisBasis::Monthly => Bson::Document(doc! { "$month": &col }),
+            ChronoAnalysisBasis::Yearly => Bson::Document(doc! { "$year": &col }),
+        };
+
+        let pipeline = vec![
+            doc! {
+                "$match": {
+                    &chrono_col: { "$gte": literal(range.start()), "$lte": literal(range.end()) }
+                }
+            },
+            doc! { "$group": { "_id": basis_expr, BASABLE_CHRONO_YCOL: { "$sum": 1 } } },
+            doc! { "$sort": { "_id": 1 } },
+            doc! { "$project": { "_id": 0, BASABLE_CHRONO_XCOL: "$_id", BASABLE_CHRONO_YCOL: 1 } },
+        ];
+
+        let rows = self.aggregate(&table, pipeline)?;
+
+        let results: AnalysisResults = rows
+            .iter()
+            .filter_map(|r| {
+                let x = match basis {
+                    ChronoAnalysisBasis::Daily => {
+                        let date: Option<Date> = r.get(BASABLE_CHRONO_XCOL)?;
+                        AnalysisValue::Date(date?)
+                    }
+                    _ => AnalysisValue::UInt(number(r, BASABLE_CHRONO_XCOL) as usize),
+                };
+
+                let y = AnalysisValue::UInt(number(r, BASABLE_CHRONO_YCOL) as usize);
+
+                Some(AnalysisResult::new(x, y))
+            })
+            .collect();
+
+        Ok(results)
+    }
+
+    fn trend_graph(&self, opts: TrendGraphOpts) -> Result<AnalysisResults, AppError> {
+        opts.check(self)?;
+
+        let TrendGraphOpts {
+            table,
+            graph_type,
+            xcol,
+            ycol,
+            order,
+            limit,
+            cross,
+        } = opts;
+
+        let order = match order {
+            Some(TrendGraphOrder::ASC) => 1,
+            _ => -1,
+        };
+
+        let x = format!("${xcol}");
+        let y = format!("${ycol}");
+
+        let mut pipeline = match &graph_type {
+            TrendGraphType::IntraModel => vec![
+                doc! {
+                    "$project": {
+                        "_id": 0,
+                        "x": { "$toString": &x },
+                        "y": { "$toDouble": &y },
+                    }
+                },
+                doc! { "$sort": { "y": order } },
+            ],
+            TrendGraphType::CrossModel => {
+                let CrossOptions {
+                    foreign_table,
+                    target_col,
+                } = cross.ok_or_else(|| {
+                    AppError::HttpError(
+                        axum::http::StatusCode::EXPECTATION_FAILED,
+                        "You must provide cross model options.".to_string(),
+                    )
+                })?;
+
+                vec![
+                    doc! {
+                        "$lookup": {
+                            "from": foreign_table,
+                            "localField": target_col,
+                            "foreignField": &ycol,
+                            "as": "matches",
+                        }
+                    },
+                    doc! { "$group": { "_id": &x, "y": { "$sum": { "$size": "$matches" } } } },
+                    doc! { "$match": { "y": { "$gt": 0 } } },
+                    doc! { "$project": { "_id": 0, "x": { "$toString": "$_id" }, "y": 1 } },
+                    doc! { "$sort": { "y": order } },
+                ]
+            }
+        };
+
+        if let Some(limit) = limit.filter(|l| *l > 0) {
+            pipeline.push(doc! { "$limit": limit as i64 });
+        }
+
+        let rows = self.aggregate(&table, pipeline)?;
+
+        let results: AnalysisResults = rows
+            .iter()
+            .map(|r| {
+                let x: Option<String> = r.get("x").flatten();
+                let x = AnalysisValue::Text(x.unwrap_or_default());
+                let y = match &graph_type {
+                    TrendGraphType::IntraModel => AnalysisValue::Double(number(r, "y")),
+                    TrendGraphType::CrossModel => AnalysisValue::UInt(number(r, "y") as usize),
+                };
+
+                AnalysisResult::new(x, y)
+            })
+            .collect();
+
+        Ok(results)
+    }
+
+    fn category_graph(&self, opts: CategoryGraphOpts) -> Result<AnalysisResults, AppError> {
+        opts.check(self)?;
+
+        self.count_by_column(&opts.table, &opts.target_column, opts.limit)
+    }
+
+    fn geo_graph(&self, opts: GeoGraphOpts) -> Result<AnalysisResults, AppError> {
+        opts.check(self)?;
+
+        self.count_by_column(&opts.table, &opts.target_column, None)
+    }
+}
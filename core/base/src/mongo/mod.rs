@@ -0,0 +1,328 @@
+use std::sync::Arc;
+
+use mongodb::bson::{doc, oid::ObjectId, Bson, DateTime, Document, Regex};
+use crate::row::{ColumnValue, Row};
+use time::OffsetDateTime;
+
+use common::query::filter::{Filter, FilterChain, FilterCombinator, FilterExpression};
+
+
+pub mod connector;
+pub mod db;
+pub mod graphs;
+pub mod table;
+
+/// Number of documents sampled to work out the columns of a collection.
+pub(crate) const SAMPLE_SIZE: i64 = 100;
+
+/// Serialize a command document into the string accepted by
+/// [`MongoConnector::exec_query`](connector::MongoConnector).
+pub(crate) fn to_command(command: Document) -> String {
+    Bson::Document(command).into_relaxed_extjson().to_string()
+}
+
+/// Parse a date or datetime string such as `2024-01-31` or `2024-01-31 10:00:00`.
+pub(crate) fn parse_datetime(value: &str) -> Option<DateTime> {
+    let value = value.trim();
+    let looks_like_date = value.len() >= 10
+        && value.as_bytes()[4] == b'-'
+        && value.as_bytes()[7] == b'-'
+        && value[..4].chars().all(|c| c.is_ascii_digit());
+
+    if !looks_like_date {
+        return None;
+    }
+
+    let mut rfc3339 = value.replacen(' ', "T", 1);
+    if rfc3339.len() == 10 {
+        rfc3339.push_str("T00:00:00");
+    }
+
+    let has_offset = rfc3339.ends_with('Z') || rfc3339[10..].contains(['+', '-']);
+    if !has_offset {
+        rfc3339.push('Z');
+    }
+
+    DateTime::parse_rfc3339_str(rfc3339).ok()
+}
+
+/// Best guess at the BSON value meant by a filter value. Filter values always arrive as
+/// strings, while documents usually hold numbers, booleans and dates.
+pub(crate) fn literal(value: &str) -> Bson {
+    if let Ok(v) = value.parse::<i64>() {
+        return Bson::Int64(v);
+    }
+
+    if let Ok(v) = value.parse::<f64>() {
+        if v.is_finite() {
+            return Bson::Double(v);
+        }
+    }
+
+    match value {
+        "true" => return Bson::Boolean(true),
+        "false" => return Bson::Boolean(false),
+        _ => {}
+    }
+
+    if let Some(date) = parse_datetime(value) {
+        return Bson::DateTime(date);
+    }
+
+    if value.len() == 24 {
+        if let Ok(oid) = ObjectId::parse_str(value) {
+            return Bson::ObjectId(oid);
+        }
+    }
+
+    Bson::String(value.to_string())
+}
+
+/// Values a filter value may be stored as: the string itself and its typed form.
+fn candidates(value: &str) -> Vec<Bson> {
+    let typed = literal(value);
+    let text = Bson::String(value.to_string());
+
+    if typed == text {
+        vec![text]
+    } else {
+        vec![text, typed]
+    }
+}
+
+fn escape_regex(value: &str) -> String {
+    value
+        .chars()
+        .flat_map(|c| {
+            let escape = "\\^$.|?*+()[]{}".contains(c);
+            escape.then_some('\\').into_iter().chain(std::iter::once(c))
+        })
+        .collect()
+}
+
+/// Case-insensitive match of `value` anywhere in a string, like SQL's `LIKE '%value%'`.
+pub(crate) fn contains_regex(value: &str) -> Bson {
+    Bson::RegularExpression(Regex {
+        pattern: escape_regex(value),
+        options: "i".to_string(),
+    })
+}
+
+fn expression_document(expression: &FilterExpression) -> Document {
+    let list = |values: &Vec<String>| -> Vec<Bson> {
+        values.iter().flat_map(|v| candidates(v)).collect()
+    };
+
+    match expression {
+        FilterExpression::Eq(v) => doc! { "$in": candidates(v) },
+        FilterExpression::NotEq(v) => doc! { "$nin": candidates(v) },
+        FilterExpression::Gt(v) => doc! { "$gt": literal(v) },
+        FilterExpression::Lt(v) => doc! { "$lt": literal(v) },
+        FilterExpression::Gte(v) => doc! { "$gte": literal(v) },
+        FilterExpression::Lte(v) => doc! { "$lte": literal(v) },
+        FilterExpression::Contains(v) => doc! { "$regex": contains_regex(v) },
+        FilterExpression::NotContains(v) => doc! { "$not": contains_regex(v) },
+        FilterExpression::Regex(v) => doc! { "$regex": v },
+        FilterExpression::NotRegex(v) => doc! {
+            "$not": Bson::RegularExpression(Regex { pattern: v.clone(), options: String::new() })
+        },
+        FilterExpression::Btw(start, end) => doc! { "$gte": literal(start), "$lte": literal(end) },
+        FilterExpression::NotBtw(start, end) => doc! {
+            "$not": { "$gte": literal(start), "$lte": literal(end) }
+        },
+        FilterExpression::Includes(values) => doc! { "$in": list(values) },
+        FilterExpression::NotInclude(values) => doc! { "$nin": list(values) },
+        FilterExpression::Null => doc! { "$eq": Bson::Null },
+        FilterExpression::NotNull => doc! { "$ne": Bson::Null },
+    }
+}
+
+/// Translate a [`Filter`] into a query document matching a single field.
+pub(crate) fn filter_document(filter: &Filter) -> Document {
+    doc! { filter.column.clone(): expression_document(&filter.expression) }
+}
+
+/// Translate a [`FilterChain`] into a Mongo query document. As in SQL, `AND` binds tighter
+/// than `OR`.
+pub(crate) fn filter_chain_document(filters: &FilterChain) -> Document {
+    let mut groups: Vec<Vec<Document>> = Vec::new();
+
+    for filter in filters.all() {
+        match (&filter.combinator, groups.last_mut()) {
+            (FilterCombinator::AND, Some(group)) => group.push(filter_document(filter)),
+            _ => groups.push(vec![filter_document(filter)]),
+        }
+    }
+
+    let mut groups: Vec<Document> = groups
+        .into_iter()
+        .map(|mut group| {
+            if group.len() == 1 {
+                group.remove(0)
+            } else {
+                doc! { "$and": group }
+            }
+        })
+        .collect();
+
+    match groups.len() {
+        0 => Document::new(),
+        1 => groups.remove(0),
+        _ => doc! { "$or": groups },
+    }
+}
+
+fn datetime_value(date: &DateTime) -> ColumnValue {
+    let nanos = i128::from(date.timestamp_millis()) * 1_000_000;
+
+    match OffsetDateTime::from_unix_timestamp_nanos(nanos) {
+        Ok(dt) => ColumnValue::Date(
+            dt.year() as u16,
+            dt.month() as u8,
+            dt.day(),
+            dt.hour(),
+            dt.minute(),
+            dt.second(),
+            dt.microsecond(),
+        ),
+        Err(_) => ColumnValue::NULL,
+    }
+}
+
+/// Convert a BSON value into the [`ColumnValue`] shared by every
+/// [`ConnectorType`](crate::ConnectorType).
+pub(crate) fn to_value(value: &Bson) -> ColumnValue {
+    match value {
+        Bson::Null | Bson::Undefined => ColumnValue::NULL,
+        Bson::Double(v) => ColumnValue::Double(*v),
+        Bson::Int32(v) => ColumnValue::Int((*v).into()),
+        Bson::Int64(v) => ColumnValue::Int(*v),
+        Bson::Boolean(v) => ColumnValue::Int(*v as i64),
+        Bson::String(v) | Bson::Symbol(v) => ColumnValue::Text(v.clone()),
+        Bson::ObjectId(oid) => ColumnValue::Text(oid.to_hex()),
+        Bson::DateTime(date) => datetime_value(date),
+        Bson::Decimal128(v) => ColumnValue::Text(v.to_string()),
+        Bson::Timestamp(ts) => ColumnValue::Int(ts.time.into()),
+        // Documents, arrays and the rarer types are shown as extended JSON.
+        other => ColumnValue::Text(other.clone().into_relaxed_extjson().to_string()),
+    }
+}
+
+/// Read a numeric field that Mongo may return either as an integer or a double.
+pub(crate) fn number(row: &Row, name: &str) -> f64 {
+    match row.get::<ColumnValue, &str>(name) {
+        Some(ColumnValue::Int(v)) => v as f64,
+        Some(ColumnValue::UInt(v)) => v as f64,
+        Some(ColumnValue::Double(v)) => v,
+        Some(ColumnValue::Float(v)) => v.into(),
+        Some(ColumnValue::Text(v)) => v.parse().unwrap_or_default(),
+        _ => 0.0,
+    }
+}
+
+/// Build a [`Row`] per document. Documents don't share a schema, so the columns are the
+/// fields of all documents, in the order they first appear.
+pub(crate) fn documents_to_rows(docs: &[Document]) -> Vec<Row> {
+    let mut names: Vec<&str> = Vec::new();
+    for doc in docs {
+        for key in doc.keys() {
+            if !names.contains(&key.as_str()) {
+                names.push(key);
+            }
+        }
+    }
+
+    let columns: Arc<[String]> = names.iter().map(|name| name.to_string()).collect();
+
+    docs.iter()
+        .map(|doc| {
+            let values = names
+                .iter()
+                .map(|name| doc.get(name).map_or(ColumnValue::NULL, to_value))
+                .collect();
+
+            Row::new(columns.clone(), values)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use common::query::filter::{Filter, FilterChain, FilterCombinator, FilterExpression};
+    use mongodb::bson::{doc, Bson};
+
+    use crate::{config::ConfigRaw, connector::Connector, mongo::connector::MongoConnector, table::Table};
+
+    use super::{filter_chain_document, literal, table::MongoTable, to_command};
+
+    fn filter(combinator: FilterCombinator, column: &str, expression: FilterExpression) -> Filter {
+        Filter {
+            combinator,
+            column: column.to_string(),
+            expression,
+        }
+    }
+
+    #[test]
+    fn test_literal() {
+        assert_eq!(literal("42"), Bson::Int64(42));
+        assert_eq!(literal("4.5"), Bson::Double(4.5));
+        assert_eq!(literal("true"), Bson::Boolean(true));
+        assert!(matches!(literal("2024-01-31"), Bson::DateTime(_)));
+        assert!(matches!(literal("2024-01-31 10:30:00"), Bson::DateTime(_)));
+        assert!(matches!(literal("65a1f0c2e4b0a1b2c3d4e5f6"), Bson::ObjectId(_)));
+        assert_eq!(literal("lagos"), Bson::String("lagos".to_string()));
+    }
+
+    #[test]
+    fn test_filter_chain_document() {
+        let chain = FilterChain::prefill(vec![
+            filter(FilterCombinator::BASE, "age", FilterExpression::Gte("18".to_string())),
+            filter(FilterCombinator::AND, "city", FilterExpression::NotNull),
+            filter(FilterCombinator::OR, "vip", FilterExpression::Eq("true".to_string())),
+        ]);
+
+        let expected = doc! {
+            "$or": [
+                { "$and": [
+                    { "age": { "$gte": 18_i64 } },
+                    { "city": { "$ne": Bson::Null } },
+                ] },
+                { "vip": { "$in": ["true", true] } },
+            ]
+        };
+
+        assert_eq!(filter_chain_document(&chain), expected);
+    }
+
+    /// Needs a mongod listening on localhost:27017.
+    #[test]
+    #[ignore]
+    fn test_query_columns_local_mongod() {
+        let config = ConfigRaw {
+            source: "mongo".to_string(),
+            db_name: Some("basable_test".to_string()),
+            ..ConfigRaw::default()
+        };
+
+        let conn = MongoConnector::new(config).unwrap();
+        conn.exec_query(&to_command(doc! { "drop": "people" }), &[]).ok();
+        conn.exec_query(
+            &to_command(doc! {
+                "insert": "people",
+                "documents": [{ "name": "Ada", "age": 36 }, { "name": "Alan", "age": 41.5, "city": "London" }],
+            }),
+            &[],
+        )
+        .unwrap();
+
+        let table = MongoTable::new("people".to_string(), std::sync::Arc::new(conn));
+        let cols = table.query_columns().unwrap();
+
+        let age = cols.iter().find(|c| c.name == "age").unwrap();
+        assert!(age.col_type.contains("int") && age.col_type.contains("double"));
+
+        let city = cols.iter().find(|c| c.name == "city").unwrap();
+        assert!(city.nullable);
+    }
+}
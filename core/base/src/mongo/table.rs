@@ -0,0 +1,576 @@
+use std::{collections::HashMap, io::Write};
+
+use axum::http::StatusCode;
+use common::{
+    data::{
+        columns::{Column, ColumnList},
+        table::{
+            DataQueryResult, TableConfig, TableEdit, TableExportOpts, TableQueryOpts,
+            UpdateTableData,
+        },
+    },
+    error::AppError,
+    query::{filter::FilterChain, BasableQuery, QueryCommand},
+};
+use mongodb::bson::{doc, oid::ObjectId, Bson, Document};
+
+use crate::{
+    export::write_export,
+    row::{ColumnValue, FromColumnValue, Row},
+    table::{
+        check_rows, numbered_error, EditBatchError, EditOutcome, RowAction, RowOutcome,
+        WriteOpts,
+    },
+    table::{Table, TableCRUD},
+    ConnectorType, SharedDB,
+};
+
+use super::{number, parse_datetime, to_command, to_value, SAMPLE_SIZE};
+
+pub struct MongoTable {
+    pub name: String,
+    pub connector: ConnectorType,
+}
+
+/// Convert an input value to the BSON type sampled for its column. Values for unknown
+/// columns, or that don't parse as the column type, are stored as strings.
+fn typed_value(col_type: Option<&str>, value: &str) -> Bson {
+    let types: Vec<&str> = col_type.map(|t| t.split('|').collect()).unwrap_or_default();
+    let has = |t: &str| types.contains(&t);
+
+    if has("objectId") {
+        if let Ok(oid) = ObjectId::parse_str(value) {
+            return Bson::ObjectId(oid);
+        }
+    }
+
+    if has("int") || has("long") {
+        if let Ok(v) = value.parse::<i64>() {
+            return Bson::Int64(v);
+        }
+    }
+
+    if has("double") || has("decimal") {
+        if let Ok(v) = value.parse::<f64>() {
+            return Bson::Double(v);
+        }
+    }
+
+    if has("bool") {
+        if let Ok(v) = value.parse::<bool>() {
+            return Bson::Boolean(v);
+        }
+    }
+
+    if has("date") {
+        if let Some(date) = parse_datetime(value) {
+            return Bson::DateTime(date);
+        }
+    }
+
+    Bson::String(value.to_string())
+}
+
+impl MongoTable {
+    fn run(&self, command: Document) -> Result<Vec<Row>, AppError> {
+        self.connector.exec_query(&to_command(command), &[])
+    }
+
+    /// Run a write command, failing if the server reports a write error.
+    fn write(&self, command: Document) -> Result<(), AppError> {
+        let reply = self.run(command)?;
+
+        let errors = reply
+            .first()
+            .and_then(|r| r.get::<String, &str>("writeErrors"));
+
+        match errors {
+            Some(errors) => Err(AppError::ServerError(errors)),
+            None => Ok(()),
+        }
+    }
+
+    /// Run a write command and return its reply. Fails on the first write error, naming the
+    /// row it's about: Mongo reports the position of the document or update in the command.
+    fn write_reply(&self, command: Document) -> Result<Row, AppError> {
+        let reply = self
+            .run(command)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| AppError::ServerError("Mongo sent an empty reply".to_string()))?;
+
+        let Some(errors) = reply.get::<String, &str>("writeErrors") else {
+            return Ok(reply);
+        };
+
+        let first = serde_json::from_str::<Vec<serde_json::Value>>(&errors)
+            .ok()
+            .and_then(|errors| errors.into_iter().next());
+        let error = match first {
+            Some(err) => {
+                let index = err.get("index").and_then(|i| i.as_u64()).unwrap_or_default();
+                let msg = err.get("errmsg").and_then(|m| m.as_str()).unwrap_or(&errors);
+                numbered_error("Row", index as usize, AppError::ServerError(msg.to_string()))
+            }
+            None => AppError::ServerError(errors),
+        };
+
+        Err(error)
+    }
+
+    /// Documents holding the values of `columns` of each row, typed as sampled.
+    fn row_documents(
+        &self,
+        columns: &[String],
+        rows: &[Vec<ColumnValue>],
+    ) -> Result<Vec<Document>, AppError> {
+        let types = self.column_types()?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                columns
+                    .iter()
+                    .zip(row)
+                    .map(|(col, value)| {
+                        let value = match String::from_value(value) {
+                            Some(value) => typed_value(types.get(col).map(|t| t.as_str()), &value),
+                            None => Bson::Null,
+                        };
+
+                        (col.clone(), value)
+                    })
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// Sampled type of each column, used to convert input values before writing them.
+    fn column_types(&self) -> Result<HashMap<String, String>, AppError> {
+        let cols = self.query_columns()?;
+
+        Ok(cols.into_iter().map(|c| (c.name, c.col_type)).collect())
+    }
+
+    fn typed_document(
+        &self,
+        input: &HashMap<String, String>,
+        types: &HashMap<String, String>,
+    ) -> Document {
+        input
+            .iter()
+            .map(|(k, v)| (k.clone(), typed_value(types.get(k).map(|t| t.as_str()), v)))
+            .collect()
+    }
+}
+
+impl Table for MongoTable {
+    type Row = Row;
+
+    fn new(name: String, conn: ConnectorType) -> Self
+    where
+        Self: Sized,
+    {
+        MongoTable {
+            name,
+            connector: conn,
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Collections have no schema, so columns are worked out from a sample of documents. A column's
+    /// type lists every BSON type found for it (e.g. `int|double`).
+    fn query_columns(&self) -> Result<ColumnList, AppError> {
+        let count = self.run(doc! { "count": &self.name })?;
+        let sampled = count
+            .first()
+            .map_or(0, |r| number(r, "n") as i64)
+            .min(SAMPLE_SIZE);
+
+        let fields = self.run(doc! {
+            "aggregate": &self.name,
+            "pipeline": [
+                { "$sample": { "size": SAMPLE_SIZE } },
+                { "$project": { "kv": { "$objectToArray": "$$ROOT" } } },
+                { "$unwind": { "path": "$kv", "includeArrayIndex": "pos" } },
+                { "$group": {
+                    "_id": "$kv.k",
+                    "pos": { "$min": "$pos" },
+                    "types": { "$addToSet": { "$type": "$kv.v" } },
+                    "count": { "$sum": 1 },
+                } },
+                { "$sort": { "pos": 1, "_id": 1 } },
+            ],
+            "cursor": {},
+        })?;
+
+        let indexes = self.run(doc! { "listIndexes": &self.name })?;
+        let unique_fields: Vec<String> = indexes
+            .iter()
+            .filter(|idx| number(idx, "unique") > 0.0)
+            .filter_map(|idx| idx.get::<String, &str>("key"))
+            .filter_map(|key| serde_json::from_str::<serde_json::Map<_, _>>(&key).ok())
+            .filter(|key| key.len() == 1)
+            .filter_map(|key| key.keys().next().cloned())
+            .collect();
+
+        let cols: ColumnList = fields
+            .iter()
+            .map(|r| {
+                let name: String = r.get("_id").unwrap_or_default();
+
+                // `types` is an array, which comes back as JSON.
+                let types: Vec<String> = r
+                    .get::<String, &str>("types")
+                    .and_then(|t| serde_json::from_str(&t).ok())
+                    .unwrap_or_default();
+
+                let has_null = types.iter().any(|t| t == "null");
+                let col_type: Vec<String> = types.into_iter().filter(|t| t != "null").collect();
+
+                let missing = (number(r, "count") as i64) < sampled;
+                let primary = name == "_id";
+
+                Column {
+                    unique: primary || unique_fields.contains(&name),
+                    name,
+                    col_type: col_type.join("|"),
+                    default_value: None,
+                    nullable: has_null || missing,
+                    primary,
+                }
+            })
+            .collect();
+
+        Ok(cols)
+    }
+
+    fn connector(&self) -> &ConnectorType {
+        &self.connector
+    }
+
+    fn init_config(&self) -> Option<TableConfig> {
+        Some(TableConfig {
+            pk_column: Some("_id".to_string()),
+            name: self.name.clone(),
+            label: self.name.clone(),
+            ..TableConfig::default()
+        })
+    }
+}
+
+impl TableCRUD for MongoTable {
+    fn query_data(
+        &self,
+        opts: TableQueryOpts,
+        db: &SharedDB,
+    ) -> DataQueryResult<ColumnValue, AppError> {
+        let cols = opts
+            .columns
+            .clone()
+            .take_if(|cols| !cols.is_empty())
+            .unwrap_or_else(|| match self.query_columns() {
+                Ok(cs) => cs.iter().map(|col| col.name.clone()).collect(),
+                Err(err) => {
+                    tracing::error!("error reading db column: {err}");
+                    vec![]
+                }
+            });
+
+        let query = opts.try_into()?;
+        let (command, params) = db.generate_sql(query)?;
+
+        let conn = self.connector();
+        let rows = conn.exec_query(&command, &params)?;
+
+        let data = rows
+            .iter()
+            .map(|r| {
+                let mut map: HashMap<String, ColumnValue> = HashMap::new();
+
+                for col in &cols {
+                    if let Some(v) = r.get::<ColumnValue, &str>(col) {
+                        map.insert(col.clone(), v);
+                    }
+                }
+
+                map
+            })
+            .collect();
+
+        Ok(data)
+    }
+
+    fn query_result_count(&self, opts: TableQueryOpts, db: &SharedDB) -> Result<usize, AppError> {
+        let query = BasableQuery {
+            table: opts.table,
+            command: QueryCommand::SelectData(Some(vec!["COUNT(*)".to_string()])),
+            search_opts: opts.search_opts,
+            filters: opts
+                .filters
+                .map_or(FilterChain::empty(), FilterChain::prefill),
+            ..Default::default()
+        };
+
+        let (command, params) = db.generate_sql(query)?;
+
+        let conn = self.connector();
+        let rows = conn.exec_query(&command, &params)?;
+
+        // $count returns no document at all when nothing matches.
+        let count = rows.first().map_or(0.0, |row| number(row, "COUNT(*)"));
+
+        Ok(count as usize)
+    }
+
+    fn insert_data(&self, input: HashMap<String, String>) -> Result<(), AppError> {
+        let types = self.column_types()?;
+        let document = self.typed_document(&input, &types);
+
+        self.write(doc! { "insert": &self.name, "documents": [document] })
+    }
+
+    /// Mongo transactions need a replica set, so the documents are sent in one ordered
+    /// `insert` instead: it stops at the first failing document, keeping the ones before it.
+    fn insert_batch(&self, columns: &[String], rows: Vec<Vec<ColumnValue>>) -> Result<usize, AppError> {
+        if rows.is_empty() {
+            return Ok(0);
+        }
+
+        let documents = self.row_documents(columns, &rows)?;
+
+        self.write(doc! { "insert": &self.name, "documents": documents, "ordered": true })?;
+        Ok(rows.len())
+    }
+
+    /// Collections can't be written in a transaction outside of replica sets, so the rows are
+    /// written by a single ordered command instead. It stops at the first failing row, but the
+    /// rows before it are kept.
+    fn write_rows(
+        &self,
+        columns: &[String],
+        rows: Vec<Vec<ColumnValue>>,
+        opts: WriteOpts,
+    ) -> Result<Vec<RowOutcome>, AppError> {
+        check_rows(columns, &rows)?;
+        if rows.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let upsert_on = opts.upsert_on.unwrap_or_default();
+        if let Some(col) = upsert_on.iter().find(|col| !columns.contains(col)) {
+            return Err(AppError::HttpError(
+                StatusCode::BAD_REQUEST,
+                format!("Upsert column `{col}` must be one of the written columns"),
+            ));
+        }
+
+        let mut documents = self.row_documents(columns, &rows)?;
+
+        // Ids are made here rather than by the server, so they can be reported.
+        let ids: Vec<Option<ObjectId>> = documents
+            .iter()
+            .map(|doc| (!doc.contains_key("_id")).then(ObjectId::new))
+            .collect();
+
+        let upserted: Vec<usize> = match upsert_on.is_empty() {
+            true => {
+                for (doc, id) in documents.iter_mut().zip(&ids) {
+                    if let Some(id) = id {
+                        doc.insert("_id", *id);
+                    }
+                }
+
+                self.write_reply(doc! { "insert": &self.name, "documents": &documents, "ordered": true })?;
+                (0..documents.len()).collect()
+            }
+            false => {
+                let updates: Vec<Document> = documents
+                    .iter()
+                    .zip(&ids)
+                    .map(|(doc, id)| {
+                        let (query, set): (Document, Document) = doc
+                            .iter()
+                            .map(|(k, v)| (k.clone(), v.clone()))
+                            .partition(|(k, _)| upsert_on.contains(k));
+
+                        // Matched fields go in `$setOnInsert`, which is never empty: an update
+                        // without operators would replace the whole document.
+                        let mut on_insert = query.clone();
+                        if let Some(id) = id {
+                            on_insert.insert("_id", *id);
+                        }
+
+                        let mut update = doc! { "$setOnInsert": on_insert };
+                        if !set.is_empty() {
+                            update.insert("$set", set);
+                        }
+
+                        doc! { "q": query, "u": update, "upsert": true }
+                    })
+                    .collect();
+
+                let reply =
+                    self.write_reply(doc! { "update": &self.name, "updates": updates, "ordered": true })?;
+
+                // `upserted` lists the position and `_id` of each inserted document.
+                reply
+                    .get::<String, &str>("upserted")
+                    .and_then(|upserted| serde_json::from_str::<Vec<serde_json::Value>>(&upserted).ok())
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|u| u.get("index").and_then(|i| i.as_u64()))
+                    .map(|i| i as usize)
+                    .collect()
+            }
+        };
+
+        let outcomes = documents
+            .iter()
+            .zip(ids)
+            .enumerate()
+            .map(|(index, (doc, id))| {
+                let inserted = upserted.contains(&index);
+                let key = opts.key_column.as_deref().and_then(|col| match doc.get(col) {
+                    Some(value) => Some(to_value(value)),
+                    None if col == "_id" && inserted => id.map(|id| ColumnValue::Text(id.to_hex())),
+                    None => None,
+                });
+
+                RowOutcome {
+                    action: match inserted {
+                        true => RowAction::Inserted,
+                        false => RowAction::Updated,
+                    },
+                    key,
+                }
+            })
+            .collect();
+
+        Ok(outcomes)
+    }
+
+    /// Edits can't be rolled back without a transaction, which collections don't support
+    /// outside of replica sets.
+    fn apply_edits(
+        &self,
+        _unique_key: &str,
+        _edits: Vec<TableEdit>,
+    ) -> Result<Vec<EditOutcome>, EditBatchError> {
+        Err(AppError::HttpError(
+            StatusCode::NOT_IMPLEMENTED,
+            "Edit batches need transactions, which Mongo collections don't support".to_string(),
+        )
+        .into())
+    }
+
+    fn update_data(&self, options: UpdateTableData) -> Result<(), AppError> {
+        let UpdateTableData {
+            unique_key,
+            columns,
+            unique_values,
+            input,
+        } = options;
+
+        let types = self.column_types()?;
+        let key_type = types.get(&unique_key).map(|t| t.as_str());
+
+        let mut updates = Vec::with_capacity(unique_values.len());
+        for (index, uv) in unique_values.iter().enumerate() {
+            let Some(values) = input.get(index) else {
+                continue;
+            };
+
+            let values: HashMap<String, String> = values
+                .iter()
+                .filter(|(k, _)| columns.contains(k))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+
+            if values.is_empty() {
+                continue;
+            }
+
+            updates.push(doc! {
+                "q": { &unique_key: typed_value(key_type, uv) },
+                "u": { "$set": self.typed_document(&values, &types) },
+            });
+        }
+
+        if updates.is_empty() {
+            return Ok(());
+        }
+
+        self.write(doc! { "update": &self.name, "updates": updates })
+    }
+
+    fn delete_data(&self, col: &str, values: Vec<&str>) -> Result<(), AppError> {
+        if values.is_empty() {
+            return Err(AppError::ServerError("a value must be provided".to_string()));
+        }
+
+        let types = self.column_types()?;
+        let col_type = types.get(col).map(|t| t.as_str());
+        let values: Vec<Bson> = values.iter().map(|v| typed_value(col_type, v)).collect();
+
+        self.write(doc! {
+            "delete": &self.name,
+            "deletes": [{ "q": { col: { "$in": values } }, "limit": 0 }],
+        })
+    }
+
+    fn export(&self, opts: TableExportOpts, db: &SharedDB, out: &mut (dyn Write + Send)) -> Result<(), AppError> {
+        let TableExportOpts {
+            query_opts,
+            format,
+            trim,
+            xml,
+            ..
+        } = opts;
+
+        let cols = query_opts
+            .columns
+            .clone()
+            .take_if(|cols| !cols.is_empty())
+            .unwrap_or_else(|| match self.query_columns() {
+                Ok(cs) => cs.iter().map(|col| col.name.clone()).collect(),
+                Err(err) => {
+                    tracing::error!("error reading db column: {err}");
+                    vec![]
+                }
+            });
+
+        let selection = if cols.is_empty() {
+            None
+        } else {
+            Some(cols.clone())
+        };
+
+        let filters = query_opts
+            .filters
+            .map_or(FilterChain::empty(), FilterChain::prefill);
+
+        let query = BasableQuery {
+            table: query_opts.table,
+            command: QueryCommand::SelectData(selection),
+            filters,
+            offset: trim.as_ref().map(|trim| trim.offset),
+            row_count: trim.map(|trim| trim.count),
+            ..Default::default()
+        };
+
+        let (command, params) = db.generate_sql(query)?;
+
+        write_export(self.connector(), format, xml, cols, &command, &params, out)
+    }
+
+    fn clear(&self) -> Result<(), AppError> {
+        self.write(doc! {
+            "delete": &self.name,
+            "deletes": [{ "q": {}, "limit": 0 }],
+        })
+    }
+}
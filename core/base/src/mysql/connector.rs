@@ -1,7 +1,13 @@
 use common::error::AppError;
-use mysql::{prelude::Queryable, Opts, Params, Pool, Row};
+use mysql::{prelude::Queryable, Opts, Params, Pool, TxOpts, Value};
 
-use crate::{config::ConfigRaw, connector::Connector};
+use crate::{
+    config::ConfigRaw,
+    connector::{Connector, Statement, TransactionError},
+    row::{ColumnValue, Row},
+};
+
+use super::into_row;
 
 /// MySQL implementation of `BasableConnection`
 #[derive(Clone, Default)]
@@ -20,6 +26,14 @@ impl MysqlConnector {
     }
 }
 
+fn to_params(params: &[ColumnValue]) -> Params {
+    if params.is_empty() {
+        Params::Empty
+    } else {
+        Params::Positional(params.iter().map(Value::from).collect())
+    }
+}
+
 impl Connector for MysqlConnector {
     type Row = Row;
 
@@ -35,13 +49,57 @@ impl Connector for MysqlConnector {
             .map_err(|err| AppError::ServerError(err.to_string()))
     }
 
-    fn exec_query(&self, query: &str) -> Result<Vec<Self::Row>, AppError> {
+    fn exec_query(&self, query: &str, params: &[ColumnValue]) -> Result<Vec<Self::Row>, AppError> {
         let conn = &mut self.pool().get_conn()?;
 
         let stmt = conn.prep(query)?;
-        let rows = conn.exec(stmt, Params::Empty)?;
+        let rows: Vec<mysql::Row> = conn.exec(stmt, to_params(params))?;
+
+        Ok(rows.into_iter().map(into_row).collect())
+    }
+
+    fn for_each_row(
+        &self,
+        query: &str,
+        params: &[ColumnValue],
+        f: &mut (dyn FnMut(Self::Row) -> Result<(), AppError> + Send),
+    ) -> Result<(), AppError> {
+        let conn = &mut self.pool().get_conn()?;
+
+        let stmt = conn.prep(query)?;
+        for row in conn.exec_iter(stmt, to_params(params))? {
+            f(into_row(row?))?;
+        }
+
+        Ok(())
+    }
+
+    fn exec_transaction(
+        &self,
+        statements: &[Statement],
+    ) -> Result<Vec<Vec<Self::Row>>, TransactionError> {
+        let failed = |index: usize| {
+            move |err: mysql::Error| TransactionError {
+                index,
+                error: err.into(),
+            }
+        };
+
+        let mut conn = self.pool().get_conn().map_err(failed(0))?;
+        // Dropping the transaction before it's committed rolls it back.
+        let mut tx = conn.start_transaction(TxOpts::default()).map_err(failed(0))?;
+
+        let mut results = Vec::with_capacity(statements.len());
+        for (index, (query, params)) in statements.iter().enumerate() {
+            let rows: Vec<mysql::Row> = tx
+                .exec(query.as_str(), to_params(params))
+                .map_err(failed(index))?;
+            results.push(rows.into_iter().map(into_row).collect());
+        }
 
-        Ok(rows)
+        tx.commit()
+            .map_err(failed(statements.len().saturating_sub(1)))?;
+        Ok(results)
     }
 
     fn config(&self) -> &ConfigRaw {
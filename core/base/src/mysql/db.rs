@@ -1,14 +1,23 @@
 use std::{collections::HashMap, sync::Arc};
 
 use common::{data::table::{TableSummaries, TableSummary}, error::AppError, DBVersion, DbServerDetails};
-use mysql::Row;
 use time::Date;
 use uuid::Uuid;
 
-use crate::{config::ConfigRaw, db::{QuerySqlParser, DB}, table::Table, ConnectorType, SharedTable};
+use crate::{config::ConfigRaw, db::{QuerySqlParser, DB}, row::{ColumnValue, Row}, table::Table, ConnectorType, SharedTable};
 
 use super::table::MySqlTable;
 
+/// Extract the version number from a MariaDB `version` string, e.g. `10.11.6` from
+/// `10.11.6-MariaDB-1:10.11.6+maria~ubu2204`. Servers that speak the old replication protocol
+/// also prefix it with `5.5.5-`. Returns `None` for anything that isn't a MariaDB server.
+pub(crate) fn mariadb_version(version: &str) -> Option<String> {
+    let (number, _) = version.split_once("-MariaDB")?;
+    let number = number.strip_prefix("5.5.5-").unwrap_or(number);
+
+    Some(number.to_string())
+}
+
 pub struct MySqlDB {
     pub connector: ConnectorType,
     pub tables: Vec<SharedTable>,
@@ -26,7 +35,8 @@ impl MySqlDB {
         }
     }
 
-    /// Get MySQL server version and host OS version
+    /// Get MySQL server version and host OS version. `server` is set to `MySQL` or `MariaDB`
+    /// depending on the server we're actually connected to.
     fn show_version_variables(&self) -> Result<DBVersion, AppError> {
         let vars = self.exec_query(
             "
@@ -39,6 +49,7 @@ impl MySqlDB {
                     'version_compile_zlib'
                 )
             ",
+            &[],
         )?;
 
         let mut data = HashMap::new();
@@ -49,23 +60,32 @@ impl MySqlDB {
             data.insert(name, value);
         }
 
+        let mariadb = data.get("version").and_then(|v| mariadb_version(v));
+        match mariadb {
+            Some(version) => {
+                data.insert("version".to_string(), version);
+                data.insert("server".to_string(), "MariaDB".to_string());
+            }
+            None => {
+                data.insert("server".to_string(), "MySQL".to_string());
+            }
+        }
+
         Ok(data)
     }
 
     fn size(&self) -> Result<f64, AppError> {
         let db = self.config().db_name.as_ref().unwrap();
 
-        let query = format!(
-            "
-            SELECT table_schema '{db}', 
-            ROUND(SUM(data_length + index_length) / 1024 / 1024, 1) 'size' 
-            FROM information_schema.tables 
-            WHERE table_schema = '{db}'
+        let query = "
+            SELECT table_schema,
+            ROUND(SUM(data_length + index_length) / 1024 / 1024, 1) 'size'
+            FROM information_schema.tables
+            WHERE table_schema = ?
             GROUP BY table_schema
-        "
-        );
+        ";
 
-        let qr = self.exec_query(&query)?;
+        let qr = self.exec_query(query, &[db.as_str().into()])?;
 
         // db size is returned in MB, we may want to write a function
         // to convert for GB, TB...etc
@@ -78,21 +98,25 @@ impl MySqlDB {
     }
 
     fn config(&self) -> &ConfigRaw {
-        &self.connector.config()
+        self.connector.config()
     }
 
-    fn exec_query(&self, query: &str) -> Result<Vec<Row>, AppError> {
-        self.connector.exec_query(query)
+    fn exec_query(&self, query: &str, params: &[ColumnValue]) -> Result<Vec<Row>, AppError> {
+        self.connector.exec_query(query, params)
     }
 }
 
 impl DB for MySqlDB {
-    type Row = mysql::Row;
+    type Row = Row;
 
     fn id(&self) -> &Uuid {
         &self.id
     }
 
+    fn set_id(&mut self, id: Uuid) {
+        self.id = id;
+    }
+
     fn user_id(&self) -> &str {
         &self.user_id
     }
@@ -122,17 +146,20 @@ impl DB for MySqlDB {
     }
 
     fn query_tables(&self) -> Result<Vec<Row>, AppError> {
-        let query = format!(
-            "
-                SELECT table_name, table_rows, create_time, update_time
+        let query = "
+                SELECT
+                    table_name AS TABLE_NAME,
+                    table_type AS TABLE_TYPE,
+                    table_rows AS TABLE_ROWS,
+                    create_time AS CREATE_TIME,
+                    update_time AS UPDATE_TIME
                 FROM information_schema.tables
-                WHERE table_schema = '{}'
+                WHERE table_schema = ?
                 ORDER BY table_name;
-            ",
-            self.config().db_name.clone().unwrap()
-        );
+            ";
 
-        self.connector.exec_query(&query)
+        let db = self.config().db_name.clone().unwrap();
+        self.connector.exec_query(query, &[db.into()])
     }
 
     fn build_table_list(&self) -> Result<TableSummaries, AppError> {
@@ -149,9 +176,10 @@ impl DB for MySqlDB {
                 TableSummary {
                     name,
                     col_count,
-                    row_count: res.get("TABLE_ROWS").unwrap(),
-                    created: created.map_or(None, |d| Some(d.to_string())),
-                    updated: updated.map_or(None, |d| Some(d.to_string())),
+                    // NULL for views
+                    row_count: res.get::<Option<u32>, &str>("TABLE_ROWS").flatten().unwrap_or_default(),
+                    created: created.map(|d| d.to_string()),
+                    updated: updated.map(|d| d.to_string()),
                 }
             })
             .collect();
@@ -160,18 +188,15 @@ impl DB for MySqlDB {
     }
 
     fn query_column_count(&self, tb_name: &str) -> Result<u32, AppError> {
-        let query = format!(
-            "
-                SELECT count(*) 
-                FROM information_schema.columns 
-                WHERE table_schema = '{}' and table_name = '{}'
+        let query = "
+                SELECT count(*)
+                FROM information_schema.columns
+                WHERE table_schema = ? and table_name = ?
                 ORDER BY table_name;
-            ",
-            self.config().db_name.clone().unwrap(),
-            tb_name
-        );
+            ";
 
-        let qr = self.exec_query(&query)?;
+        let db = self.config().db_name.clone().unwrap();
+        let qr = self.exec_query(query, &[db.into(), tb_name.into()])?;
         let c: u32 = qr.first().map_or(0, |r| r.get("count(*)").unwrap());
 
         Ok(c)
@@ -198,9 +223,11 @@ impl DB for MySqlDB {
     }
 
     fn drop_table(&self, table_name: &str) -> Result<(), AppError> {
-        let query = format!("DROP TABLE {table_name}");
+        self.check_columns(table_name, &[])?;
+
+        let query = format!("DROP TABLE {}", self.quote_ident(table_name));
         let conn = self.connector();
-        conn.exec_query(&query)?;
+        conn.exec_query(&query, &[])?;
 
         Ok(())
     }
@@ -1,21 +1,90 @@
 use common::error::AppError;
 use time::Date;
 
-use crate::{db::{QuerySqlParser, DB}, globals::{BASABLE_CHRONO_XCOL, BASABLE_CHRONO_YCOL}, graphs::{category::CategoryGraphOpts, chrono::{ChronoAnalysisBasis, ChronoAnalysisOpts}, geo::GeoGraphOpts, trend::{TrendGraphOpts, TrendGraphType}, AnalysisResult, AnalysisResults, AnalysisValue, VisualizeDB}};
+use crate::{
+    db::DB,
+    globals::{BASABLE_CHRONO_XCOL, BASABLE_CHRONO_YCOL},
+    graphs::{
+        category::CategoryGraphOpts,
+        chrono::{ChronoAnalysisBasis, ChronoAnalysisOpts},
+        geo::GeoGraphOpts,
+        trend::{CrossOptions, TrendGraphOpts, TrendGraphOrder, TrendGraphType},
+        AnalysisResult, AnalysisResults, AnalysisValue, VisualizeDB,
+    },
+    row::ColumnValue,
+};
+
+use super::{db::MySqlDB, quote_ident};
+
+/// `LIMIT` clause for graphs where a zero or missing limit means "everything".
+fn limit_clause(limit: Option<usize>) -> String {
+    match limit {
+        Some(limit) if limit > 0 => format!(" LIMIT {limit}"),
+        _ => String::new(),
+    }
+}
 
-use mysql::{DriverError::SetupError, Value};
+impl MySqlDB {
+    /// Count rows per distinct value of `target_col`.
+    fn count_by_column(
+        &self,
+        table: &str,
+        target_col: &str,
+        limit: Option<usize>,
+    ) -> Result<AnalysisResults, AppError> {
+        let col = quote_ident(target_col);
+        let sql = format!(
+            "SELECT COUNT(*) AS `COUNT`, {col} FROM {} GROUP BY {col} ORDER BY 1 DESC{}",
+            quote_ident(table),
+            limit_clause(limit)
+        );
+
+        let conn = self.connector();
+        let rows = conn.exec_query(&sql, &[])?;
+
+        let results: AnalysisResults = rows
+            .iter()
+            .map(|r| {
+                let x_value: ColumnValue = r.get(target_col).unwrap_or(ColumnValue::NULL);
+                let x = x_value.try_into().unwrap_or_default();
+
+                let y = AnalysisValue::UInt(r.get("COUNT").unwrap());
+
+                AnalysisResult::new(x, y)
+            })
+            .collect();
 
-use super::db::MySqlDB;
+        Ok(results)
+    }
+}
 
 impl VisualizeDB for MySqlDB {
     fn chrono_graph(&self, opts: ChronoAnalysisOpts) -> Result<AnalysisResults, AppError> {
-        let basis = opts.basis.clone();
-
-        let query = opts.into();
-        let sql = self.generate_sql(query)?;
+        opts.check(self)?;
+
+        let ChronoAnalysisOpts {
+            table,
+            chrono_col,
+            basis,
+            range,
+        } = opts;
+
+        let col = quote_ident(&chrono_col);
+        let sql = format!(
+            "
+            SELECT {basis}({col}) AS `{BASABLE_CHRONO_XCOL}`, COUNT(*) AS `{BASABLE_CHRONO_YCOL}`
+            FROM {}
+            WHERE {col} BETWEEN ? AND ?
+            GROUP BY 1
+            ORDER BY 1 ASC
+        ",
+            quote_ident(&table)
+        );
+
+        let params = [range.start().into(), range.end().into()];
 
         let conn = self.connector();
-        let rows = conn.exec_query(&sql)?;
+        let rows = conn.exec_query(&sql, &params)?;
 
         let results: AnalysisResults = rows
             .iter()
@@ -38,30 +107,73 @@ impl VisualizeDB for MySqlDB {
     }
 
     fn trend_graph(&self, opts: TrendGraphOpts) -> Result<AnalysisResults, AppError> {
-        let xcol = opts.xcol.clone();
-        let ycol = opts.ycol.clone();
-        let analysis_type = opts.graph_type.clone();
-
-        let query = opts
-            .try_into()
-            .map_err(|_| mysql::Error::DriverError(SetupError));
-
-        let query = query?;
-        let sql = self.generate_sql(query)?;
+        opts.check(self)?;
+
+        let TrendGraphOpts {
+            table,
+            graph_type,
+            xcol,
+            ycol,
+            order,
+            limit,
+            cross,
+        } = opts;
+
+        let order = match order {
+            Some(TrendGraphOrder::ASC) => "ASC",
+            _ => "DESC",
+        };
+
+        let x = quote_ident(&xcol);
+        let y = quote_ident(&ycol);
+
+        let sql = match &graph_type {
+            TrendGraphType::IntraModel => format!(
+                "SELECT {x}, {y} FROM {} ORDER BY {y} {order}{}",
+                quote_ident(&table),
+                limit_clause(limit)
+            ),
+            TrendGraphType::CrossModel => {
+                let CrossOptions {
+                    foreign_table,
+                    target_col,
+                } = cross.ok_or_else(|| {
+                    AppError::HttpError(
+                        axum::http::StatusCode::EXPECTATION_FAILED,
+                        "You must provide cross model options.".to_string(),
+                    )
+                })?;
+
+                format!(
+                    "
+                    SELECT x.{x} AS {x}, COUNT(y.{y}) AS {y}
+                    FROM {} x
+                    LEFT JOIN {} y ON x.{} = y.{y}
+                    GROUP BY x.{x}
+                    HAVING {y} > 0
+                    ORDER BY {y} {order}{}
+                ",
+                    quote_ident(&table),
+                    quote_ident(&foreign_table),
+                    quote_ident(&target_col),
+                    limit_clause(limit)
+                )
+            }
+        };
 
         let conn = self.connector();
-        let rows = conn.exec_query(&sql)?;
+        let rows = conn.exec_query(&sql, &[])?;
 
         let results: AnalysisResults = rows
             .iter()
             .map(|r| {
-                let x = AnalysisValue::Text(r.get(xcol.as_str()).unwrap());
-                let y = match analysis_type {
+                let x = AnalysisValue::Text(r.get(xcol.as_str()).unwrap_or_default());
+                let y = match &graph_type {
                     TrendGraphType::IntraModel => {
-                        AnalysisValue::Double(r.get(ycol.as_str()).unwrap())
+                        AnalysisValue::Double(r.get(ycol.as_str()).unwrap_or_default())
                     }
                     TrendGraphType::CrossModel => {
-                        AnalysisValue::UInt(r.get(ycol.as_str()).unwrap())
+                        AnalysisValue::UInt(r.get(ycol.as_str()).unwrap_or_default())
                     }
                 };
 
@@ -73,54 +185,14 @@ impl VisualizeDB for MySqlDB {
     }
 
     fn category_graph(&self, opts: CategoryGraphOpts) -> Result<AnalysisResults, AppError> {
-        let target_col = opts.target_column.clone();
-        let query = opts.into();
-
-        let sql = self
-            .generate_sql(query)
-            .map_err(|_| mysql::Error::DriverError(SetupError))?;
-
-        let conn = self.connector();
+        opts.check(self)?;
 
-        let rows = conn.exec_query(&sql)?;
-        let results: AnalysisResults = rows
-            .iter()
-            .map(|r| {
-                let x_value: Value = r.get(target_col.as_str()).unwrap_or(Value::NULL);
-                let x = x_value.try_into().unwrap_or_default();
-
-                let y = AnalysisValue::UInt(r.get("COUNT").unwrap());
-
-                AnalysisResult::new(x, y)
-            })
-            .collect();
-
-        Ok(results)
+        self.count_by_column(&opts.table, &opts.target_column, opts.limit)
     }
 
     fn geo_graph(&self, opts: GeoGraphOpts) -> Result<AnalysisResults, AppError> {
-        let target_col = opts.target_column.clone();
-        let query = opts.into();
-
-        let sql = self
-            .generate_sql(query)
-            .map_err(|_| mysql::Error::DriverError(SetupError))?;
-
-        let conn = self.connector();
+        opts.check(self)?;
 
-        let rows = conn.exec_query(&sql)?;
-        let results: AnalysisResults = rows
-            .iter()
-            .map(|r| {
-                let x_value: Value = r.get(target_col.as_str()).unwrap_or(Value::NULL);
-                let x = x_value.try_into().unwrap_or_default();
-
-                let y = AnalysisValue::UInt(r.get("COUNT").unwrap());
-
-                AnalysisResult::new(x, y)
-            })
-            .collect();
-
-        Ok(results)
+        self.count_by_column(&opts.table, &opts.target_column, None)
     }
 }
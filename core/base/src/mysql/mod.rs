@@ -1,26 +1,17 @@
-use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
 use mysql::Value;
 
+use crate::row::{ColumnValue, Row};
+
 pub mod db;
 pub mod connector;
 pub mod table;
 pub mod graphs;
 
-/// Client side representation of a value of MySql column.
-///
-/// The `Value` is also used as a parameter to a prepared statement.
-#[derive(Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
-pub enum ColumnValue {
-    NULL,
-    Text(String),
-    Int(i64),
-    UInt(u64),
-    Float(f32),
-    Double(f64),
-    /// year, month, day, hour, minutes, seconds, micro seconds
-    Date(u16, u8, u8, u8, u8, u8, u32),
-    /// is negative, days, hours, minutes, seconds, micro seconds
-    Time(bool, u32, u8, u8, u8, u32),
+/// Quote an identifier (table or column name) for use in a MySQL query.
+pub(crate) fn quote_ident(name: &str) -> String {
+    format!("`{}`", name.replace('`', "``"))
 }
 
 impl From<Value> for ColumnValue {
@@ -28,7 +19,7 @@ impl From<Value> for ColumnValue {
         match value {
             Value::NULL => ColumnValue::NULL,
             Value::Bytes(buf) => {
-                let s = String::from_utf8(buf).unwrap();
+                let s = String::from_utf8_lossy(&buf).into_owned();
                 ColumnValue::Text(s)
             },
             Value::Int(v) => ColumnValue::Int(v),
@@ -39,4 +30,32 @@ impl From<Value> for ColumnValue {
             Value::Time(neg, d, h, min, sec, ms) => ColumnValue::Time(neg, d, h, min, sec, ms),
         }
     }
-}
\ No newline at end of file
+}
+
+impl From<&ColumnValue> for Value {
+    fn from(value: &ColumnValue) -> Self {
+        match value.clone() {
+            ColumnValue::NULL => Value::NULL,
+            ColumnValue::Text(v) => Value::Bytes(v.into_bytes()),
+            ColumnValue::Int(v) => Value::Int(v),
+            ColumnValue::UInt(v) => Value::UInt(v),
+            ColumnValue::Float(v) => Value::Float(v),
+            ColumnValue::Double(v) => Value::Double(v),
+            ColumnValue::Date(y, m, d, h, min, sec, ms) => Value::Date(y, m, d, h, min, sec, ms),
+            ColumnValue::Time(neg, d, h, min, sec, ms) => Value::Time(neg, d, h, min, sec, ms),
+        }
+    }
+}
+
+/// Convert a `mysql::Row` into the [`Row`] shared by every [`ConnectorType`](crate::ConnectorType).
+pub(crate) fn into_row(row: mysql::Row) -> Row {
+    let columns: Arc<[String]> = row
+        .columns_ref()
+        .iter()
+        .map(|col| col.name_str().into_owned())
+        .collect();
+
+    let values = row.unwrap().into_iter().map(ColumnValue::from).collect();
+
+    Row::new(columns, values)
+}
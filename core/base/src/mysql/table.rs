@@ -1,44 +1,56 @@
-use std::collections::HashMap;
-use common::{data::{columns::{Column, ColumnList}, table::{DataQueryResult, TableConfig, TableExportFormat, TableExportOpts, TableQueryOpts, UpdateTableData}}, error::AppError, query::{filter::FilterChain, BasableQuery, QueryCommand}};
+use std::{collections::HashMap, io::Write};
+use common::{data::{columns::{Column, ColumnList}, table::{DataQueryResult, TableConfig, TableExportOpts, TableEdit, TableQueryOpts, UpdateTableData}}, error::AppError, query::{filter::FilterChain, BasableQuery, QueryCommand}};
 
-use crate::{table::{Table, TableCRUD}, ConnectorType, SharedDB};
+use crate::{db::QueryParams, export::write_export, row::{ColumnValue, Row}, table::{apply_sql_edits, insert_statements, write_sql_rows, EditBatchError, EditOutcome, RowOutcome, SqlSyntax, Table, TableCRUD, WriteOpts}, ConnectorType, SharedDB};
 
-use super::ColumnValue;
+use super::quote_ident;
+
+pub(crate) const SYNTAX: SqlSyntax = SqlSyntax {
+    quote: quote_ident,
+    new_params: QueryParams::new,
+    mysql: true,
+    row_alias: true,
+};
 
 pub struct MySqlTable {
     pub name: String,
     pub connector: ConnectorType,
 }
 impl MySqlTable {
-    fn search_index_name(&self, search_cols: &Vec<String>) -> String {
+    fn quoted_name(&self) -> String {
+        quote_ident(&self.name)
+    }
+
+    fn search_index_name(&self, search_cols: &[String]) -> String {
         let name = format!("bsearch_{}", search_cols.join("_"));
         name.replace(" ", "_")
     }
 
-    fn create_search_index(&self, search_cols: &Vec<String>) -> Result<(), AppError> {
-        let wrap_cols: Vec<String> = search_cols.iter().map(|col| format!("`{col}`")).collect();
+    fn create_search_index(&self, search_cols: &[String]) -> Result<(), AppError> {
+        let wrap_cols: Vec<String> = search_cols.iter().map(|col| quote_ident(col)).collect();
 
-        let index_name = self.search_index_name(&search_cols);
+        let index_name = self.search_index_name(search_cols);
         let index_query = format!(
-            "CREATE FULLTEXT INDEX {index_name} 
+            "CREATE FULLTEXT INDEX {}
                 ON {} ({})",
-            self.name,
+            quote_ident(&index_name),
+            self.quoted_name(),
             wrap_cols.join(", ")
         );
 
         let conn = self.connector();
-        conn.exec_query(&index_query)?;
+        conn.exec_query(&index_query, &[])?;
 
         Ok(())
     }
 
-    fn search_index_exists(&self, search_cols: &Vec<String>) -> bool {
-        let index_name = self.search_index_name(&search_cols);
+    fn search_index_exists(&self, search_cols: &[String]) -> bool {
+        let index_name = self.search_index_name(search_cols);
 
-        let index_query = format!("SHOW INDEX FROM {}", self.name);
+        let index_query = format!("SHOW INDEX FROM {}", self.quoted_name());
         let conn = self.connector();
 
-        if let Ok(rows) = conn.exec_query(&index_query) {
+        if let Ok(rows) = conn.exec_query(&index_query, &[]) {
             let exists = rows.iter().find(|row| {
                 if let Some(name) = row.get::<String, &str>("Key_name") {
                     return name == index_name;
@@ -53,27 +65,34 @@ impl MySqlTable {
         false
     }
 
-    fn drop_search_index(&self, search_cols: &Vec<String>) -> Result<(), AppError> {
-        if self.search_index_exists(&search_cols) {
-            let index_name = self.search_index_name(&search_cols);
-            let index_query = format!("DROP INDEX {index_name} ON {};", self.name,);
+    fn drop_search_index(&self, search_cols: &[String]) -> Result<(), AppError> {
+        if self.search_index_exists(search_cols) {
+            let index_name = self.search_index_name(search_cols);
+            let index_query = format!(
+                "DROP INDEX {} ON {};",
+                quote_ident(&index_name),
+                self.quoted_name()
+            );
 
             let conn = self.connector();
-            conn.exec_query(&index_query)?;
+            conn.exec_query(&index_query, &[])?;
         }
 
         Ok(())
     }
 
-    fn search_prelude(&self, search_cols: &Vec<String>) -> Result<(), AppError> {
-        self.drop_search_index(&search_cols)?;
-        self.create_search_index(&search_cols)?;
+    fn search_prelude(&self, search_cols: &[String]) -> Result<(), AppError> {
+        let cols: Vec<&str> = search_cols.iter().map(|col| col.as_str()).collect();
+        self.check_columns(&cols)?;
+
+        self.drop_search_index(search_cols)?;
+        self.create_search_index(search_cols)?;
         Ok(())
     }
 }
 
 impl Table for MySqlTable {
-    type Row = mysql::Row;
+    type Row = Row;
 
     fn new(name: String, conn: ConnectorType) -> Self
     where
@@ -90,15 +109,14 @@ impl Table for MySqlTable {
     }
 
     fn query_columns(&self) -> Result<ColumnList, AppError> {
-        let table_name = &self.name;
+        let table_name = self.name.as_str();
 
-        let query = format!(
-            "
+        let query = "
             SELECT 
-                cols.column_name,
-                cols.column_type,
-                cols.is_nullable,
-                cols.column_default,
+                cols.column_name AS COLUMN_NAME,
+                cols.column_type AS COLUMN_TYPE,
+                cols.is_nullable AS IS_NULLABLE,
+                cols.column_default AS COLUMN_DEFAULT,
                 IF(stats.index_name IS NOT NULL, 'YES', 'NO') AS IS_UNIQUE,
                 IF(kcus.constraint_name IS NOT NULL, 'YES', 'NO') AS IS_PRIMARY
             FROM 
@@ -110,11 +128,11 @@ impl Table for MySqlTable {
                 FROM
                     information_schema.statistics
                 WHERE
-                    table_name = '{table_name}'
+                    table_name = ?
                     AND non_unique = 0) AS stats
             ON 
                 cols.column_name = stats.column_name
-                AND cols.table_name = '{table_name}'
+                AND cols.table_name = ?
             LEFT JOIN
                 information_schema.key_column_usage AS kcus
             ON
@@ -122,13 +140,12 @@ impl Table for MySqlTable {
                 AND cols.column_name = kcus.column_name
                 AND kcus.constraint_name = 'PRIMARY'
             WHERE
-                cols.table_name = '{table_name}'
-
-        "
-        );
+                cols.table_name = ?
+        ";
 
         let conn = self.connector();
-        let result = conn.exec_query(&query)?;
+        let params = [table_name.into(), table_name.into(), table_name.into()];
+        let result = conn.exec_query(query, &params)?;
 
         let cols: ColumnList = result
             .iter()
@@ -138,13 +155,13 @@ impl Table for MySqlTable {
                 let default: Option<String> = r.get("COLUMN_DEFAULT").unwrap();
 
                 let nullable: Option<String> = r.get("IS_NULLABLE");
-                let nullable = nullable.map(|s| s == "YES".to_owned()).unwrap();
+                let nullable = nullable.map(|s| s == "YES").unwrap();
 
                 let unique: Option<String> = r.get("IS_UNIQUE");
-                let unique = unique.map(|s| s == "YES".to_owned()).unwrap();
+                let unique = unique.map(|s| s == "YES").unwrap();
 
                 let primary: Option<String> = r.get("IS_PRIMARY");
-                let primary = primary.map(|s| s == "YES".to_owned()).unwrap();
+                let primary = primary.map(|s| s == "YES").unwrap();
 
                 Column {
                     name,
@@ -171,7 +188,7 @@ impl Table for MySqlTable {
             let mut iter = cols.iter();
             let mut pk = iter.find(|c| c.primary);
 
-            if let None = pk {
+            if pk.is_none() {
                 pk = iter.find(|c| c.unique);
             }
 
@@ -220,10 +237,11 @@ impl TableCRUD for MySqlTable {
             });
 
         let query = opts.try_into()?;
-        let sql = db.generate_sql(query)?;
+        db.check_query(&query)?;
+        let (sql, params) = db.generate_sql(query)?;
 
         let conn = self.connector();
-        let rows = conn.exec_query(&sql)?;
+        let rows = conn.exec_query(&sql, &params)?;
 
         let data = rows
             .iter()
@@ -231,8 +249,8 @@ impl TableCRUD for MySqlTable {
                 let mut map: HashMap<String, ColumnValue> = HashMap::new();
 
                 for col in &cols {
-                    if let Some(v) = r.get::<mysql::Value, &str>(col) {
-                        map.insert(col.clone(), v.into());
+                    if let Some(v) = r.get::<ColumnValue, &str>(col) {
+                        map.insert(col.clone(), v);
                     }
                 }
 
@@ -241,7 +259,7 @@ impl TableCRUD for MySqlTable {
             .collect();
 
         if is_search_mode {
-            let _ = self.drop_search_index(&search_cols)?;
+            self.drop_search_index(&search_cols)?;
         }
 
         Ok(data)
@@ -264,14 +282,15 @@ impl TableCRUD for MySqlTable {
             search_opts: opts.search_opts,
             filters: opts
                 .filters
-                .map_or(FilterChain::empty(), |fs| FilterChain::prefill(fs)),
+                .map_or(FilterChain::empty(), FilterChain::prefill),
             ..Default::default()
         };
 
-        let sql = db.generate_sql(query)?;
+        db.check_query(&query)?;
+        let (sql, params) = db.generate_sql(query)?;
 
         let conn = self.connector();
-        let rows = conn.exec_query(&sql)?;
+        let rows = conn.exec_query(&sql, &params)?;
 
         let count = rows
             .first()
@@ -279,36 +298,64 @@ impl TableCRUD for MySqlTable {
             .unwrap_or_default();
 
         if is_search_mode {
-            let _ = self.drop_search_index(&search_cols)?;
+            self.drop_search_index(&search_cols)?;
         }
 
         Ok(count)
     }
 
     fn insert_data(&self, input: HashMap<String, String>) -> Result<(), AppError> {
-        let len = input.len();
-        let mut data = HashMap::new();
+        let cols: Vec<&str> = input.keys().map(|k| k.as_str()).collect();
+        self.check_columns(&cols)?;
+
+        let mut params = QueryParams::new();
+        let mut keys = Vec::with_capacity(input.len());
+        let mut values = Vec::with_capacity(input.len());
 
         for (k, v) in input {
-            data.insert(k, format!("'{}'", v));
+            keys.push(quote_ident(&k));
+            values.push(params.bind(v));
         }
 
-        let mut keys = Vec::with_capacity(len);
-        let mut values = Vec::with_capacity(len);
+        let query = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            self.quoted_name(),
+            keys.join(", "),
+            values.join(", ")
+        );
+        let conn = self.connector();
+        conn.exec_query(&query, &params.into_values())?;
+
+        Ok(())
+    }
 
-        data.iter().for_each(|(k, v)| {
-            keys.push(k.as_str());
-            values.push(v.as_str());
-        });
+    fn insert_batch(&self, columns: &[String], rows: Vec<Vec<ColumnValue>>) -> Result<usize, AppError> {
+        let cols: Vec<&str> = columns.iter().map(|c| c.as_str()).collect();
+        self.check_columns(&cols)?;
 
-        let keys = keys.join(", ");
-        let values = values.join(", ");
+        let count = rows.len();
+        let statements =
+            insert_statements(&self.quoted_name(), columns, rows, quote_ident, QueryParams::new)?;
+        self.connector().exec_transaction(&statements)?;
 
-        let query = format!("INSERT INTO {} ({}) VALUES ({})", self.name, keys, values);
-        let conn = self.connector();
-        conn.exec_query(&query)?;
+        Ok(count)
+    }
 
-        Ok(())
+    fn write_rows(
+        &self,
+        columns: &[String],
+        rows: Vec<Vec<ColumnValue>>,
+        opts: WriteOpts,
+    ) -> Result<Vec<RowOutcome>, AppError> {
+        write_sql_rows(self, &SYNTAX, columns, rows, opts)
+    }
+
+    fn apply_edits(
+        &self,
+        unique_key: &str,
+        edits: Vec<TableEdit>,
+    ) -> Result<Vec<EditOutcome>, EditBatchError> {
+        apply_sql_edits(self, &SYNTAX, unique_key, edits)
     }
 
     fn update_data(&self, options: UpdateTableData) -> Result<(), AppError> {
@@ -319,61 +366,78 @@ impl TableCRUD for MySqlTable {
             input,
         } = options;
 
+        let mut cols: Vec<&str> = columns.iter().map(|col| col.as_str()).collect();
+        cols.push(&unique_key);
+        self.check_columns(&cols)?;
+
+        let unique_key = quote_ident(&unique_key);
+        let mut params = QueryParams::new();
+
         let mut cases = vec![];
         for (index, col) in columns.iter().enumerate() {
             let cmd = if index == 0 { "SET \n" } else { "" };
-            let mut q = format!("{cmd} `{col}` = CASE `{unique_key}` \n");
+            let quoted_col = quote_ident(col);
+            let mut q = format!("{cmd} {quoted_col} = CASE {unique_key} \n");
 
             for (index, uv) in unique_values.iter().enumerate() {
                 if let Some(values) = input.get(index) {
                     if let Some(val) = values.get(col) {
-                        q.push_str(&format!("WHEN {uv} THEN '{val}' \n"));
+                        let when = params.bind(uv.as_str());
+                        let then = params.bind(val.as_str());
+                        q.push_str(&format!("WHEN {when} THEN {then} \n"));
                     }
                 }
             }
 
-            q.push_str(&format!("ELSE `{col}` \n END"));
+            q.push_str(&format!("ELSE {quoted_col} \n END"));
             cases.push(q);
         }
 
         let cases = cases.join(", \n");
-        let unique_values = unique_values.join(",");
+        let unique_values = params.bind_list(unique_values.iter().map(|v| v.as_str()));
 
         let query = format!(
-            "UPDATE {} \n {} WHERE `{}` IN ({})",
-            self.name, cases, unique_key, unique_values
+            "UPDATE {} \n {} WHERE {} IN ({})",
+            self.quoted_name(),
+            cases,
+            unique_key,
+            unique_values
         );
 
         let conn = self.connector();
-        conn.exec_query(&query)?;
+        conn.exec_query(&query, &params.into_values())?;
 
         Ok(())
     }
 
     fn delete_data(&self, col: &str, values: Vec<&str>) -> Result<(), AppError> {
-        let query_prefix = format!("DELETE FROM {} WHERE", self.name);
-        let query_suffix = if values.len() == 1 {
-            match values.first() {
-                Some(value) => Ok(format!("{col} = '{value}'")),
-                None => Err(AppError::ServerError("a value must be provided".to_string()))
-            }?
-        } else {
-            let vl = values.join(", ");
-            format!("{col} IN ({vl})")
-        };
+        if values.is_empty() {
+            return Err(AppError::ServerError("a value must be provided".to_string()));
+        }
 
-        let query = format!("{query_prefix} {query_suffix}");
+        self.check_columns(&[col])?;
+
+        let mut params = QueryParams::new();
+        let values = params.bind_list(values);
+
+        let query = format!(
+            "DELETE FROM {} WHERE {} IN ({values})",
+            self.quoted_name(),
+            quote_ident(col)
+        );
         let conn = self.connector();
-        conn.exec_query(&query)?;
+        conn.exec_query(&query, &params.into_values())?;
 
         Ok(())
     }
 
-    fn export(&self, opts: TableExportOpts, db: &SharedDB) -> Result<String, AppError> {
+    fn export(&self, opts: TableExportOpts, db: &SharedDB, out: &mut (dyn Write + Send)) -> Result<(), AppError> {
         let TableExportOpts {
             query_opts,
             format,
             trim,
+            xml,
+            ..
         } = opts;
 
         let cols = query_opts
@@ -403,102 +467,22 @@ impl TableCRUD for MySqlTable {
             table: query_opts.table,
             command: QueryCommand::SelectData(selection),
             filters,
-            offset: trim.as_ref().map_or(None, |trim| Some(trim.offset)),
-            row_count: trim.map_or(None, |trim| Some(trim.count)),
+            offset: trim.as_ref().map(|trim| trim.offset),
+            row_count: trim.map(|trim| trim.count),
             ..Default::default()
         };
 
-        let sql = db.generate_sql(query)?;
-
-        let conn = self.connector();
-        let rows = conn.exec_query(&sql)?;
-        let content = process_exports(format, cols, rows);
+        db.check_query(&query)?;
+        let (sql, params) = db.generate_sql(query)?;
 
-        Ok(content)
+        write_export(self.connector(), format, xml, cols, &sql, &params, out)
     }
     
     fn clear(&self) -> Result<(), AppError> {
-        let query = format!("DELETE FROM {}", self.name);
+        let query = format!("DELETE FROM {}", self.quoted_name());
         let conn = self.connector();
-        conn.exec_query(&query)?;
+        conn.exec_query(&query, &[])?;
 
         Ok(())
     }
 }
-
-fn process_exports(
-    format: TableExportFormat,
-    columns: Vec<String>,
-    rows: Vec<mysql::Row>,
-) -> String {
-    match format {
-        TableExportFormat::CSV
-        | TableExportFormat::PSV
-        | TableExportFormat::TSV
-        | TableExportFormat::TEXT => {
-            let delimiter = format.field_delimiter().unwrap_or_default();
-            let headers = columns.join(&delimiter);
-            let row_list: Vec<String> = rows
-                .iter()
-                .map(|row| {
-                    let mut row_data = Vec::with_capacity(columns.len());
-                    for col in &columns {
-                        row_data.push(row.get::<String, &str>(col).unwrap_or_default());
-                    }
-
-                    row_data.join(&delimiter)
-                })
-                .collect();
-
-            let body = row_list.join("\n");
-            format!("{headers} \n {body}")
-        }
-        TableExportFormat::JSON => {
-            let row_list: Vec<String> = rows
-                .iter()
-                .map(|row| {
-                    let values: Vec<String> = columns
-                        .iter()
-                        .map(|col| {
-                            let val = row.get::<String, &str>(col).unwrap_or_default();
-                            format!("\t\"{}\":\"{}\"", col, val)
-                        })
-                        .collect();
-
-                    format!("{{\n\t{}\n\t}}", values.join(",\n\t"))
-                })
-                .collect();
-
-            format!("[\n\t{}\n]", row_list.join(",\n\t"))
-        }
-        TableExportFormat::HTML => {
-            let col_list: Vec<String> = columns
-                .iter()
-                .map(|col| format!("<th>{col}</th>"))
-                .collect();
-            let header = format!(
-                "<thead>\n\t\t<tr>\n\t\t\t{}\n\t\t</tr>\n\t</thead>",
-                col_list.join("\n\t\t\t")
-            );
-
-            let row_list: Vec<String> = rows
-                .iter()
-                .map(|row| {
-                    let td_list: Vec<String> = columns
-                        .iter()
-                        .map(|col| {
-                            let val = row.get::<String, &str>(col).unwrap_or_default();
-                            format!("<td>{val}</td>")
-                        })
-                        .collect();
-
-                    format!("<tr>\n\t\t\t{}\n\t\t</tr>", td_list.join("\n\t\t\t"))
-                })
-                .collect();
-            let body = format!("<tbody>\n\t\t{}\n\t</tbody>", row_list.join("\n\t\t"));
-
-            format!("<table>\n\t{header}\n\t{body}\n<table>")
-        }
-        _ => "".to_string(),
-    }
-}
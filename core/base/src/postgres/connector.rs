@@ -0,0 +1,119 @@
+use common::error::AppError;
+use postgres::{fallible_iterator::FallibleIterator, types::ToSql, Config, NoTls};
+use r2d2::Pool;
+use r2d2_postgres::PostgresConnectionManager;
+
+use crate::{
+    config::ConfigRaw,
+    connector::{run_blocking, Connector, Statement, TransactionError},
+    row::{ColumnValue, Row},
+};
+
+use super::into_rows;
+
+type PostgresPool = Pool<PostgresConnectionManager<NoTls>>;
+
+/// PostgreSQL implementation of `BasableConnection`
+#[derive(Clone)]
+pub struct PostgresConnector {
+    /// Database connection pool
+    pub pool: PostgresPool,
+
+    /// Connection options
+    pub config: ConfigRaw,
+}
+
+impl Connector for PostgresConnector {
+    type Row = Row;
+
+    fn new(config: ConfigRaw) -> Result<Self, AppError> {
+        let url = config.build_url()?;
+        let opts: Config = url
+            .parse()
+            .map_err(|err: postgres::Error| AppError::ServerError(err.to_string()))?;
+
+        let manager = PostgresConnectionManager::new(opts, NoTls);
+
+        run_blocking(|| Pool::new(manager))
+            .map(|pool| PostgresConnector { pool, config })
+            .map_err(|err| AppError::ServerError(err.to_string()))
+    }
+
+    fn exec_query(&self, query: &str, params: &[ColumnValue]) -> Result<Vec<Self::Row>, AppError> {
+        let params: Vec<&(dyn ToSql + Sync)> = params.iter().map(|p| p as &(dyn ToSql + Sync)).collect();
+
+        run_blocking(|| {
+            let mut conn = self
+                .pool
+                .get()
+                .map_err(|err| AppError::ServerError(err.to_string()))?;
+
+            let rows = conn
+                .query(query, &params)
+                .map_err(|err| AppError::ServerError(err.to_string()))?;
+
+            Ok(into_rows(&rows))
+        })
+    }
+
+    fn for_each_row(
+        &self,
+        query: &str,
+        params: &[ColumnValue],
+        f: &mut (dyn FnMut(Self::Row) -> Result<(), AppError> + Send),
+    ) -> Result<(), AppError> {
+        let to_app_error = |err: postgres::Error| AppError::ServerError(err.to_string());
+        let params = params.iter().map(|p| p as &dyn ToSql);
+
+        run_blocking(|| {
+            let mut conn = self
+                .pool
+                .get()
+                .map_err(|err| AppError::ServerError(err.to_string()))?;
+
+            let mut rows = conn.query_raw(query, params).map_err(to_app_error)?;
+            while let Some(row) = rows.next().map_err(to_app_error)? {
+                into_rows(std::slice::from_ref(&row)).into_iter().try_for_each(&mut *f)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn exec_transaction(
+        &self,
+        statements: &[Statement],
+    ) -> Result<Vec<Vec<Self::Row>>, TransactionError> {
+        let failed = |index: usize| {
+            move |err: String| TransactionError {
+                index,
+                error: AppError::ServerError(err),
+            }
+        };
+
+        run_blocking(|| {
+            let mut conn = self.pool.get().map_err(|err| failed(0)(err.to_string()))?;
+            // Dropping the transaction before it's committed rolls it back.
+            let mut tx = conn.transaction().map_err(|err| failed(0)(err.to_string()))?;
+
+            let mut results = Vec::with_capacity(statements.len());
+            for (index, (query, params)) in statements.iter().enumerate() {
+                let params: Vec<&(dyn ToSql + Sync)> =
+                    params.iter().map(|p| p as &(dyn ToSql + Sync)).collect();
+
+                let rows = tx
+                    .query(query.as_str(), &params)
+                    .map_err(|err| failed(index)(err.to_string()))?;
+                results.push(into_rows(&rows));
+            }
+
+            tx.commit()
+                .map_err(|err| failed(statements.len().saturating_sub(1))(err.to_string()))?;
+            Ok(results)
+        })
+    }
+
+    fn config(&self) -> &ConfigRaw {
+        &self.config
+    }
+}
@@ -0,0 +1,256 @@
+use std::{collections::HashMap, sync::Arc};
+
+use common::{
+    data::table::{TableSearchOpts, TableSummaries, TableSummary},
+    error::AppError,
+    query::{
+        filter::{Filter, FilterCombinator, FilterExpression},
+    },
+    DBVersion, DbServerDetails,
+};
+use crate::row::{ColumnValue, Row};
+use uuid::Uuid;
+
+use crate::{
+    db::{QueryParams, QuerySqlParser, DB},
+    table::Table,
+    ConnectorType, SharedTable,
+};
+
+use super::{quote_ident, table::PostgresTable};
+
+pub struct PostgresDB {
+    pub connector: ConnectorType,
+    pub tables: Vec<SharedTable>,
+    user_id: String,
+    id: Uuid,
+}
+
+impl PostgresDB {
+    pub fn new(connector: ConnectorType, user_id: String) -> Self {
+        PostgresDB {
+            connector,
+            tables: Vec::new(),
+            user_id,
+            id: Uuid::new_v4(),
+        }
+    }
+
+    /// Get PostgreSQL server version and host OS version
+    fn show_version_variables(&self) -> Result<DBVersion, AppError> {
+        let vars = self.exec_query(
+            "SELECT current_setting('server_version') AS version, version() AS version_comment",
+            &[],
+        )?;
+
+        let mut data = HashMap::new();
+
+        if let Some(v) = vars.first() {
+            let version: String = v.get("version").unwrap_or_default();
+            let comment: String = v.get("version_comment").unwrap_or_default();
+
+            // version() reads like "PostgreSQL 16.2 on x86_64-pc-linux-gnu, compiled by gcc ..."
+            let os = comment
+                .split_once(" on ")
+                .and_then(|(_, rest)| rest.split(',').next())
+                .unwrap_or_default()
+                .to_string();
+
+            data.insert("version".to_string(), version);
+            data.insert("version_comment".to_string(), comment);
+            data.insert("version_compile_os".to_string(), os);
+        }
+
+        Ok(data)
+    }
+
+    fn size(&self) -> Result<f64, AppError> {
+        let qr = self.exec_query(
+            "SELECT ROUND(pg_database_size(current_database()) / 1024.0 / 1024.0, 1)::float8 AS size",
+            &[],
+        )?;
+
+        // db size is returned in MB
+        let size: f64 = qr.first().map_or(0.0, |r| r.get("size").unwrap_or_default());
+
+        Ok(size)
+    }
+
+    fn exec_query(&self, query: &str, params: &[ColumnValue]) -> Result<Vec<Row>, AppError> {
+        self.connector.exec_query(query, params)
+    }
+}
+
+impl DB for PostgresDB {
+    type Row = Row;
+
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+
+    fn set_id(&mut self, id: Uuid) {
+        self.id = id;
+    }
+
+    fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    fn connector(&self) -> &ConnectorType {
+        &self.connector
+    }
+
+    fn load_tables(&mut self, connector: ConnectorType) -> Result<(), AppError> {
+        let tables = self.query_tables()?;
+
+        tables.iter().for_each(|t| {
+            let connector = connector.clone();
+            let name: String = t.get("TABLE_NAME").unwrap();
+
+            let table = PostgresTable::new(name, connector);
+            self.tables.push(Arc::new(table));
+        });
+
+        Ok(())
+    }
+
+    fn tables(&self) -> &Vec<SharedTable> {
+        &self.tables
+    }
+
+    fn query_tables(&self) -> Result<Vec<Row>, AppError> {
+        // PostgreSQL doesn't record when tables are created or updated.
+        let query = "
+            SELECT
+                c.relname AS \"TABLE_NAME\",
+                GREATEST(c.reltuples, 0)::bigint AS \"TABLE_ROWS\"
+            FROM pg_catalog.pg_class c
+            JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+            WHERE c.relkind IN ('r', 'p') AND n.nspname = current_schema()
+            ORDER BY c.relname
+        ";
+
+        self.connector.exec_query(query, &[])
+    }
+
+    fn build_table_list(&self) -> Result<TableSummaries, AppError> {
+        let results = self.query_tables()?;
+        let mut tables = Vec::with_capacity(results.len());
+
+        for res in results {
+            let name: String = res.get("TABLE_NAME").unwrap();
+            let col_count = self.query_column_count(&name)?;
+
+            tables.push(TableSummary {
+                name,
+                col_count,
+                row_count: res.get("TABLE_ROWS").unwrap_or_default(),
+                created: None,
+                updated: None,
+            });
+        }
+
+        Ok(tables)
+    }
+
+    fn query_column_count(&self, tb_name: &str) -> Result<u32, AppError> {
+        let query = "
+                SELECT COUNT(*) AS count
+                FROM information_schema.columns
+                WHERE table_schema = current_schema() AND table_name = $1
+            ";
+
+        let qr = self.exec_query(query, &[tb_name.into()])?;
+        let c: u32 = qr.first().map_or(0, |r| r.get("count").unwrap_or_default());
+
+        Ok(c)
+    }
+
+    fn get_table(&self, name: &str) -> Option<&SharedTable> {
+        self.tables.iter().find(|t| t.name() == name)
+    }
+
+    fn details(&self) -> Result<DbServerDetails, AppError> {
+        let vrbs = self.show_version_variables()?;
+        let version = vrbs.get("version").map(|v| v.to_string());
+        let os = vrbs.get("version_compile_os").map(|v| v.to_string());
+        let comment = vrbs.get("version_comment").map(|v| v.to_string());
+
+        let size = self.size()?;
+
+        Ok(DbServerDetails {
+            version: version.unwrap_or_default(),
+            os: os.unwrap_or_default(),
+            comment,
+            db_size: size,
+        })
+    }
+
+    fn drop_table(&self, table_name: &str) -> Result<(), AppError> {
+        self.check_columns(table_name, &[])?;
+
+        let query = format!("DROP TABLE {}", quote_ident(table_name));
+        let conn = self.connector();
+        conn.exec_query(&query, &[])?;
+
+        Ok(())
+    }
+}
+
+/// Text operators (`LIKE`, `~`) need the column cast to text so they also work on
+/// numeric and date columns.
+fn column_cast(expression: &FilterExpression) -> &'static str {
+    match expression {
+        FilterExpression::Contains(_)
+        | FilterExpression::NotContains(_)
+        | FilterExpression::Regex(_)
+        | FilterExpression::NotRegex(_) => "::text",
+        _ => "",
+    }
+}
+
+impl QuerySqlParser for PostgresDB {
+    fn quote_ident(&self, name: &str) -> String {
+        quote_ident(name)
+    }
+
+    fn new_params(&self) -> QueryParams {
+        QueryParams::numbered()
+    }
+
+    fn regex_operator(&self, negated: bool) -> &'static str {
+        match negated {
+            true => "!~",
+            false => "~",
+        }
+    }
+
+    fn parse_filter(&self, filter: &Filter, params: &mut QueryParams) -> String {
+        let comb = match filter.combinator {
+            FilterCombinator::AND => "AND ",
+            FilterCombinator::OR => "OR ",
+            FilterCombinator::BASE => "",
+        };
+
+        format!(
+            "{comb}{}{} {}",
+            quote_ident(&filter.column),
+            column_cast(&filter.expression),
+            self.parse_expression(&filter.expression, params)
+        )
+    }
+
+    fn parse_search(&self, opts: &TableSearchOpts, params: &mut QueryParams) -> String {
+        let wrap_cols: Vec<String> = opts.search_cols.iter().map(|col| quote_ident(col)).collect();
+
+        format!(
+            "to_tsvector('simple', concat_ws(' ', {})) @@ plainto_tsquery('simple', {})",
+            wrap_cols.join(", "),
+            params.bind(opts.query.as_str())
+        )
+    }
+
+    fn parse_limit(&self, row_count: usize, offset: usize) -> String {
+        format!(" LIMIT {row_count} OFFSET {offset}")
+    }
+}
@@ -0,0 +1,205 @@
+use common::error::AppError;
+use crate::row::ColumnValue;
+use time::Date;
+
+use crate::{
+    db::DB,
+    globals::{BASABLE_CHRONO_XCOL, BASABLE_CHRONO_YCOL},
+    graphs::{
+        category::CategoryGraphOpts,
+        chrono::{ChronoAnalysisBasis, ChronoAnalysisOpts},
+        geo::GeoGraphOpts,
+        trend::{CrossOptions, TrendGraphOpts, TrendGraphOrder, TrendGraphType},
+        AnalysisResult, AnalysisResults, AnalysisValue, VisualizeDB,
+    },
+};
+
+use super::{db::PostgresDB, quote_ident};
+
+/// `LIMIT` clause for graphs where a zero or missing limit means "everything".
+fn limit_clause(limit: Option<usize>) -> String {
+    match limit {
+        Some(limit) if limit > 0 => format!(" LIMIT {limit}"),
+        _ => String::new(),
+    }
+}
+
+impl PostgresDB {
+    /// Count rows per distinct value of `target_col`.
+    fn count_by_column(
+        &self,
+        table: &str,
+        target_col: &str,
+        limit: Option<usize>,
+    ) -> Result<AnalysisResults, AppError> {
+        let col = quote_ident(target_col);
+        let sql = format!(
+            "SELECT COUNT(*) AS \"COUNT\", {col} FROM {} GROUP BY {col} ORDER BY 1 DESC{}",
+            quote_ident(table),
+            limit_clause(limit)
+        );
+
+        let conn = self.connector();
+        let rows = conn.exec_query(&sql, &[])?;
+
+        let results: AnalysisResults = rows
+            .iter()
+            .map(|r| {
+                let x_value: ColumnValue = r.get(target_col).unwrap_or(ColumnValue::NULL);
+                let x = x_value.try_into().unwrap_or_default();
+
+                let y = AnalysisValue::UInt(r.get("COUNT").unwrap());
+
+                AnalysisResult::new(x, y)
+            })
+            .collect();
+
+        Ok(results)
+    }
+}
+
+impl VisualizeDB for PostgresDB {
+    fn chrono_graph(&self, opts: ChronoAnalysisOpts) -> Result<AnalysisResults, AppError> {
+        opts.check(self)?;
+
+        let ChronoAnalysisOpts {
+            table,
+            chrono_col,
+            basis,
+            range,
+        } = opts;
+
+        let col = quote_ident(&chrono_col);
+        let basis_expr = match basis {
+            ChronoAnalysisBasis::Daily => format!("{col}::date"),
+            ChronoAnalysisBasis::Monthly => format!("EXTRACT(MONTH FROM {col})::bigint"),
+            ChronoAnalysisBasis::Yearly => format!("EXTRACT(YEAR FROM {col})::bigint"),
+        };
+
+        let sql = format!(
+            "
+            SELECT {basis_expr} AS \"{BASABLE_CHRONO_XCOL}\", COUNT(*) AS \"{BASABLE_CHRONO_YCOL}\"
+            FROM {}
+            WHERE {col} BETWEEN $1 AND $2
+            GROUP BY 1
+            ORDER BY 1 ASC
+        ",
+            quote_ident(&table)
+        );
+
+        let params = [range.start().into(), range.end().into()];
+
+        let conn = self.connector();
+        let rows = conn.exec_query(&sql, &params)?;
+
+        let results: AnalysisResults = rows
+            .iter()
+            .map(|r| {
+                let x = match basis {
+                    ChronoAnalysisBasis::Daily => {
+                        let date: Date = r.get(BASABLE_CHRONO_XCOL).unwrap();
+                        AnalysisValue::Date(date)
+                    }
+                    _ => AnalysisValue::UInt(r.get(BASABLE_CHRONO_XCOL).unwrap()),
+                };
+
+                let y = AnalysisValue::UInt(r.get(BASABLE_CHRONO_YCOL).unwrap());
+
+                AnalysisResult::new(x, y)
+            })
+            .collect();
+
+        Ok(results)
+    }
+
+    fn trend_graph(&self, opts: TrendGraphOpts) -> Result<AnalysisResults, AppError> {
+        opts.check(self)?;
+
+        let TrendGraphOpts {
+            table,
+            graph_type,
+            xcol,
+            ycol,
+            order,
+            limit,
+            cross,
+        } = opts;
+
+        let order = match order {
+            Some(TrendGraphOrder::ASC) => "ASC",
+            _ => "DESC",
+        };
+
+        let x = quote_ident(&xcol);
+        let y = quote_ident(&ycol);
+
+        let sql = match &graph_type {
+            TrendGraphType::IntraModel => format!(
+                "SELECT {x}::text AS {x}, {y}::float8 AS {y} FROM {} ORDER BY {y} {order}{}",
+                quote_ident(&table),
+                limit_clause(limit)
+            ),
+            TrendGraphType::CrossModel => {
+                let CrossOptions {
+                    foreign_table,
+                    target_col,
+                } = cross.ok_or_else(|| {
+                    AppError::HttpError(
+                        axum::http::StatusCode::EXPECTATION_FAILED,
+                        "You must provide cross model options.".to_string(),
+                    )
+                })?;
+
+                // PostgreSQL doesn't allow output aliases in HAVING, so we repeat the aggregate.
+                format!(
+                    "
+                    SELECT x.{x}::text AS {x}, COUNT(y.{y}) AS {y}
+                    FROM {} x
+                    LEFT JOIN {} y ON x.{} = y.{y}
+                    GROUP BY x.{x}
+                    HAVING COUNT(y.{y}) > 0
+                    ORDER BY {y} {order}{}
+                ",
+                    quote_ident(&table),
+                    quote_ident(&foreign_table),
+                    quote_ident(&target_col),
+                    limit_clause(limit)
+                )
+            }
+        };
+
+        let conn = self.connector();
+        let rows = conn.exec_query(&sql, &[])?;
+
+        let results: AnalysisResults = rows
+            .iter()
+            .map(|r| {
+                let x = AnalysisValue::Text(r.get(xcol.as_str()).unwrap_or_default());
+                let y = match &graph_type {
+                    TrendGraphType::IntraModel => {
+                        AnalysisValue::Double(r.get(ycol.as_str()).unwrap_or_default())
+                    }
+                    TrendGraphType::CrossModel => {
+                        AnalysisValue::UInt(r.get(ycol.as_str()).unwrap_or_default())
+                    }
+                };
+
+                AnalysisResult::new(x, y)
+            })
+            .collect();
+
+        Ok(results)
+    }
+
+    fn category_graph(&self, opts: CategoryGraphOpts) -> Result<AnalysisResults, AppError> {
+        opts.check(self)?;
+
+        self.count_by_column(&opts.table, &opts.target_column, opts.limit)
+    }
+
+    fn geo_graph(&self, opts: GeoGraphOpts) -> Result<AnalysisResults, AppError> {
+        opts.check(self)?;
+
+        self.count_by_column(&opts.table, &opts.target_column, None)
+    }
+}
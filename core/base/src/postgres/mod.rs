@@ -0,0 +1,275 @@
+use std::{error::Error, sync::Arc};
+
+use crate::row::{ColumnValue, FromColumnValue, Row};
+use bytes::BytesMut;
+use postgres::types::{to_sql_checked, Format, FromSql, IsNull, ToSql, Type};
+use time::{Date, OffsetDateTime, PrimitiveDateTime, Time};
+
+
+pub mod connector;
+pub mod db;
+pub mod graphs;
+pub mod table;
+
+/// Quote an identifier (table or column name) for use in a PostgreSQL query.
+pub(crate) fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Convert the rows of a result into the [`Row`] shared by every
+/// [`ConnectorType`](crate::ConnectorType).
+pub(crate) fn into_rows(rows: &[postgres::Row]) -> Vec<Row> {
+    let Some(first) = rows.first() else {
+        return Vec::new();
+    };
+
+    let columns: Arc<[String]> = first.columns().iter().map(|col| col.name().to_string()).collect();
+
+    rows.iter()
+        .map(|row| {
+            let values = (0..row.len()).map(|idx| column_value(row, idx)).collect();
+            Row::new(columns.clone(), values)
+        })
+        .collect()
+}
+
+fn column_value(row: &postgres::Row, idx: usize) -> ColumnValue {
+    let col_type = row.columns()[idx].type_();
+
+    let value = match *col_type {
+        Type::BOOL => row
+            .try_get::<_, Option<bool>>(idx)
+            .map(|v| v.map(|b| ColumnValue::Int(b as i64))),
+        Type::CHAR => row
+            .try_get::<_, Option<i8>>(idx)
+            .map(|v| v.map(|i| ColumnValue::Int(i as i64))),
+        Type::INT2 => row
+            .try_get::<_, Option<i16>>(idx)
+            .map(|v| v.map(|i| ColumnValue::Int(i as i64))),
+        Type::INT4 => row
+            .try_get::<_, Option<i32>>(idx)
+            .map(|v| v.map(|i| ColumnValue::Int(i as i64))),
+        Type::INT8 => row.try_get::<_, Option<i64>>(idx).map(|v| v.map(ColumnValue::Int)),
+        Type::OID => row
+            .try_get::<_, Option<u32>>(idx)
+            .map(|v| v.map(|i| ColumnValue::UInt(i as u64))),
+        Type::FLOAT4 => row.try_get::<_, Option<f32>>(idx).map(|v| v.map(ColumnValue::Float)),
+        Type::FLOAT8 => row.try_get::<_, Option<f64>>(idx).map(|v| v.map(ColumnValue::Double)),
+        Type::NUMERIC => row
+            .try_get::<_, Option<PgNumeric>>(idx)
+            .map(|v| v.map(|n| ColumnValue::Text(n.0))),
+        Type::DATE => row
+            .try_get::<_, Option<Date>>(idx)
+            .map(|v| v.map(|d| ColumnValue::Date(d.year() as u16, d.month() as u8, d.day(), 0, 0, 0, 0))),
+        Type::TIMESTAMP => row
+            .try_get::<_, Option<PrimitiveDateTime>>(idx)
+            .map(|v| v.map(datetime_value)),
+        Type::TIMESTAMPTZ => row
+            .try_get::<_, Option<OffsetDateTime>>(idx)
+            .map(|v| v.map(|dt| datetime_value(to_utc(dt)))),
+        Type::TIME => row.try_get::<_, Option<Time>>(idx).map(|v| {
+            v.map(|t| ColumnValue::Time(false, 0, t.hour(), t.minute(), t.second(), t.microsecond()))
+        }),
+        Type::UUID => row
+            .try_get::<_, Option<uuid::Uuid>>(idx)
+            .map(|v| v.map(|id| ColumnValue::Text(id.to_string()))),
+        Type::BYTEA => row
+            .try_get::<_, Option<Vec<u8>>>(idx)
+            .map(|v| v.map(|buf| ColumnValue::Text(hex_bytes(&buf)))),
+        _ => row
+            .try_get::<_, Option<PgText>>(idx)
+            .map(|v| v.map(|t| ColumnValue::Text(t.0))),
+    };
+
+    match value {
+        Ok(v) => v.unwrap_or(ColumnValue::NULL),
+        Err(err) => {
+            tracing::error!("error reading postgres column of type {col_type}: {err}");
+            ColumnValue::NULL
+        }
+    }
+}
+
+fn datetime_value(dt: PrimitiveDateTime) -> ColumnValue {
+    ColumnValue::Date(
+        dt.year() as u16,
+        dt.month() as u8,
+        dt.day(),
+        dt.hour(),
+        dt.minute(),
+        dt.second(),
+        dt.microsecond(),
+    )
+}
+
+fn to_utc(dt: OffsetDateTime) -> PrimitiveDateTime {
+    let dt = dt.to_offset(time::UtcOffset::UTC);
+    PrimitiveDateTime::new(dt.date(), dt.time())
+}
+
+/// Render bytes the way `psql` displays `bytea` values.
+fn hex_bytes(buf: &[u8]) -> String {
+    let hex: Vec<String> = buf.iter().map(|b| format!("{b:02x}")).collect();
+    format!("\\x{}", hex.join(""))
+}
+
+/// Fallback for types we don't map explicitly (`text`, `varchar`, `json`, enums...etc).
+/// Their binary representation is UTF-8 text.
+struct PgText(String);
+
+impl<'a> FromSql<'a> for PgText {
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        // jsonb is prefixed with a version byte
+        let raw = match *ty {
+            Type::JSONB => raw.get(1..).unwrap_or_default(),
+            _ => raw,
+        };
+
+        let text = std::str::from_utf8(raw)?;
+        Ok(PgText(text.to_string()))
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+}
+
+/// `numeric` values decoded into their exact decimal representation.
+struct PgNumeric(String);
+
+impl<'a> FromSql<'a> for PgNumeric {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        decode_numeric(raw).map(PgNumeric)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        *ty == Type::NUMERIC
+    }
+}
+
+/// Decode the binary `numeric` format: a header of `ndigits`, `weight`, `sign` and `dscale`
+/// followed by `ndigits` base-10000 digits.
+fn decode_numeric(raw: &[u8]) -> Result<String, Box<dyn Error + Sync + Send>> {
+    let read = |pos: usize| -> Option<u16> {
+        raw.get(pos..pos + 2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+    };
+
+    let header = (read(0), read(2), read(4), read(6));
+    let (ndigits, weight, sign, dscale) = match header {
+        (Some(n), Some(w), Some(s), Some(d)) => (n as usize, w as i16 as i32, s, d as usize),
+        _ => return Err("invalid numeric header".into()),
+    };
+
+    match sign {
+        0xC000 => return Ok("NaN".to_string()),
+        0xD000 => return Ok("Infinity".to_string()),
+        0xF000 => return Ok("-Infinity".to_string()),
+        _ => {}
+    }
+
+    let mut digits = Vec::with_capacity(ndigits);
+    for i in 0..ndigits {
+        let digit = read(8 + i * 2).ok_or("invalid numeric digits")?;
+        digits.push(digit);
+    }
+
+    let digit_at = |pos: i32| -> u16 {
+        if pos < 0 {
+            return 0;
+        }
+        digits.get(pos as usize).copied().unwrap_or(0)
+    };
+
+    let mut value = String::new();
+    if sign == 0x4000 {
+        value.push('-');
+    }
+
+    if weight < 0 {
+        value.push('0');
+    } else {
+        for pos in 0..=weight {
+            let digit = digit_at(pos);
+            if pos == 0 {
+                value.push_str(&digit.to_string());
+            } else {
+                value.push_str(&format!("{digit:04}"));
+            }
+        }
+    }
+
+    if dscale > 0 {
+        let mut fraction = String::with_capacity(dscale + 4);
+        let mut pos = weight + 1;
+
+        while fraction.len() < dscale {
+            fraction.push_str(&format!("{:04}", digit_at(pos)));
+            pos += 1;
+        }
+
+        fraction.truncate(dscale);
+        value.push('.');
+        value.push_str(&fraction);
+    }
+
+    Ok(value)
+}
+
+/// Query parameters are sent in text format, so the server parses them as whatever type the
+/// placeholder is inferred to have, just like it would a quoted literal.
+impl ToSql for ColumnValue {
+    fn to_sql(&self, _: &Type, out: &mut BytesMut) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        match String::from_value(self) {
+            Some(text) => {
+                out.extend_from_slice(text.as_bytes());
+                Ok(IsNull::No)
+            }
+            None => Ok(IsNull::Yes),
+        }
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+
+    fn encode_format(&self, _: &Type) -> Format {
+        Format::Text
+    }
+
+    to_sql_checked!();
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::decode_numeric;
+
+    fn encode(ndigits: u16, weight: i16, sign: u16, dscale: u16, digits: &[u16]) -> Vec<u8> {
+        let mut raw = Vec::new();
+        raw.extend_from_slice(&ndigits.to_be_bytes());
+        raw.extend_from_slice(&weight.to_be_bytes());
+        raw.extend_from_slice(&sign.to_be_bytes());
+        raw.extend_from_slice(&dscale.to_be_bytes());
+        digits.iter().for_each(|d| raw.extend_from_slice(&d.to_be_bytes()));
+
+        raw
+    }
+
+    #[test]
+    pub fn test_decode_numeric() {
+        // 12345.678
+        let raw = encode(3, 1, 0, 3, &[1, 2345, 6780]);
+        assert_eq!(decode_numeric(&raw).unwrap(), "12345.678");
+
+        // -0.00012
+        let raw = encode(2, -1, 0x4000, 5, &[1, 2000]);
+        assert_eq!(decode_numeric(&raw).unwrap(), "-0.00012");
+
+        // 0.000012
+        let raw = encode(1, -2, 0, 6, &[1200]);
+        assert_eq!(decode_numeric(&raw).unwrap(), "0.000012");
+
+        // 20000
+        let raw = encode(1, 1, 0, 0, &[2]);
+        assert_eq!(decode_numeric(&raw).unwrap(), "20000");
+    }
+}
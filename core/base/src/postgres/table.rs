@@ -0,0 +1,402 @@
+use std::{collections::HashMap, io::Write};
+
+use common::{
+    data::{
+        columns::{Column, ColumnList},
+        table::{DataQueryResult, TableConfig, TableExportOpts, TableEdit, TableQueryOpts, UpdateTableData},
+    },
+    error::AppError,
+    query::{filter::FilterChain, BasableQuery, QueryCommand},
+};
+
+use crate::{
+    db::QueryParams,
+    export::write_export,
+    row::{ColumnValue, Row},
+    table::{
+        apply_sql_edits, insert_statements, write_sql_rows, EditBatchError, EditOutcome,
+        RowOutcome, SqlSyntax, WriteOpts,
+    },
+    table::{Table, TableCRUD},
+    ConnectorType, SharedDB,
+};
+
+use super::quote_ident;
+
+const SYNTAX: SqlSyntax = SqlSyntax {
+    quote: quote_ident,
+    new_params: QueryParams::numbered,
+    mysql: false,
+    row_alias: false,
+};
+
+pub struct PostgresTable {
+    pub name: String,
+    pub connector: ConnectorType,
+}
+
+impl PostgresTable {
+    fn quoted_name(&self) -> String {
+        quote_ident(&self.name)
+    }
+}
+
+impl Table for PostgresTable {
+    type Row = Row;
+
+    fn new(name: String, conn: ConnectorType) -> Self
+    where
+        Self: Sized,
+    {
+        PostgresTable {
+            name,
+            connector: conn,
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn query_columns(&self) -> Result<ColumnList, AppError> {
+        let query = "
+            SELECT
+                a.attname AS \"COLUMN_NAME\",
+                pg_catalog.format_type(a.atttypid, a.atttypmod) AS \"COLUMN_TYPE\",
+                CASE WHEN a.attnotnull THEN 'NO' ELSE 'YES' END AS \"IS_NULLABLE\",
+                pg_catalog.pg_get_expr(d.adbin, d.adrelid) AS \"COLUMN_DEFAULT\",
+                CASE WHEN EXISTS (
+                    SELECT 1 FROM pg_catalog.pg_index i
+                    WHERE i.indrelid = a.attrelid
+                        AND i.indisunique
+                        AND i.indnatts = 1
+                        AND i.indkey[0] = a.attnum
+                ) THEN 'YES' ELSE 'NO' END AS \"IS_UNIQUE\",
+                CASE WHEN EXISTS (
+                    SELECT 1 FROM pg_catalog.pg_index i
+                    WHERE i.indrelid = a.attrelid
+                        AND i.indisprimary
+                        AND a.attnum = ANY(i.indkey)
+                ) THEN 'YES' ELSE 'NO' END AS \"IS_PRIMARY\"
+            FROM
+                pg_catalog.pg_attribute a
+            LEFT JOIN
+                pg_catalog.pg_attrdef d
+            ON
+                d.adrelid = a.attrelid AND d.adnum = a.attnum
+            WHERE
+                a.attrelid = to_regclass($1)
+                AND a.attnum > 0
+                AND NOT a.attisdropped
+            ORDER BY
+                a.attnum
+        ";
+
+        let conn = self.connector();
+        let result = conn.exec_query(query, &[self.quoted_name().into()])?;
+
+        let field = |name: &str| {
+            AppError::ServerError(format!("Missing {name} for table {}", self.name))
+        };
+
+        result
+            .iter()
+            .map(|r| {
+                let name: String = r.get("COLUMN_NAME").ok_or_else(|| field("COLUMN_NAME"))?;
+                let col_type: String = r.get("COLUMN_TYPE").ok_or_else(|| field("COLUMN_TYPE"))?;
+                let default: Option<String> = r.get("COLUMN_DEFAULT").flatten();
+
+                let flag = |key: &str| {
+                    r.get::<String, _>(key)
+                        .map(|s| s == "YES")
+                        .ok_or_else(|| field(key))
+                };
+
+                Ok(Column {
+                    name,
+                    col_type,
+                    default_value: default,
+                    nullable: flag("IS_NULLABLE")?,
+                    unique: flag("IS_UNIQUE")?,
+                    primary: flag("IS_PRIMARY")?,
+                })
+            })
+            .collect()
+    }
+
+    fn connector(&self) -> &ConnectorType {
+        &self.connector
+    }
+
+    fn init_config(&self) -> Option<TableConfig> {
+        let mut config = None;
+
+        if let Ok(cols) = self.query_columns() {
+            let pk = cols
+                .iter()
+                .find(|c| c.primary)
+                .or_else(|| cols.iter().find(|c| c.unique))
+                .map(|pk| pk.name.clone());
+
+            let c = TableConfig {
+                pk_column: pk,
+                name: self.name.clone(),
+                label: self.name.clone(),
+                ..TableConfig::default()
+            };
+
+            config = Some(c);
+        }
+
+        config
+    }
+}
+
+impl TableCRUD for PostgresTable {
+    fn query_data(
+        &self,
+        opts: TableQueryOpts,
+        db: &SharedDB,
+    ) -> DataQueryResult<ColumnValue, AppError> {
+        let cols = opts
+            .columns
+            .clone()
+            .take_if(|cols| !cols.is_empty())
+            .unwrap_or_else(|| match self.query_columns() {
+                Ok(cs) => cs.iter().map(|col| col.name.clone()).collect(),
+                Err(err) => {
+                    tracing::error!("error reading db column: {err}");
+                    vec![]
+                }
+            });
+
+        let query = opts.try_into()?;
+        db.check_query(&query)?;
+        let (sql, params) = db.generate_sql(query)?;
+
+        let conn = self.connector();
+        let rows = conn.exec_query(&sql, &params)?;
+
+        let data = rows
+            .iter()
+            .map(|r| {
+                let mut map: HashMap<String, ColumnValue> = HashMap::new();
+
+                for col in &cols {
+                    if let Some(v) = r.get::<ColumnValue, &str>(col) {
+                        map.insert(col.clone(), v);
+                    }
+                }
+
+                map
+            })
+            .collect();
+
+        Ok(data)
+    }
+
+    fn query_result_count(&self, opts: TableQueryOpts, db: &SharedDB) -> Result<usize, AppError> {
+        let query = BasableQuery {
+            table: opts.table,
+            command: QueryCommand::SelectData(Some(vec!["COUNT(*)".to_string()])),
+            search_opts: opts.search_opts,
+            filters: opts
+                .filters
+                .map_or(FilterChain::empty(), FilterChain::prefill),
+            ..Default::default()
+        };
+
+        db.check_query(&query)?;
+        let (sql, params) = db.generate_sql(query)?;
+
+        let conn = self.connector();
+        let rows = conn.exec_query(&sql, &params)?;
+
+        let count = rows
+            .first()
+            .map(|row| row.get::<usize, usize>(0).unwrap_or_default())
+            .unwrap_or_default();
+
+        Ok(count)
+    }
+
+    fn insert_data(&self, input: HashMap<String, String>) -> Result<(), AppError> {
+        let cols: Vec<&str> = input.keys().map(|k| k.as_str()).collect();
+        self.check_columns(&cols)?;
+
+        let mut params = QueryParams::numbered();
+        let mut keys = Vec::with_capacity(input.len());
+        let mut values = Vec::with_capacity(input.len());
+
+        for (k, v) in input {
+            keys.push(quote_ident(&k));
+            values.push(params.bind(v));
+        }
+
+        let query = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            self.quoted_name(),
+            keys.join(", "),
+            values.join(", ")
+        );
+
+        let conn = self.connector();
+        conn.exec_query(&query, &params.into_values())?;
+
+        Ok(())
+    }
+
+    fn insert_batch(&self, columns: &[String], rows: Vec<Vec<ColumnValue>>) -> Result<usize, AppError> {
+        let cols: Vec<&str> = columns.iter().map(|c| c.as_str()).collect();
+        self.check_columns(&cols)?;
+
+        let count = rows.len();
+        let statements =
+            insert_statements(&self.quoted_name(), columns, rows, quote_ident, QueryParams::numbered)?;
+        self.connector().exec_transaction(&statements)?;
+
+        Ok(count)
+    }
+
+    fn write_rows(
+        &self,
+        columns: &[String],
+        rows: Vec<Vec<ColumnValue>>,
+        opts: WriteOpts,
+    ) -> Result<Vec<RowOutcome>, AppError> {
+        write_sql_rows(self, &SYNTAX, columns, rows, opts)
+    }
+
+    fn apply_edits(
+        &self,
+        unique_key: &str,
+        edits: Vec<TableEdit>,
+    ) -> Result<Vec<EditOutcome>, EditBatchError> {
+        apply_sql_edits(self, &SYNTAX, unique_key, edits)
+    }
+
+    fn update_data(&self, options: UpdateTableData) -> Result<(), AppError> {
+        let UpdateTableData {
+            unique_key,
+            columns,
+            unique_values,
+            input,
+        } = options;
+
+        let mut cols: Vec<&str> = columns.iter().map(|col| col.as_str()).collect();
+        cols.push(&unique_key);
+        self.check_columns(&cols)?;
+
+        let unique_key = quote_ident(&unique_key);
+        let mut params = QueryParams::numbered();
+
+        let mut cases = vec![];
+        for col in &columns {
+            let quoted_col = quote_ident(col);
+            let mut q = format!("{quoted_col} = CASE {unique_key} \n");
+
+            for (index, uv) in unique_values.iter().enumerate() {
+                if let Some(values) = input.get(index) {
+                    if let Some(val) = values.get(col) {
+                        let when = params.bind(uv.as_str());
+                        let then = params.bind(val.as_str());
+                        q.push_str(&format!("WHEN {when} THEN {then} \n"));
+                    }
+                }
+            }
+
+            q.push_str(&format!("ELSE {quoted_col} \n END"));
+            cases.push(q);
+        }
+
+        let unique_values = params.bind_list(unique_values.iter().map(|v| v.as_str()));
+
+        let query = format!(
+            "UPDATE {} \n SET {} WHERE {} IN ({})",
+            self.quoted_name(),
+            cases.join(", \n"),
+            unique_key,
+            unique_values
+        );
+
+        let conn = self.connector();
+        conn.exec_query(&query, &params.into_values())?;
+
+        Ok(())
+    }
+
+    fn delete_data(&self, col: &str, values: Vec<&str>) -> Result<(), AppError> {
+        if values.is_empty() {
+            return Err(AppError::ServerError("a value must be provided".to_string()));
+        }
+
+        self.check_columns(&[col])?;
+
+        let mut params = QueryParams::numbered();
+        let query = format!(
+            "DELETE FROM {} WHERE {} IN ({})",
+            self.quoted_name(),
+            quote_ident(col),
+            params.bind_list(values)
+        );
+
+        let conn = self.connector();
+        conn.exec_query(&query, &params.into_values())?;
+
+        Ok(())
+    }
+
+    fn export(&self, opts: TableExportOpts, db: &SharedDB, out: &mut (dyn Write + Send)) -> Result<(), AppError> {
+        let TableExportOpts {
+            query_opts,
+            format,
+            trim,
+            xml,
+            ..
+        } = opts;
+
+        let cols = query_opts
+            .columns
+            .clone()
+            .take_if(|cols| !cols.is_empty())
+            .unwrap_or_else(|| match self.query_columns() {
+                Ok(cs) => cs.iter().map(|col| col.name.clone()).collect(),
+                Err(err) => {
+                    tracing::error!("error reading db column: {err}");
+                    vec![]
+                }
+            });
+
+        let selection = if cols.is_empty() {
+            None
+        } else {
+            Some(cols.clone())
+        };
+
+        let filters = query_opts
+            .filters
+            .map_or(FilterChain::empty(), FilterChain::prefill);
+
+        let query = BasableQuery {
+            table: query_opts.table,
+            command: QueryCommand::SelectData(selection),
+            filters,
+            offset: trim.as_ref().map(|trim| trim.offset),
+            row_count: trim.map(|trim| trim.count),
+            ..Default::default()
+        };
+
+        db.check_query(&query)?;
+        let (sql, params) = db.generate_sql(query)?;
+
+        write_export(self.connector(), format, xml, cols, &sql, &params, out)
+    }
+
+    fn clear(&self) -> Result<(), AppError> {
+        let query = format!("DELETE FROM {}", self.quoted_name());
+        let conn = self.connector();
+        conn.exec_query(&query, &[])?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock, RwLock},
+};
+
+use axum::http::StatusCode;
+use common::error::AppError;
+use uuid::Uuid;
+
+use crate::{config::ConfigRaw, connector::Connector, row::Row, ConnectorType, DbType, SharedDB};
+
+/// Creates the [`DbType`] for a connection config. Tables are loaded by [`ConnectorRegistry::connect`].
+pub type ConnectionFactory = fn(config: ConfigRaw, user_id: String) -> Result<Box<DbType>, AppError>;
+
+/// Maps each data `source` (e.g. `mysql`, `csv`) to the factory that connects to it.
+///
+/// Backends compiled in through cargo features are registered by [`ConnectorRegistry::new`].
+/// Others can be added with [`register`] before connections are made.
+pub struct ConnectorRegistry {
+    factories: HashMap<String, ConnectionFactory>,
+}
+
+impl ConnectorRegistry {
+    /// Create a registry holding every backend enabled at compile time.
+    pub fn new() -> Self {
+        // Stays empty when no backend is enabled.
+        #[allow(unused_mut)]
+        let mut registry = ConnectorRegistry::empty();
+
+        #[cfg(feature = "mysql")]
+        registry.register("mysql", |config, user_id| {
+            use crate::mysql::{connector::MysqlConnector, db::MySqlDB};
+            create::<MysqlConnector, _>(config, user_id, MySqlDB::new)
+        });
+
+        #[cfg(feature = "mariadb")]
+        registry.register("mariadb", |config, user_id| {
+            use crate::{mariadb::db::MariaDB, mysql::connector::MysqlConnector};
+            create::<MysqlConnector, _>(config, user_id, MariaDB::new)
+        });
+
+        #[cfg(feature = "postgres")]
+        registry.register("postgres", |config, user_id| {
+            use crate::postgres::{connector::PostgresConnector, db::PostgresDB};
+            create::<PostgresConnector, _>(config, user_id, PostgresDB::new)
+        });
+
+        #[cfg(feature = "sqlite")]
+        registry.register("sqlite", |config, user_id| {
+            use crate::sqlite::{connector::SqliteConnector, db::SqliteDB};
+            create::<SqliteConnector, _>(config, user_id, SqliteDB::new)
+        });
+
+        #[cfg(feature = "mongo")]
+        registry.register("mongo", |config, user_id| {
+            use crate::mongo::{connector::MongoConnector, db::MongoDB};
+            create::<MongoConnector, _>(config, user_id, MongoDB::new)
+        });
+
+        #[cfg(feature = "file")]
+        for source in ["csv", "tsv", "ndjson", "parquet", "arrow"] {
+            registry.register(source, |config, user_id| {
+                use crate::file::{connector::FileConnector, db::FileDB};
+                create::<FileConnector, _>(config, user_id, FileDB::new)
+            });
+        }
+
+        registry
+    }
+
+    /// Create a registry without any backend.
+    pub fn empty() -> Self {
+        ConnectorRegistry {
+            factories: HashMap::new(),
+        }
+    }
+
+    /// Register `factory` for `source`, replacing any factory already registered for it.
+    pub fn register(&mut self, source: &str, factory: ConnectionFactory) {
+        self.factories.insert(source.to_string(), factory);
+    }
+
+    /// Sources that can be connected to.
+    pub fn sources(&self) -> Vec<&str> {
+        let mut sources: Vec<&str> = self.factories.keys().map(|s| s.as_str()).collect();
+        sources.sort();
+        sources
+    }
+
+    /// The factory registered for `source`.
+    pub fn factory(&self, source: &str) -> Result<ConnectionFactory, AppError> {
+        self.factories.get(source).copied().ok_or_else(|| {
+            AppError::HttpError(
+                StatusCode::EXPECTATION_FAILED,
+                format!("Unsupported source `{source}`"),
+            )
+        })
+    }
+
+    /// Connect to the source named by `config.source` and load its tables.
+    pub fn connect(&self, config: &ConfigRaw, user_id: String) -> Result<SharedDB, AppError> {
+        let factory = self.factory(&config.source)?;
+        connect_with(factory, config, user_id, None)
+    }
+
+    /// Like [`ConnectorRegistry::connect`], but the connection keeps `id` instead of a new one.
+    pub fn connect_with_id(
+        &self,
+        config: &ConfigRaw,
+        user_id: String,
+        id: Uuid,
+    ) -> Result<SharedDB, AppError> {
+        let factory = self.factory(&config.source)?;
+        connect_with(factory, config, user_id, Some(id))
+    }
+}
+
+impl Default for ConnectorRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn connect_with(
+    factory: ConnectionFactory,
+    config: &ConfigRaw,
+    user_id: String,
+    id: Option<Uuid>,
+) -> Result<SharedDB, AppError> {
+    let mut db = factory(config.clone(), user_id)?;
+    if let Some(id) = id {
+        db.set_id(id);
+    }
+
+    let conn = db.connector().clone();
+    db.load_tables(conn)?;
+
+    Ok(Arc::from(db))
+}
+
+/// Build a [`DbType`] from a [`Connector`] and the constructor of its [`DB`](crate::db::DB).
+pub fn create<C, D>(
+    config: ConfigRaw,
+    user_id: String,
+    new_db: fn(ConnectorType, String) -> D,
+) -> Result<Box<DbType>, AppError>
+where
+    C: Connector<Row = Row> + 'static,
+    D: crate::db::DB<Row = Row> + 'static,
+{
+    let conn = C::new(config)?;
+    let db = new_db(Arc::new(conn), user_id);
+
+    Ok(Box::new(db))
+}
+
+fn registry() -> &'static RwLock<ConnectorRegistry> {
+    static REGISTRY: OnceLock<RwLock<ConnectorRegistry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(ConnectorRegistry::new()))
+}
+
+/// Register a backend with the app-wide registry used by [`connect`].
+pub fn register(source: &str, factory: ConnectionFactory) {
+    let mut registry = registry().write().unwrap_or_else(|err| err.into_inner());
+    registry.register(source, factory);
+}
+
+/// The factory registered with the app-wide registry for `source`.
+fn factory(source: &str) -> Result<ConnectionFactory, AppError> {
+    let registry = registry().read().unwrap_or_else(|err| err.into_inner());
+    registry.factory(source)
+}
+
+/// Connect using the app-wide registry. See [`ConnectorRegistry::connect`].
+pub fn connect(config: &ConfigRaw, user_id: String) -> Result<SharedDB, AppError> {
+    // Don't hold the lock while connecting, which may take a while.
+    let factory = factory(&config.source)?;
+    connect_with(factory, config, user_id, None)
+}
+
+/// Connect using the app-wide registry. See [`ConnectorRegistry::connect_with_id`].
+pub fn connect_with_id(config: &ConfigRaw, user_id: String, id: Uuid) -> Result<SharedDB, AppError> {
+    let factory = factory(&config.source)?;
+    connect_with(factory, config, user_id, Some(id))
+}
+
+/// Sources supported by the app-wide registry.
+pub fn sources() -> Vec<String> {
+    let registry = registry().read().unwrap_or_else(|err| err.into_inner());
+    registry.sources().into_iter().map(|s| s.to_string()).collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::config::ConfigRaw;
+
+    use super::ConnectorRegistry;
+
+    #[test]
+    fn test_unknown_source() {
+        let mut registry = ConnectorRegistry::empty();
+        registry.register("nothing", |_, _| Err(common::error::AppError::not_implemented()));
+
+        assert_eq!(registry.sources(), vec!["nothing"]);
+
+        let config = ConfigRaw {
+            source: "oracle".to_string(),
+            ..ConfigRaw::default()
+        };
+        assert!(registry.connect(&config, "user".to_string()).is_err());
+    }
+}
@@ -0,0 +1,248 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use time::{Date, Month};
+
+/// Client side representation of a column value. Every backend converts the values it reads
+/// into `ColumnValue`, so the rest of the app doesn't depend on any driver's value type.
+///
+/// It is also the type of the values bound to a query, see [`QueryParams`](crate::db::QueryParams).
+#[derive(Clone, PartialEq, PartialOrd, Deserialize, Serialize, Debug)]
+pub enum ColumnValue {
+    NULL,
+    Text(String),
+    Int(i64),
+    UInt(u64),
+    Float(f32),
+    Double(f64),
+    /// year, month, day, hour, minutes, seconds, micro seconds
+    Date(u16, u8, u8, u8, u8, u8, u32),
+    /// is negative, days, hours, minutes, seconds, micro seconds
+    Time(bool, u32, u8, u8, u8, u32),
+}
+
+impl From<String> for ColumnValue {
+    fn from(value: String) -> Self {
+        ColumnValue::Text(value)
+    }
+}
+
+impl From<&str> for ColumnValue {
+    fn from(value: &str) -> Self {
+        ColumnValue::Text(value.to_string())
+    }
+}
+
+/// A row of a query result, as returned by every [`Connector`](crate::connector::Connector).
+#[derive(Clone, Debug)]
+pub struct Row {
+    columns: Arc<[String]>,
+    values: Vec<ColumnValue>,
+}
+
+impl Row {
+    /// Create a row from column names and their values. Rows of the same result usually share
+    /// their `columns`.
+    pub fn new(columns: Arc<[String]>, values: Vec<ColumnValue>) -> Self {
+        Row { columns, values }
+    }
+
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Get the value of a column, by name or position. Returns `None` if there is no such column
+    /// or its value can't be converted to `T`. Use `Option<T>` for nullable columns.
+    pub fn get<T: FromColumnValue, I: ColumnIndex>(&self, index: I) -> Option<T> {
+        let idx = index.idx(&self.columns)?;
+        self.values.get(idx).and_then(T::from_value)
+    }
+
+    /// Consume the row, returning its values in column order.
+    pub fn unwrap(self) -> Vec<ColumnValue> {
+        self.values
+    }
+}
+
+/// Something that identifies a column of a [`Row`].
+pub trait ColumnIndex {
+    fn idx(&self, columns: &[String]) -> Option<usize>;
+}
+
+impl ColumnIndex for usize {
+    fn idx(&self, columns: &[String]) -> Option<usize> {
+        (*self < columns.len()).then_some(*self)
+    }
+}
+
+impl ColumnIndex for &str {
+    fn idx(&self, columns: &[String]) -> Option<usize> {
+        columns.iter().position(|col| col == self)
+    }
+}
+
+/// Conversion from a [`ColumnValue`], used by [`Row::get`].
+pub trait FromColumnValue: Sized {
+    fn from_value(value: &ColumnValue) -> Option<Self>;
+}
+
+impl FromColumnValue for ColumnValue {
+    fn from_value(value: &ColumnValue) -> Option<Self> {
+        Some(value.clone())
+    }
+}
+
+impl<T: FromColumnValue> FromColumnValue for Option<T> {
+    fn from_value(value: &ColumnValue) -> Option<Self> {
+        match value {
+            ColumnValue::NULL => Some(None),
+            v => T::from_value(v).map(Some),
+        }
+    }
+}
+
+impl FromColumnValue for String {
+    fn from_value(value: &ColumnValue) -> Option<Self> {
+        let s = match value {
+            ColumnValue::NULL => return None,
+            ColumnValue::Text(v) => v.clone(),
+            ColumnValue::Int(v) => v.to_string(),
+            ColumnValue::UInt(v) => v.to_string(),
+            ColumnValue::Float(v) => v.to_string(),
+            ColumnValue::Double(v) => v.to_string(),
+            ColumnValue::Date(y, m, d, h, min, s, us) => {
+                let mut date = format!("{y:04}-{m:02}-{d:02}");
+                if (h, min, s, us) != (&0, &0, &0, &0) {
+                    date.push_str(&format!(" {h:02}:{min:02}:{s:02}"));
+                }
+                if *us > 0 {
+                    date.push_str(&format!(".{us:06}"));
+                }
+                date
+            }
+            ColumnValue::Time(neg, d, h, min, s, us) => {
+                let sign = if *neg { "-" } else { "" };
+                let hours = *d * 24 + u32::from(*h);
+                let mut time = format!("{sign}{hours:02}:{min:02}:{s:02}");
+                if *us > 0 {
+                    time.push_str(&format!(".{us:06}"));
+                }
+                time
+            }
+        };
+
+        Some(s)
+    }
+}
+
+macro_rules! impl_from_value_for_number {
+    ($($t:ty),*) => {
+        $(
+            impl FromColumnValue for $t {
+                fn from_value(value: &ColumnValue) -> Option<Self> {
+                    match value {
+                        ColumnValue::Int(v) => <$t>::try_from(*v).ok(),
+                        ColumnValue::UInt(v) => <$t>::try_from(*v).ok(),
+                        ColumnValue::Text(v) => v.trim().parse().ok(),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_value_for_number!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl FromColumnValue for f64 {
+    fn from_value(value: &ColumnValue) -> Option<Self> {
+        match value {
+            ColumnValue::Int(v) => Some(*v as f64),
+            ColumnValue::UInt(v) => Some(*v as f64),
+            ColumnValue::Float(v) => Some((*v).into()),
+            ColumnValue::Double(v) => Some(*v),
+            ColumnValue::Text(v) => v.trim().parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl FromColumnValue for f32 {
+    fn from_value(value: &ColumnValue) -> Option<Self> {
+        f64::from_value(value).map(|v| v as f32)
+    }
+}
+
+impl FromColumnValue for bool {
+    fn from_value(value: &ColumnValue) -> Option<Self> {
+        match value {
+            ColumnValue::Int(v) => Some(*v != 0),
+            ColumnValue::UInt(v) => Some(*v != 0),
+            ColumnValue::Text(v) => match v.to_lowercase().as_str() {
+                "1" | "true" => Some(true),
+                "0" | "false" => Some(false),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+impl FromColumnValue for Date {
+    fn from_value(value: &ColumnValue) -> Option<Self> {
+        let (y, m, d) = match value {
+            ColumnValue::Date(y, m, d, ..) => (i32::from(*y), *m, *d),
+            // dates read as text, e.g. `2024-01-31` or `2024-01-31 10:00:00`
+            ColumnValue::Text(v) => {
+                let mut parts = v.get(..10)?.splitn(3, '-');
+                let y = parts.next()?.parse().ok()?;
+                let m = parts.next()?.parse().ok()?;
+                let d = parts.next()?.parse().ok()?;
+                (y, m, d)
+            }
+            _ => return None,
+        };
+
+        Date::from_calendar_date(y, Month::try_from(m).ok()?, d).ok()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::Arc;
+
+    use time::{Date, Month};
+
+    use super::{ColumnValue, Row};
+
+    #[test]
+    fn test_row_get() {
+        let columns: Arc<[String]> = vec!["id".to_string(), "name".to_string(), "joined".to_string()].into();
+        let row = Row::new(
+            columns,
+            vec![
+                ColumnValue::Int(7),
+                ColumnValue::NULL,
+                ColumnValue::Text("2024-01-31 10:00:00".to_string()),
+            ],
+        );
+
+        assert_eq!(row.get::<u32, &str>("id"), Some(7));
+        assert_eq!(row.get::<String, usize>(0), Some("7".to_string()));
+        assert_eq!(row.get::<String, &str>("name"), None);
+        assert_eq!(row.get::<Option<String>, &str>("name"), Some(None));
+        assert_eq!(row.get::<String, &str>("missing"), None);
+        assert_eq!(
+            row.get::<Date, &str>("joined"),
+            Date::from_calendar_date(2024, Month::January, 31).ok()
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use std::sync::Arc;
+
+use common::error::AppError;
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use regex::Regex;
+use rusqlite::{functions::FunctionFlags, params_from_iter, types::ValueRef, Connection, OpenFlags};
+
+use crate::{
+    config::ConfigRaw,
+    connector::{Connector, Statement, TransactionError},
+    row::{ColumnValue, Row},
+};
+
+use super::to_value;
+
+/// SQLite implementation of `BasableConnection`
+#[derive(Clone)]
+pub struct SqliteConnector {
+    /// Database connection pool
+    pub pool: Pool<SqliteConnectionManager>,
+
+    /// Connection options
+    pub config: ConfigRaw,
+}
+
+/// SQLite has a `REGEXP` operator but leaves its implementation to the application.
+pub(crate) fn register_regexp(conn: &mut Connection) -> Result<(), rusqlite::Error> {
+    conn.create_scalar_function(
+        "regexp",
+        2,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let re: Arc<Regex> = ctx.get_or_create_aux(0, |vr| -> Result<_, regex::Error> {
+                Regex::new(vr.as_str().unwrap_or_default())
+            })?;
+
+            let is_match = match ctx.get_raw(1) {
+                ValueRef::Null => false,
+                ValueRef::Integer(v) => re.is_match(&v.to_string()),
+                ValueRef::Real(v) => re.is_match(&v.to_string()),
+                ValueRef::Text(v) | ValueRef::Blob(v) => {
+                    re.is_match(&String::from_utf8_lossy(v))
+                }
+            };
+
+            Ok(is_match)
+        },
+    )
+}
+
+impl Connector for SqliteConnector {
+    type Row = Row;
+
+    fn new(config: ConfigRaw) -> Result<Self, AppError> {
+        let path = config.build_url()?;
+
+        // We don't want to create an empty database if the file doesn't exist.
+        // Paths aren't parsed as URIs, whose parameters could point SQLite at other files.
+        let flags = OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+
+        let manager = SqliteConnectionManager::file(path)
+            .with_flags(flags)
+            .with_init(register_regexp);
+
+        Pool::new(manager)
+            .map(|pool| SqliteConnector { pool, config })
+            .map_err(|err| AppError::ServerError(err.to_string()))
+    }
+
+    fn exec_query(&self, query: &str, params: &[ColumnValue]) -> Result<Vec<Self::Row>, AppError> {
+        query_pool(&self.pool, query, params)
+    }
+
+    fn for_each_row(
+        &self,
+        query: &str,
+        params: &[ColumnValue],
+        f: &mut (dyn FnMut(Self::Row) -> Result<(), AppError> + Send),
+    ) -> Result<(), AppError> {
+        for_each_pool_row(&self.pool, query, params, f)
+    }
+
+    fn exec_transaction(
+        &self,
+        statements: &[Statement],
+    ) -> Result<Vec<Vec<Self::Row>>, TransactionError> {
+        transaction_pool(&self.pool, statements)
+    }
+
+    fn config(&self) -> &ConfigRaw {
+        &self.config
+    }
+}
+
+/// Run `query` with `params` on a connection from `pool`, converting each result into a [`Row`].
+pub(crate) fn query_pool(
+    pool: &Pool<SqliteConnectionManager>,
+    query: &str,
+    params: &[ColumnValue],
+) -> Result<Vec<Row>, AppError> {
+    let conn = pool
+        .get()
+        .map_err(|err| AppError::ServerError(err.to_string()))?;
+
+    query_conn(&conn, query, params)
+}
+
+/// Run `statements` in a transaction on a connection from `pool`. See
+/// [`Connector::exec_transaction`].
+pub(crate) fn transaction_pool(
+    pool: &Pool<SqliteConnectionManager>,
+    statements: &[Statement],
+) -> Result<Vec<Vec<Row>>, TransactionError> {
+    let failed = |index: usize| {
+        move |err: rusqlite::Error| TransactionError {
+            index,
+            error: AppError::ServerError(err.to_string()),
+        }
+    };
+
+    let mut conn = pool.get().map_err(|err| TransactionError {
+        index: 0,
+        error: AppError::ServerError(err.to_string()),
+    })?;
+    // Dropping the transaction before it's committed rolls it back.
+    let tx = conn.transaction().map_err(failed(0))?;
+
+    let mut results = Vec::with_capacity(statements.len());
+    for (index, (query, params)) in statements.iter().enumerate() {
+        let rows = query_conn(&tx, query, params).map_err(|error| TransactionError { index, error })?;
+        results.push(rows);
+    }
+
+    tx.commit()
+        .map_err(failed(statements.len().saturating_sub(1)))?;
+    Ok(results)
+}
+
+fn query_conn(conn: &Connection, query: &str, params: &[ColumnValue]) -> Result<Vec<Row>, AppError> {
+    let mut results = Vec::new();
+    for_each_conn_row(conn, query, params, &mut |row| {
+        results.push(row);
+        Ok(())
+    })?;
+
+    Ok(results)
+}
+
+/// Run `query` on a connection from `pool`, handing the rows to `f` as they're read. See
+/// [`Connector::for_each_row`].
+pub(crate) fn for_each_pool_row(
+    pool: &Pool<SqliteConnectionManager>,
+    query: &str,
+    params: &[ColumnValue],
+    f: &mut (dyn FnMut(Row) -> Result<(), AppError> + Send),
+) -> Result<(), AppError> {
+    let conn = pool
+        .get()
+        .map_err(|err| AppError::ServerError(err.to_string()))?;
+
+    for_each_conn_row(&conn, query, params, f)
+}
+
+fn for_each_conn_row(
+    conn: &Connection,
+    query: &str,
+    params: &[ColumnValue],
+    f: &mut (dyn FnMut(Row) -> Result<(), AppError> + Send),
+) -> Result<(), AppError> {
+    let to_app_error = |err: rusqlite::Error| AppError::ServerError(err.to_string());
+
+    let mut stmt = conn.prepare(query).map_err(to_app_error)?;
+    let names: Arc<[String]> = stmt.column_names().iter().map(|n| n.to_string()).collect();
+
+    let mut rows = stmt.query(params_from_iter(params)).map_err(to_app_error)?;
+    while let Some(row) = rows.next().map_err(to_app_error)? {
+        let mut values = Vec::with_capacity(names.len());
+        for idx in 0..names.len() {
+            let value = row.get_ref(idx).map_err(to_app_error)?;
+            values.push(to_value(value));
+        }
+
+        f(Row::new(names.clone(), values))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use std::{collections::HashMap, sync::Arc};
+
+use common::{
+    data::table::{TableSearchOpts, TableSummaries, TableSummary},
+    error::AppError,
+    DBVersion, DbServerDetails,
+};
+use crate::row::{ColumnValue, Row};
+use uuid::Uuid;
+
+use crate::{
+    db::{QueryParams, QuerySqlParser, DB},
+    table::Table,
+    ConnectorType, SharedTable,
+};
+
+use super::{quote_ident, table::SqliteTable};
+
+pub struct SqliteDB {
+    pub connector: ConnectorType,
+    pub tables: Vec<SharedTable>,
+    user_id: String,
+    id: Uuid,
+}
+
+impl SqliteDB {
+    pub fn new(connector: ConnectorType, user_id: String) -> Self {
+        SqliteDB {
+            connector,
+            tables: Vec::new(),
+            user_id,
+            id: Uuid::new_v4(),
+        }
+    }
+
+    /// Get SQLite library version. SQLite runs in-process, so the OS is our own.
+    fn show_version_variables(&self) -> Result<DBVersion, AppError> {
+        let vars = self.exec_query("SELECT sqlite_version() AS version", &[])?;
+
+        let mut data = HashMap::new();
+
+        if let Some(v) = vars.first() {
+            let version: String = v.get("version").unwrap_or_default();
+
+            data.insert("version_comment".to_string(), format!("SQLite {version}"));
+            data.insert("version".to_string(), version);
+            data.insert(
+                "version_compile_os".to_string(),
+                std::env::consts::OS.to_string(),
+            );
+        }
+
+        Ok(data)
+    }
+
+    fn size(&self) -> Result<f64, AppError> {
+        let qr = self.exec_query(
+            "
+            SELECT ROUND(p.page_count * s.page_size / 1024.0 / 1024.0, 1) AS size
+            FROM pragma_page_count() p, pragma_page_size() s
+        ",
+            &[],
+        )?;
+
+        // db size is returned in MB
+        let size: f64 = qr.first().map_or(0.0, |r| r.get("size").unwrap_or_default());
+
+        Ok(size)
+    }
+
+    fn exec_query(&self, query: &str, params: &[ColumnValue]) -> Result<Vec<Row>, AppError> {
+        self.connector.exec_query(query, params)
+    }
+}
+
+impl DB for SqliteDB {
+    type Row = Row;
+
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+
+    fn set_id(&mut self, id: Uuid) {
+        self.id = id;
+    }
+
+    fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    fn connector(&self) -> &ConnectorType {
+        &self.connector
+    }
+
+    fn load_tables(&mut self, connector: ConnectorType) -> Result<(), AppError> {
+        let tables = self.query_tables()?;
+
+        tables.iter().for_each(|t| {
+            let connector = connector.clone();
+            let name: String = t.get("TABLE_NAME").unwrap();
+
+            let table = SqliteTable::new(name, connector);
+            self.tables.push(Arc::new(table));
+        });
+
+        Ok(())
+    }
+
+    fn tables(&self) -> &Vec<SharedTable> {
+        &self.tables
+    }
+
+    fn query_tables(&self) -> Result<Vec<Row>, AppError> {
+        // SQLite doesn't keep row estimates or timestamps for tables, row counts
+        // are computed in `build_table_list`.
+        let query = "
+            SELECT name AS TABLE_NAME
+            FROM sqlite_master
+            WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+            ORDER BY name
+        ";
+
+        self.connector.exec_query(query, &[])
+    }
+
+    fn build_table_list(&self) -> Result<TableSummaries, AppError> {
+        let results = self.query_tables()?;
+        let mut tables = Vec::with_capacity(results.len());
+
+        for res in results {
+            let name: String = res.get("TABLE_NAME").unwrap();
+            let col_count = self.query_column_count(&name)?;
+
+            let query = format!("SELECT COUNT(*) AS count FROM {}", quote_ident(&name));
+            let qr = self.exec_query(&query, &[])?;
+            let row_count: u32 = qr.first().map_or(0, |r| r.get("count").unwrap_or_default());
+
+            tables.push(TableSummary {
+                name,
+                col_count,
+                row_count,
+                created: None,
+                updated: None,
+            });
+        }
+
+        Ok(tables)
+    }
+
+    fn query_column_count(&self, tb_name: &str) -> Result<u32, AppError> {
+        let query = "SELECT COUNT(*) AS count FROM pragma_table_info(?)";
+
+        let qr = self.exec_query(query, &[tb_name.into()])?;
+        let c: u32 = qr.first().map_or(0, |r| r.get("count").unwrap_or_default());
+
+        Ok(c)
+    }
+
+    fn get_table(&self, name: &str) -> Option<&SharedTable> {
+        self.tables.iter().find(|t| t.name() == name)
+    }
+
+    fn details(&self) -> Result<DbServerDetails, AppError> {
+        let vrbs = self.show_version_variables()?;
+        let version = vrbs.get("version").map(|v| v.to_string());
+        let os = vrbs.get("version_compile_os").map(|v| v.to_string());
+        let comment = vrbs.get("version_comment").map(|v| v.to_string());
+
+        let size = self.size()?;
+
+        Ok(DbServerDetails {
+            version: version.unwrap_or_default(),
+            os: os.unwrap_or_default(),
+            comment,
+            db_size: size,
+        })
+    }
+
+    fn drop_table(&self, table_name: &str) -> Result<(), AppError> {
+        self.check_columns(table_name, &[])?;
+
+        let query = format!("DROP TABLE {}", quote_ident(table_name));
+        let conn = self.connector();
+        conn.exec_query(&query, &[])?;
+
+        Ok(())
+    }
+}
+
+impl QuerySqlParser for SqliteDB {
+    fn quote_ident(&self, name: &str) -> String {
+        quote_ident(name)
+    }
+
+    /// SQLite has no fulltext index on ordinary tables, so search falls back to matching the
+    /// query anywhere in any of the search columns.
+    fn parse_search(&self, opts: &TableSearchOpts, params: &mut QueryParams) -> String {
+        let pattern = format!("%{}%", opts.query);
+        let matches: Vec<String> = opts
+            .search_cols
+            .iter()
+            .map(|col| format!("{} LIKE {}", quote_ident(col), params.bind(pattern.as_str())))
+            .collect();
+
+        matches.join(" OR ")
+    }
+
+    fn parse_limit(&self, row_count: usize, offset: usize) -> String {
+        format!(" LIMIT {row_count} OFFSET {offset}")
+    }
+}
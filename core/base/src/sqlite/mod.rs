@@ -0,0 +1,53 @@
+use crate::row::{ColumnValue, FromColumnValue};
+use rusqlite::{
+    types::{ToSqlOutput, Value, ValueRef},
+    ToSql,
+};
+
+pub mod connector;
+pub mod db;
+pub mod graphs;
+pub mod table;
+
+/// Quote an identifier (table or column name) for use in a SQLite query.
+pub(crate) fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+impl ToSql for ColumnValue {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        let value = match self {
+            ColumnValue::NULL => Value::Null,
+            ColumnValue::Text(v) => return Ok(ToSqlOutput::Borrowed(ValueRef::Text(v.as_bytes()))),
+            ColumnValue::Int(v) => Value::Integer(*v),
+            ColumnValue::UInt(v) => match i64::try_from(*v) {
+                Ok(v) => Value::Integer(v),
+                Err(_) => Value::Text(v.to_string()),
+            },
+            ColumnValue::Float(v) => Value::Real((*v).into()),
+            ColumnValue::Double(v) => Value::Real(*v),
+            // SQLite stores dates and times as text
+            v => Value::Text(String::from_value(v).unwrap_or_default()),
+        };
+
+        Ok(ToSqlOutput::Owned(value))
+    }
+}
+
+/// Convert a SQLite value into the [`ColumnValue`] shared by every
+/// [`ConnectorType`](crate::ConnectorType).
+pub(crate) fn to_value(value: ValueRef) -> ColumnValue {
+    match value {
+        ValueRef::Null => ColumnValue::NULL,
+        ValueRef::Integer(v) => ColumnValue::Int(v),
+        ValueRef::Real(v) => ColumnValue::Double(v),
+        ValueRef::Text(v) => ColumnValue::Text(String::from_utf8_lossy(v).into_owned()),
+        ValueRef::Blob(v) => match std::str::from_utf8(v) {
+            Ok(s) => ColumnValue::Text(s.to_string()),
+            Err(_) => {
+                let hex: Vec<String> = v.iter().map(|b| format!("{b:02X}")).collect();
+                ColumnValue::Text(format!("X'{}'", hex.join("")))
+            }
+        },
+    }
+}
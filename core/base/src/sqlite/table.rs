@@ -0,0 +1,519 @@
+use std::{collections::HashMap, io::Write};
+
+use common::{
+    data::{
+        columns::{Column, ColumnList},
+        table::{DataQueryResult, TableConfig, TableExportOpts, TableEdit, TableQueryOpts, UpdateTableData},
+    },
+    error::AppError,
+    query::{filter::FilterChain, BasableQuery, QueryCommand},
+};
+
+use crate::{
+    db::QueryParams,
+    export::write_export,
+    row::{ColumnValue, Row},
+    table::{
+        apply_sql_edits, insert_statements, write_sql_rows, EditBatchError, EditOutcome,
+        RowOutcome, SqlSyntax, WriteOpts,
+    },
+    table::{Table, TableCRUD},
+    ConnectorType, SharedDB,
+};
+
+use super::quote_ident;
+
+const SYNTAX: SqlSyntax = SqlSyntax {
+    quote: quote_ident,
+    new_params: QueryParams::new,
+    mysql: false,
+    row_alias: false,
+};
+
+pub struct SqliteTable {
+    pub name: String,
+    pub connector: ConnectorType,
+}
+
+impl SqliteTable {
+    fn quoted_name(&self) -> String {
+        quote_ident(&self.name)
+    }
+}
+
+impl Table for SqliteTable {
+    type Row = Row;
+
+    fn new(name: String, conn: ConnectorType) -> Self
+    where
+        Self: Sized,
+    {
+        SqliteTable {
+            name,
+            connector: conn,
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn query_columns(&self) -> Result<ColumnList, AppError> {
+        let query = "
+            SELECT
+                c.name AS COLUMN_NAME,
+                c.type AS COLUMN_TYPE,
+                CASE WHEN c.\"notnull\" = 1 OR c.pk > 0 THEN 'NO' ELSE 'YES' END AS IS_NULLABLE,
+                c.dflt_value AS COLUMN_DEFAULT,
+                CASE WHEN EXISTS (
+                    SELECT 1 FROM pragma_index_list(?) il
+                    WHERE il.\"unique\" = 1
+                        AND (SELECT COUNT(*) FROM pragma_index_info(il.name)) = 1
+                        AND (SELECT ii.name FROM pragma_index_info(il.name) ii) = c.name
+                ) THEN 'YES' ELSE 'NO' END AS IS_UNIQUE,
+                CASE WHEN c.pk > 0 THEN 'YES' ELSE 'NO' END AS IS_PRIMARY
+            FROM
+                pragma_table_info(?) c
+            ORDER BY
+                c.cid
+        ";
+
+        let conn = self.connector();
+        let params = [self.name.as_str().into(), self.name.as_str().into()];
+        let result = conn.exec_query(query, &params)?;
+
+        let field = |name: &str| AppError::ServerError(format!("Missing {name} for table {}", self.name));
+
+        result
+            .iter()
+            .map(|r| {
+                let name: String = r.get("COLUMN_NAME").ok_or_else(|| field("COLUMN_NAME"))?;
+                let col_type: String = r.get("COLUMN_TYPE").unwrap_or_default();
+                let default: Option<String> = r.get("COLUMN_DEFAULT").flatten();
+
+                let flag = |key: &str| r.get::<String, _>(key).is_some_and(|s| s == "YES");
+
+                Ok(Column {
+                    name,
+                    col_type,
+                    default_value: default,
+                    nullable: flag("IS_NULLABLE"),
+                    unique: flag("IS_UNIQUE"),
+                    primary: flag("IS_PRIMARY"),
+                })
+            })
+            .collect()
+    }
+
+    fn connector(&self) -> &ConnectorType {
+        &self.connector
+    }
+
+    fn init_config(&self) -> Option<TableConfig> {
+        let mut config = None;
+
+        if let Ok(cols) = self.query_columns() {
+            let pk = cols
+                .iter()
+                .find(|c| c.primary)
+                .or_else(|| cols.iter().find(|c| c.unique))
+                .map(|pk| pk.name.clone());
+
+            let c = TableConfig {
+                pk_column: pk,
+                name: self.name.clone(),
+                label: self.name.clone(),
+                ..TableConfig::default()
+            };
+
+            config = Some(c);
+        }
+
+        config
+    }
+}
+
+impl TableCRUD for SqliteTable {
+    fn query_data(
+        &self,
+        opts: TableQueryOpts,
+        db: &SharedDB,
+    ) -> DataQueryResult<ColumnValue, AppError> {
+        let cols = opts
+            .columns
+            .clone()
+            .take_if(|cols| !cols.is_empty())
+            .unwrap_or_else(|| match self.query_columns() {
+                Ok(cs) => cs.iter().map(|col| col.name.clone()).collect(),
+                Err(err) => {
+                    tracing::error!("error reading db column: {err}");
+                    vec![]
+                }
+            });
+
+        let query = opts.try_into()?;
+        db.check_query(&query)?;
+        let (sql, params) = db.generate_sql(query)?;
+
+        let conn = self.connector();
+        let rows = conn.exec_query(&sql, &params)?;
+
+        let data = rows
+            .iter()
+            .map(|r| {
+                let mut map: HashMap<String, ColumnValue> = HashMap::new();
+
+                for col in &cols {
+                    if let Some(v) = r.get::<ColumnValue, &str>(col) {
+                        map.insert(col.clone(), v);
+                    }
+                }
+
+                map
+            })
+            .collect();
+
+        Ok(data)
+    }
+
+    fn query_result_count(&self, opts: TableQueryOpts, db: &SharedDB) -> Result<usize, AppError> {
+        let query = BasableQuery {
+            table: opts.table,
+            command: QueryCommand::SelectData(Some(vec!["COUNT(*)".to_string()])),
+            search_opts: opts.search_opts,
+            filters: opts
+                .filters
+                .map_or(FilterChain::empty(), FilterChain::prefill),
+            ..Default::default()
+        };
+
+        db.check_query(&query)?;
+        let (sql, params) = db.generate_sql(query)?;
+
+        let conn = self.connector();
+        let rows = conn.exec_query(&sql, &params)?;
+
+        let count = rows
+            .first()
+            .map(|row| row.get::<usize, usize>(0).unwrap_or_default())
+            .unwrap_or_default();
+
+        Ok(count)
+    }
+
+    fn insert_data(&self, input: HashMap<String, String>) -> Result<(), AppError> {
+        let cols: Vec<&str> = input.keys().map(|k| k.as_str()).collect();
+        self.check_columns(&cols)?;
+
+        let mut params = QueryParams::new();
+        let mut keys = Vec::with_capacity(input.len());
+        let mut values = Vec::with_capacity(input.len());
+
+        for (k, v) in input {
+            keys.push(quote_ident(&k));
+            values.push(params.bind(v));
+        }
+
+        let query = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            self.quoted_name(),
+            keys.join(", "),
+            values.join(", ")
+        );
+
+        let conn = self.connector();
+        conn.exec_query(&query, &params.into_values())?;
+
+        Ok(())
+    }
+
+    fn insert_batch(&self, columns: &[String], rows: Vec<Vec<ColumnValue>>) -> Result<usize, AppError> {
+        let cols: Vec<&str> = columns.iter().map(|c| c.as_str()).collect();
+        self.check_columns(&cols)?;
+
+        let count = rows.len();
+        let statements =
+            insert_statements(&self.quoted_name(), columns, rows, quote_ident, QueryParams::new)?;
+        self.connector().exec_transaction(&statements)?;
+
+        Ok(count)
+    }
+
+    fn write_rows(
+        &self,
+        columns: &[String],
+        rows: Vec<Vec<ColumnValue>>,
+        opts: WriteOpts,
+    ) -> Result<Vec<RowOutcome>, AppError> {
+        write_sql_rows(self, &SYNTAX, columns, rows, opts)
+    }
+
+    fn apply_edits(
+        &self,
+        unique_key: &str,
+        edits: Vec<TableEdit>,
+    ) -> Result<Vec<EditOutcome>, EditBatchError> {
+        apply_sql_edits(self, &SYNTAX, unique_key, edits)
+    }
+
+    fn update_data(&self, options: UpdateTableData) -> Result<(), AppError> {
+        let UpdateTableData {
+            unique_key,
+            columns,
+            unique_values,
+            input,
+        } = options;
+
+        let mut cols: Vec<&str> = columns.iter().map(|col| col.as_str()).collect();
+        cols.push(&unique_key);
+        self.check_columns(&cols)?;
+
+        let unique_key = quote_ident(&unique_key);
+        let mut params = QueryParams::new();
+
+        let mut cases = vec![];
+        for col in &columns {
+            let quoted_col = quote_ident(col);
+            let mut q = format!("{quoted_col} = CASE {unique_key} \n");
+
+            for (index, uv) in unique_values.iter().enumerate() {
+                if let Some(values) = input.get(index) {
+                    if let Some(val) = values.get(col) {
+                        let when = params.bind(uv.as_str());
+                        let then = params.bind(val.as_str());
+                        q.push_str(&format!("WHEN {when} THEN {then} \n"));
+                    }
+                }
+            }
+
+            q.push_str(&format!("ELSE {quoted_col} \n END"));
+            cases.push(q);
+        }
+
+        let unique_values = params.bind_list(unique_values.iter().map(|v| v.as_str()));
+
+        let query = format!(
+            "UPDATE {} \n SET {} WHERE {} IN ({})",
+            self.quoted_name(),
+            cases.join(", \n"),
+            unique_key,
+            unique_values
+        );
+
+        let conn = self.connector();
+        conn.exec_query(&query, &params.into_values())?;
+
+        Ok(())
+    }
+
+    fn delete_data(&self, col: &str, values: Vec<&str>) -> Result<(), AppError> {
+        if values.is_empty() {
+            return Err(AppError::ServerError("a value must be provided".to_string()));
+        }
+
+        self.check_columns(&[col])?;
+
+        let mut params = QueryParams::new();
+        let query = format!(
+            "DELETE FROM {} WHERE {} IN ({})",
+            self.quoted_name(),
+            quote_ident(col),
+            params.bind_list(values)
+        );
+
+        let conn = self.connector();
+        conn.exec_query(&query, &params.into_values())?;
+
+        Ok(())
+    }
+
+    fn export(&self, opts: TableExportOpts, db: &SharedDB, out: &mut (dyn Write + Send)) -> Result<(), AppError> {
+        let TableExportOpts {
+            query_opts,
+            format,
+            trim,
+            xml,
+            ..
+        } = opts;
+
+        let cols = query_opts
+            .columns
+            .clone()
+            .take_if(|cols| !cols.is_empty())
+            .unwrap_or_else(|| match self.query_columns() {
+                Ok(cs) => cs.iter().map(|col| col.name.clone()).collect(),
+                Err(err) => {
+                    tracing::error!("error reading db column: {err}");
+                    vec![]
+                }
+            });
+
+        let selection = if cols.is_empty() {
+            None
+        } else {
+            Some(cols.clone())
+        };
+
+        let filters = query_opts
+            .filters
+            .map_or(FilterChain::empty(), FilterChain::prefill);
+
+        let query = BasableQuery {
+            table: query_opts.table,
+            command: QueryCommand::SelectData(selection),
+            filters,
+            offset: trim.as_ref().map(|trim| trim.offset),
+            row_count: trim.map(|trim| trim.count),
+            ..Default::default()
+        };
+
+        db.check_query(&query)?;
+        let (sql, params) = db.generate_sql(query)?;
+
+        write_export(self.connector(), format, xml, cols, &sql, &params, out)
+    }
+
+    fn clear(&self) -> Result<(), AppError> {
+        let query = format!("DELETE FROM {}", self.quoted_name());
+        let conn = self.connector();
+        conn.exec_query(&query, &[])?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use common::data::table::TableEdit;
+    use r2d2::Pool;
+    use r2d2_sqlite::SqliteConnectionManager;
+
+    use crate::{
+        config::ConfigRaw,
+        row::ColumnValue,
+        sqlite::connector::SqliteConnector,
+        table::{EditStatus, RowAction, RowOutcome, Table, TableCRUD, WriteOpts},
+        ConnectorType,
+    };
+
+    use super::SqliteTable;
+
+    fn users_table() -> SqliteTable {
+        // A single connection, since each in-memory connection has its own database.
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(SqliteConnectionManager::memory())
+            .unwrap();
+        let conn: ConnectorType = Arc::new(SqliteConnector {
+            pool,
+            config: ConfigRaw::default(),
+        });
+
+        conn.exec_query(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT UNIQUE, name TEXT)",
+            &[],
+        )
+        .unwrap();
+
+        SqliteTable::new("users".to_string(), conn)
+    }
+
+    #[test]
+    fn test_write_rows() {
+        let table = users_table();
+        let columns = vec!["email".to_string(), "name".to_string()];
+        let row = |email: &str, name: &str| vec![ColumnValue::from(email), ColumnValue::from(name)];
+        let outcome = |action, id| RowOutcome {
+            action,
+            key: Some(ColumnValue::Int(id)),
+        };
+        let opts = |upsert: bool| WriteOpts {
+            key_column: Some("id".to_string()),
+            upsert_on: upsert.then(|| vec!["email".to_string()]),
+        };
+
+        let written = table
+            .write_rows(&columns, vec![row("a@x", "A"), row("b@x", "B")], opts(false))
+            .unwrap();
+        assert_eq!(written, vec![outcome(RowAction::Inserted, 1), outcome(RowAction::Inserted, 2)]);
+
+        let written = table
+            .write_rows(&columns, vec![row("a@x", "A2"), row("c@x", "C")], opts(true))
+            .unwrap();
+        assert_eq!(written, vec![outcome(RowAction::Updated, 1), outcome(RowAction::Inserted, 3)]);
+
+        // Only the upsert column is written, so there's nothing to update.
+        let written = table
+            .write_rows(&columns[..1], vec![vec![ColumnValue::from("b@x")]], opts(true))
+            .unwrap();
+        assert_eq!(written, vec![outcome(RowAction::Unchanged, 2)]);
+
+        // The duplicate email fails the second row, and the first one is rolled back.
+        let err = table
+            .write_rows(&columns, vec![row("d@x", "D"), row("b@x", "B2")], opts(false))
+            .unwrap_err();
+        assert!(err.to_string().starts_with("Row 1:"));
+
+        let count = table
+            .connector()
+            .exec_query("SELECT COUNT(*) FROM users", &[])
+            .unwrap();
+        assert_eq!(count[0].get::<i64, usize>(0), Some(3));
+    }
+
+    #[test]
+    fn test_apply_edits() {
+        let table = users_table();
+        let values = |email: &str| HashMap::from([("email".to_string(), Some(email.to_string()))]);
+        let insert = |email: &str| TableEdit::Insert { values: values(email) };
+        let statuses = |edits: Vec<TableEdit>| match table.apply_edits("id", edits) {
+            Ok(outcomes) => outcomes.into_iter().map(|o| (o.status, o.affected)).collect::<Vec<_>>(),
+            Err(err) => err.outcomes.into_iter().map(|o| (o.status, o.affected)).collect(),
+        };
+
+        let applied = statuses(vec![
+            insert("a@x"),
+            insert("b@x"),
+            TableEdit::Update {
+                key: "1".to_string(),
+                values: values("a2@x"),
+            },
+            TableEdit::Delete { key: "9".to_string() },
+        ]);
+        assert_eq!(
+            applied,
+            vec![
+                (EditStatus::Applied, Some(1)),
+                (EditStatus::Applied, Some(1)),
+                (EditStatus::Applied, Some(1)),
+                (EditStatus::NotFound, Some(0)),
+            ]
+        );
+
+        // The duplicate email fails the update, and the insert before it is rolled back.
+        let failed = statuses(vec![
+            insert("c@x"),
+            TableEdit::Update {
+                key: "2".to_string(),
+                values: values("a2@x"),
+            },
+            TableEdit::Delete { key: "1".to_string() },
+        ]);
+        assert_eq!(
+            failed,
+            vec![
+                (EditStatus::RolledBack, None),
+                (EditStatus::Failed, None),
+                (EditStatus::Skipped, None),
+            ]
+        );
+
+        let count = table
+            .connector()
+            .exec_query("SELECT COUNT(*) FROM users", &[])
+            .unwrap();
+        assert_eq!(count[0].get::<i64, usize>(0), Some(2));
+    }
+}
@@ -1,13 +1,115 @@
+use axum::http::StatusCode;
 use common::{
-    data::{columns::ColumnList, table::{DataQueryResult, TableConfig, TableExportOpts, TableQueryOpts, UpdateTableData}},
+    data::{columns::ColumnList, table::{DataQueryResult, TableConfig, TableEdit, TableExportOpts, TableQueryOpts, UpdateTableData}},
     error::AppError,
 };
-use std::collections::HashMap;
+use serde::Serialize;
+use std::{cmp::Ordering, collections::HashMap, io::Write};
 
-use crate::mysql::ColumnValue;
+use crate::row::ColumnValue;
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+use crate::{
+    connector::{Statement, TransactionError},
+    db::QueryParams,
+};
 
 use super::{ConnectorType, SharedDB};
 
+/// Most values bound in a single statement. SQLite accepts up to 32766 and MySQL and
+/// PostgreSQL 65535.
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+const MAX_STATEMENT_PARAMS: usize = 32_000;
+
+/// What [`TableCRUD::write_rows`] did with a row.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum RowAction {
+    Inserted,
+    /// An existing row matched the row on the upsert columns, and was updated.
+    Updated,
+    /// An existing row matched the row on the upsert columns, and already had its values.
+    Unchanged,
+}
+
+/// Outcome of a row written by [`TableCRUD::write_rows`].
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct RowOutcome {
+    pub action: RowAction,
+
+    /// Value of the key column for the row, whether it was given or generated by the
+    /// database. `None` when there's no key column or the value is unknown.
+    pub key: Option<ColumnValue>,
+}
+
+/// What became of an edit once [`TableCRUD::apply_edits`] returns.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum EditStatus {
+    Applied,
+    /// The edit is an update or delete whose key matched no row, so it changed nothing.
+    NotFound,
+    /// The edit ran, but was undone since a later one failed.
+    RolledBack,
+    Failed,
+    /// The edit didn't run since an earlier one failed.
+    Skipped,
+}
+
+/// Outcome of an edit of [`TableCRUD::apply_edits`].
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct EditOutcome {
+    pub status: EditStatus,
+
+    /// Number of rows inserted, updated or deleted, once applied.
+    pub affected: Option<usize>,
+    pub error: Option<String>,
+}
+
+impl EditOutcome {
+    /// Outcomes of `count` edits when the one at `failed` fails with `error`, after the ones
+    /// before it ran.
+    pub fn failure(count: usize, failed: usize, error: &AppError) -> Vec<EditOutcome> {
+        (0..count)
+            .map(|index| EditOutcome {
+                status: match index.cmp(&failed) {
+                    Ordering::Less => EditStatus::RolledBack,
+                    Ordering::Equal => EditStatus::Failed,
+                    Ordering::Greater => EditStatus::Skipped,
+                },
+                affected: None,
+                error: (index == failed).then(|| error.to_string()),
+            })
+            .collect()
+    }
+}
+
+/// Why [`TableCRUD::apply_edits`] failed. Nothing was changed.
+#[derive(Debug)]
+pub struct EditBatchError {
+    pub error: AppError,
+
+    /// Outcome of each edit. Empty when the batch failed before any edit was looked at.
+    pub outcomes: Vec<EditOutcome>,
+}
+
+impl From<AppError> for EditBatchError {
+    fn from(error: AppError) -> Self {
+        EditBatchError {
+            error,
+            outcomes: Vec::new(),
+        }
+    }
+}
+
+/// How [`TableCRUD::write_rows`] writes rows.
+#[derive(Default)]
+pub struct WriteOpts {
+    /// Column whose value is reported for each row, usually the primary key.
+    pub key_column: Option<String>,
+
+    /// Columns identifying a row. When given, rows matching an existing row on all of them
+    /// update it instead of being inserted. They must be among the written columns.
+    pub upsert_on: Option<Vec<String>>,
+}
+
 pub trait Table: TableCRUD + Sync + Send {
     type Row;
 
@@ -17,7 +119,7 @@ pub trait Table: TableCRUD + Sync + Send {
     /// then the config is also returned. It is up to the caller to save or send the config for the table.
     ///
     /// # Example:
-    /// ```
+    /// ```ignore
     /// let (table, config) = Table::new("table_name".to_string(), conn);
     /// // config is Option<TableConfig>
     /// ```
@@ -41,12 +143,50 @@ pub trait Table: TableCRUD + Sync + Send {
     /// Create table's initial [`TableConfig`] if possible. Caller is responsible for
     /// saving the configuration in persistent DB.
     fn init_config(&self) -> Option<TableConfig>;
+
+    /// Check that each of `columns` is a column of the table, so it's safe to use as an identifier.
+    fn check_columns(&self, columns: &[&str]) -> Result<(), AppError> {
+        if columns.is_empty() {
+            return Ok(());
+        }
+
+        let known = self.query_columns()?;
+        match columns.iter().find(|col| !known.iter().any(|k| k.name == **col)) {
+            Some(col) => Err(AppError::HttpError(
+                StatusCode::BAD_REQUEST,
+                format!("Unknown column `{col}` in table `{}`", self.name()),
+            )),
+            None => Ok(()),
+        }
+    }
 }
 
 pub trait TableCRUD {
     /// Inserts a new data into the table.
     fn insert_data(&self, input: HashMap<String, String>) -> Result<(), AppError>;
 
+    /// Inserts `rows`, each holding the values of `columns` in order, in a single transaction.
+    /// Returns the number of inserted rows.
+    fn insert_batch(&self, columns: &[String], rows: Vec<Vec<ColumnValue>>) -> Result<usize, AppError>;
+
+    /// Writes `rows`, each holding the values of `columns` in order, in a single transaction.
+    /// Unlike [`TableCRUD::insert_batch`], rows are written one by one to report the outcome
+    /// of each, in order. Nothing is written if a row fails, and the error names the row.
+    fn write_rows(
+        &self,
+        columns: &[String],
+        rows: Vec<Vec<ColumnValue>>,
+        opts: WriteOpts,
+    ) -> Result<Vec<RowOutcome>, AppError>;
+
+    /// Applies `edits` in order in a single transaction, finding updated and deleted rows by
+    /// `unique_key`. If an edit fails, every edit is rolled back.
+    fn apply_edits(
+        &self,
+        unique_key: &str,
+        edits: Vec<TableEdit>,
+    ) -> Result<Vec<EditOutcome>, EditBatchError>;
+
     /// Retrieve data from table based on query `filter`.
     fn query_data(
         &self,
@@ -61,7 +201,504 @@ pub trait TableCRUD {
 
     fn delete_data(&self, col: &str, values: Vec<&str>) -> Result<(), AppError>;
 
-    fn export(&self, opts: TableExportOpts, db: &SharedDB) -> Result<String, AppError>;
+    /// Writes the rows selected by `opts` to `out`, as they're read from the database.
+    fn export(&self, opts: TableExportOpts, db: &SharedDB, out: &mut (dyn Write + Send)) -> Result<(), AppError>;
 
     fn clear(&self) -> Result<(), AppError>;
-}
\ No newline at end of file
+}
+
+/// Multi-row `INSERT` statements adding `rows` to `table`. Rows are split across statements
+/// so that none binds more than [`MAX_STATEMENT_PARAMS`] values. `table` is already quoted,
+/// `quote` quotes the column names and `new_params` gives the placeholder style.
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+pub(crate) fn insert_statements(
+    table: &str,
+    columns: &[String],
+    rows: Vec<Vec<ColumnValue>>,
+    quote: fn(&str) -> String,
+    new_params: fn() -> QueryParams,
+) -> Result<Vec<Statement>, AppError> {
+    check_rows(columns, &rows)?;
+
+    let names: Vec<String> = columns.iter().map(|col| quote(col)).collect();
+    let names = names.join(", ");
+    let rows_per_statement = (MAX_STATEMENT_PARAMS / columns.len()).max(1);
+
+    let mut statements = Vec::new();
+    let mut rows = rows.into_iter().peekable();
+    while rows.peek().is_some() {
+        let mut params = new_params();
+        let values: Vec<String> = rows
+            .by_ref()
+            .take(rows_per_statement)
+            .map(|row| format!("({})", params.bind_list(row)))
+            .collect();
+
+        let query = format!("INSERT INTO {table} ({names}) VALUES {}", values.join(", "));
+        statements.push((query, params.into_values()));
+    }
+
+    Ok(statements)
+}
+
+/// Checks there are columns to write, and that each row has a value for each of them.
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite", feature = "mongo"))]
+pub(crate) fn check_rows(columns: &[String], rows: &[Vec<ColumnValue>]) -> Result<(), AppError> {
+    if columns.is_empty() {
+        return Err(AppError::HttpError(
+            StatusCode::BAD_REQUEST,
+            "No columns to insert".to_string(),
+        ));
+    }
+
+    if let Some(index) = rows.iter().position(|row| row.len() != columns.len()) {
+        return Err(AppError::HttpError(
+            StatusCode::BAD_REQUEST,
+            format!("Row {index} has {} values for {} columns", rows[index].len(), columns.len()),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Prefix `error` with the row, or other item, it's about: `what` and its position.
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite", feature = "mongo"))]
+pub(crate) fn numbered_error(what: &str, index: usize, error: AppError) -> AppError {
+    match error {
+        AppError::HttpError(code, msg) => AppError::HttpError(code, format!("{what} {index}: {msg}")),
+        err => AppError::ServerError(format!("{what} {index}: {err}")),
+    }
+}
+
+/// SQL syntax of a backend, for [`write_sql_rows`].
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+pub(crate) struct SqlSyntax {
+    pub quote: fn(&str) -> String,
+    pub new_params: fn() -> QueryParams,
+
+    /// MySQL has neither `ON CONFLICT` nor `RETURNING`: upserts use `ON DUPLICATE KEY UPDATE`
+    /// and generated keys are read with `LAST_INSERT_ID()`.
+    pub mysql: bool,
+
+    /// MySQL upserts refer to the inserted values through a row alias (`AS new`), since
+    /// `VALUES()` is deprecated there. MariaDB only has `VALUES()`.
+    pub row_alias: bool,
+}
+
+/// Statements written for a row by [`write_sql_rows`], as positions in the transaction.
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+struct RowStatements {
+    /// Looks up the row matching the upsert columns.
+    existing: Option<usize>,
+    insert: usize,
+
+    /// Gives the number of rows the upsert changed on MySQL: `SELECT ROW_COUNT()`.
+    row_count: Option<usize>,
+    last_insert_id: Option<usize>,
+}
+
+/// [`TableCRUD::write_rows`] for SQL tables. Each row is written with its own statement, after
+/// a lookup of the row it matches when upserting, so its outcome and key can be told apart.
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+pub(crate) fn write_sql_rows<T: Table + ?Sized>(
+    table: &T,
+    syntax: &SqlSyntax,
+    columns: &[String],
+    rows: Vec<Vec<ColumnValue>>,
+    opts: WriteOpts,
+) -> Result<Vec<RowOutcome>, AppError> {
+    check_rows(columns, &rows)?;
+
+    let upsert_on = opts.upsert_on.unwrap_or_default();
+    let mut known: Vec<&str> = columns.iter().map(|c| c.as_str()).collect();
+    known.extend(opts.key_column.as_deref());
+    table.check_columns(&known)?;
+
+    let upsert_at: Vec<usize> = upsert_on
+        .iter()
+        .map(|col| {
+            columns.iter().position(|c| c == col).ok_or_else(|| {
+                AppError::HttpError(
+                    StatusCode::BAD_REQUEST,
+                    format!("Upsert column `{col}` must be one of the written columns"),
+                )
+            })
+        })
+        .collect::<Result<_, _>>()?;
+
+    // `ON DUPLICATE KEY UPDATE` fires on a duplicate of any unique key, whatever the upsert
+    // columns, so they must be one.
+    #[cfg(any(feature = "mysql", feature = "mariadb"))]
+    if syntax.mysql && !upsert_on.is_empty() {
+        let matches_key = |key: &Vec<String>| {
+            key.len() == upsert_on.len() && key.iter().all(|col| upsert_on.contains(col))
+        };
+
+        if !mysql_unique_keys(table)?.iter().any(matches_key) {
+            return Err(AppError::HttpError(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Upsert columns ({}) must be the primary key or a unique key of `{}`",
+                    upsert_on.join(", "),
+                    table.name()
+                ),
+            ));
+        }
+    }
+
+    let quote = syntax.quote;
+    let name = quote(table.name());
+    let names: Vec<String> = columns.iter().map(|c| quote(c)).collect();
+    let key = opts.key_column.as_deref().map(quote);
+    let key_at = opts
+        .key_column
+        .as_ref()
+        .and_then(|key| columns.iter().position(|c| c == key));
+
+    let upsert_clause = match (upsert_on.is_empty(), syntax.mysql) {
+        (true, _) => String::new(),
+        (false, true) => {
+            let inserted = |name: &str| match syntax.row_alias {
+                true => format!("new.{name}"),
+                false => format!("VALUES({name})"),
+            };
+            let mut updates: Vec<String> = names
+                .iter()
+                .zip(columns)
+                .filter(|(_, col)| !upsert_on.contains(col))
+                .map(|(name, _)| format!("{name} = {}", inserted(name)))
+                .collect();
+            if updates.is_empty() {
+                let name = quote(&upsert_on[0]);
+                updates.push(format!("{name} = {name}"));
+            }
+
+            let alias = match syntax.row_alias {
+                true => " AS new",
+                false => "",
+            };
+            format!("{alias} ON DUPLICATE KEY UPDATE {}", updates.join(", "))
+        }
+        (false, false) => {
+            let targets: Vec<String> = upsert_on.iter().map(|c| quote(c)).collect();
+            let updates: Vec<String> = names
+                .iter()
+                .zip(columns)
+                .filter(|(_, col)| !upsert_on.contains(col))
+                .map(|(name, _)| format!("{name} = excluded.{name}"))
+                .collect();
+            let action = match updates.is_empty() {
+                true => "NOTHING".to_string(),
+                false => format!("UPDATE SET {}", updates.join(", ")),
+            };
+
+            format!(" ON CONFLICT ({}) DO {action}", targets.join(", "))
+        }
+    };
+    let returning = match (&key, syntax.mysql) {
+        (Some(key), false) => format!(" RETURNING {key}"),
+        _ => String::new(),
+    };
+
+    let mut statements: Vec<Statement> = Vec::new();
+    let mut plans = Vec::with_capacity(rows.len());
+    for row in &rows {
+        let existing = (!upsert_at.is_empty()).then(|| {
+            let mut params = (syntax.new_params)();
+            let conditions: Vec<String> = upsert_at
+                .iter()
+                .map(|at| format!("{} = {}", names[*at], params.bind(row[*at].clone())))
+                .collect();
+            let query = format!(
+                "SELECT {} FROM {name} WHERE {}",
+                key.as_deref().unwrap_or("1"),
+                conditions.join(" AND ")
+            );
+
+            statements.push((query, params.into_values()));
+            statements.len() - 1
+        });
+
+        let mut params = (syntax.new_params)();
+        let query = format!(
+            "INSERT INTO {name} ({}) VALUES ({}){upsert_clause}{returning}",
+            names.join(", "),
+            params.bind_list(row.iter().cloned())
+        );
+        statements.push((query, params.into_values()));
+        let insert = statements.len() - 1;
+
+        // Read before `LAST_INSERT_ID()`, which resets it.
+        let row_count = (syntax.mysql && existing.is_some()).then(|| {
+            statements.push(("SELECT ROW_COUNT()".to_string(), Vec::new()));
+            statements.len() - 1
+        });
+
+        let last_insert_id = (syntax.mysql && key.is_some() && key_at.is_none()).then(|| {
+            statements.push(("SELECT LAST_INSERT_ID()".to_string(), Vec::new()));
+            statements.len() - 1
+        });
+
+        plans.push(RowStatements {
+            existing,
+            insert,
+            row_count,
+            last_insert_id,
+        });
+    }
+
+    let results = table
+        .connector()
+        .exec_transaction(&statements)
+        .map_err(|TransactionError { index, error }| {
+            let row = plans.iter().rposition(|plan| {
+                plan.existing.unwrap_or(plan.insert) <= index
+            });
+            numbered_error("Row", row.unwrap_or_default(), error)
+        })?;
+
+    let first_value = |at: Option<usize>| {
+        at.and_then(|at| results[at].first())
+            .and_then(|row| row.get::<ColumnValue, usize>(0))
+    };
+
+    let outcomes = rows
+        .into_iter()
+        .zip(plans)
+        .map(|(row, plan)| {
+            let existing = plan.existing.filter(|at| !results[*at].is_empty());
+            // MySQL counts 0 rows for an update that leaves the row as it was. Elsewhere, rows
+            // are only left as they were when all the written columns are upsert columns.
+            let unchanged = match plan.row_count {
+                Some(at) => results[at].first().and_then(|row| row.get::<usize, usize>(0)) == Some(0),
+                None => upsert_at.len() == columns.len(),
+            };
+            let action = match (existing, unchanged) {
+                (Some(_), true) => RowAction::Unchanged,
+                (Some(_), false) => RowAction::Updated,
+                (None, _) => RowAction::Inserted,
+            };
+
+            let key = match key_at {
+                Some(at) => row.into_iter().nth(at),
+                None if key.is_none() => None,
+                None if existing.is_some() => first_value(existing),
+                None if syntax.mysql => {
+                    // No id was generated if the table has no `AUTO_INCREMENT` column.
+                    first_value(plan.last_insert_id).filter(|id| *id != ColumnValue::UInt(0) && *id != ColumnValue::Int(0))
+                }
+                None => first_value(Some(plan.insert)),
+            };
+
+            RowOutcome { action, key }
+        })
+        .collect();
+
+    Ok(outcomes)
+}
+
+/// Columns of each primary and unique key of a MySQL table.
+#[cfg(any(feature = "mysql", feature = "mariadb"))]
+fn mysql_unique_keys<T: Table + ?Sized>(table: &T) -> Result<Vec<Vec<String>>, AppError> {
+    let query = "
+        SELECT index_name AS INDEX_NAME, column_name AS COLUMN_NAME
+        FROM information_schema.statistics
+        WHERE table_schema = DATABASE() AND table_name = ? AND non_unique = 0
+        ORDER BY index_name, seq_in_index
+    ";
+
+    let rows = table.connector().exec_query(query, &[table.name().into()])?;
+    let mut keys: Vec<(String, Vec<String>)> = Vec::new();
+    for row in &rows {
+        let (Some(index), Some(column)) = (
+            row.get::<String, &str>("INDEX_NAME"),
+            row.get::<String, &str>("COLUMN_NAME"),
+        ) else {
+            continue;
+        };
+
+        match keys.last_mut() {
+            Some((name, columns)) if *name == index => columns.push(column),
+            _ => keys.push((index, vec![column])),
+        }
+    }
+
+    Ok(keys.into_iter().map(|(_, columns)| columns).collect())
+}
+
+/// Statements run for an edit by [`apply_sql_edits`], as positions in the transaction.
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+struct EditStatements {
+    /// The first statement of the edit.
+    first: usize,
+
+    /// Gives the number of matched rows: the edit itself with `RETURNING 1`, or on MySQL a
+    /// `SELECT COUNT(*)` run before an update, since `ROW_COUNT()` leaves out the rows it
+    /// didn't change, and `SELECT ROW_COUNT()` after inserts and deletes.
+    count: usize,
+}
+
+/// [`TableCRUD::apply_edits`] for SQL tables.
+#[cfg(any(feature = "mysql", feature = "postgres", feature = "sqlite"))]
+pub(crate) fn apply_sql_edits<T: Table + ?Sized>(
+    table: &T,
+    syntax: &SqlSyntax,
+    unique_key: &str,
+    edits: Vec<TableEdit>,
+) -> Result<Vec<EditOutcome>, EditBatchError> {
+    let known = table.query_columns()?;
+    let quote = syntax.quote;
+    let name = quote(table.name());
+    let key = quote(unique_key);
+    let returning = match syntax.mysql {
+        true => "",
+        false => " RETURNING 1",
+    };
+
+    // Checked up front, so the error names the edit it's about.
+    let check = |index: usize, edit: &TableEdit| {
+        let (values, uses_key) = match edit {
+            TableEdit::Insert { values } => (Some(values), false),
+            TableEdit::Update { values, .. } => (Some(values), true),
+            TableEdit::Delete { .. } => (None, true),
+        };
+
+        let mut columns: Vec<&str> = values.into_iter().flat_map(|v| v.keys()).map(|k| k.as_str()).collect();
+        if uses_key {
+            columns.push(unique_key);
+        }
+
+        let error = if values.is_some_and(|v| v.is_empty()) {
+            Some("No values to write".to_string())
+        } else {
+            columns
+                .iter()
+                .find(|col| !known.iter().any(|k| k.name == **col))
+                .map(|col| format!("Unknown column `{col}` in table `{}`", table.name()))
+        };
+
+        match error {
+            Some(msg) => {
+                let error = AppError::HttpError(StatusCode::BAD_REQUEST, msg);
+                let mut outcomes = EditOutcome::failure(edits.len(), index, &error);
+                // Nothing ran yet.
+                outcomes[..index].iter_mut().for_each(|o| o.status = EditStatus::Skipped);
+
+                Err(EditBatchError {
+                    error: numbered_error("Edit", index, error),
+                    outcomes,
+                })
+            }
+            None => Ok(()),
+        }
+    };
+
+    let mut statements: Vec<Statement> = Vec::new();
+    let mut plans = Vec::with_capacity(edits.len());
+    for (index, edit) in edits.iter().enumerate() {
+        check(index, edit)?;
+
+        let mut params = (syntax.new_params)();
+        let sorted = |values: &HashMap<String, Option<String>>| {
+            let mut values: Vec<(String, ColumnValue)> = values
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone().map_or(ColumnValue::NULL, ColumnValue::Text)))
+                .collect();
+            values.sort_by(|a, b| a.0.cmp(&b.0));
+            values
+        };
+
+        let query = match edit {
+            TableEdit::Insert { values } => {
+                let (columns, values): (Vec<String>, Vec<ColumnValue>) = sorted(values).into_iter().unzip();
+                let columns: Vec<String> = columns.iter().map(|c| quote(c)).collect();
+
+                format!(
+                    "INSERT INTO {name} ({}) VALUES ({}){returning}",
+                    columns.join(", "),
+                    params.bind_list(values)
+                )
+            }
+            TableEdit::Update { key: value, values } => {
+                let updates: Vec<String> = sorted(values)
+                    .into_iter()
+                    .map(|(col, value)| format!("{} = {}", quote(&col), params.bind(value)))
+                    .collect();
+
+                format!(
+                    "UPDATE {name} SET {} WHERE {key} = {}{returning}",
+                    updates.join(", "),
+                    params.bind(value.as_str())
+                )
+            }
+            TableEdit::Delete { key: value } => {
+                format!("DELETE FROM {name} WHERE {key} = {}{returning}", params.bind(value.as_str()))
+            }
+        };
+
+        let statement = (query, params.into_values());
+        let first = statements.len();
+        let count = match (edit, syntax.mysql) {
+            (TableEdit::Update { key: value, .. }, true) => {
+                let mut params = (syntax.new_params)();
+                let query = format!("SELECT COUNT(*) FROM {name} WHERE {key} = {}", params.bind(value.as_str()));
+                statements.push((query, params.into_values()));
+                statements.push(statement);
+                first
+            }
+            (_, true) => {
+                statements.push(statement);
+                statements.push(("SELECT ROW_COUNT()".to_string(), Vec::new()));
+                first + 1
+            }
+            (_, false) => {
+                statements.push(statement);
+                first
+            }
+        };
+
+        plans.push(EditStatements { first, count });
+    }
+
+    let results = table
+        .connector()
+        .exec_transaction(&statements)
+        .map_err(|TransactionError { index, error }| {
+            let failed = plans.iter().rposition(|plan| plan.first <= index).unwrap_or_default();
+
+            EditBatchError {
+                outcomes: EditOutcome::failure(edits.len(), failed, &error),
+                error: numbered_error("Edit", failed, error),
+            }
+        })?;
+
+    let outcomes = plans
+        .iter()
+        .zip(&edits)
+        .map(|(plan, edit)| {
+            let affected = match syntax.mysql {
+                true => results[plan.count]
+                    .first()
+                    .and_then(|row| row.get::<usize, usize>(0)),
+                false => Some(results[plan.count].len()),
+            };
+
+            match edit {
+                TableEdit::Update { key: value, .. } | TableEdit::Delete { key: value } if affected == Some(0) => {
+                    EditOutcome {
+                        status: EditStatus::NotFound,
+                        affected,
+                        error: Some(format!("No row has `{unique_key}` = `{value}`")),
+                    }
+                }
+                _ => EditOutcome {
+                    status: EditStatus::Applied,
+                    affected,
+                    error: None,
+                },
+            }
+        })
+        .collect();
+
+    Ok(outcomes)
+}
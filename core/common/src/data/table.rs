@@ -1,3 +1,4 @@
+use axum::http::StatusCode;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -20,8 +21,19 @@ pub struct HistoryColumn {
     pattern: String,
 }
 
+impl HistoryColumn {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// `strftime`-like format of the column values, used when they're stored as strings.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+}
+
 /// The type of `SpecialColumn`
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub enum SpecialValueType {
     Image,
     Audio,
@@ -38,23 +50,39 @@ pub struct SpecialColumn {
     path: String,
 }
 
+impl SpecialColumn {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn special_type(&self) -> &SpecialValueType {
+        &self.special_type
+    }
+
+    /// Where the column values point to: a local directory, or a URL. URLs (and directories)
+    /// may hold a `{value}` placeholder for the column value, which is appended otherwise.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
 /// The action that should trigger `NotifyEvent`.
-#[derive(Deserialize, Serialize, Clone)]
-enum NotifyTrigger {
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum NotifyTrigger {
     Create,
     Update,
     Delete,
 }
 
 /// When should `NotifyEvent` get triggered around `NotifyTrigger`.
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub enum NotifyTriggerTime {
     Before,
     After,
 }
 
 /// The REST API method expected by the webhook URL.
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub enum NotifyEventMethod {
     Get,
     Post,
@@ -65,7 +93,7 @@ pub enum NotifyEventMethod {
 
 /// What should happen to the operation `NotifyTrigger` when there's notification error?
 /// Let's say there's a server error from the webhook URL, should we proceed or fail the operation?
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, PartialEq)]
 pub enum OnNotifyError {
     Fail,
     Proceed,
@@ -81,6 +109,28 @@ pub struct NotifyEvent {
     on_error: OnNotifyError,
 }
 
+impl NotifyEvent {
+    pub fn trigger(&self) -> &NotifyTrigger {
+        &self.trigger
+    }
+
+    pub fn trigger_time(&self) -> &NotifyTriggerTime {
+        &self.trigger_time
+    }
+
+    pub fn method(&self) -> &NotifyEventMethod {
+        &self.method
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn on_error(&self) -> &OnNotifyError {
+        &self.on_error
+    }
+}
+
 #[derive(Deserialize, Serialize, Clone)]
 pub struct TableConfig {
     pub label: String,
@@ -109,6 +159,84 @@ pub struct TableConfig {
     pub exclude_columns: Option<Vec<String>>,
 }
 
+impl TableConfig {
+    /// Every table column this config refers to.
+    pub fn columns(&self) -> Vec<&str> {
+        let mut columns: Vec<&str> = Vec::new();
+
+        columns.extend(self.pk_column.as_deref());
+        columns.extend(self.created_column.iter().map(|c| c.name()));
+        columns.extend(self.updated_column.iter().map(|c| c.name()));
+        columns.extend(self.special_columns.iter().flatten().map(|c| c.name()));
+        columns.extend(self.exclude_columns.iter().flatten().map(|c| c.as_str()));
+
+        columns
+    }
+
+    fn is_excluded(&self, column: &str) -> bool {
+        self.exclude_columns
+            .as_ref()
+            .is_some_and(|excluded| excluded.iter().any(|c| c == column))
+    }
+
+    /// Remove the excluded columns from a row fetched without [`TableConfig::apply`].
+    pub fn strip_excluded<V>(&self, row: &mut HashMap<String, V>) {
+        row.retain(|column, _| !self.is_excluded(column));
+    }
+
+    /// Apply this config to a data query: excluded columns are left out of the selection, and
+    /// rows are ordered by `pk_column` unless `opts` asks for another order. Rows in another
+    /// order are then ordered by `pk_column`, so ties keep their order from a page to the next.
+    /// Queries that name an excluded column, to select, filter, search or order by it, are
+    /// rejected. `all_columns` lists the table columns, and is only called when `opts` selects
+    /// them all.
+    pub fn apply(
+        &self,
+        opts: &mut TableQueryOpts,
+        all_columns: impl FnOnce() -> Result<Vec<String>, AppError>,
+    ) -> Result<(), AppError> {
+        let mut named = opts
+            .columns
+            .iter()
+            .flatten()
+            .map(String::as_str)
+            .chain(opts.filters.iter().flatten().map(|f| f.column.as_str()))
+            .chain(opts.search_opts.iter().flat_map(|s| s.search_cols.iter().map(String::as_str)))
+            .chain(opts.order_by.iter().chain(&opts.then_by).map(QueryOrder::column));
+        if let Some(column) = named.find(|c| self.is_excluded(c)) {
+            return Err(AppError::HttpError(
+                StatusCode::BAD_REQUEST,
+                format!("Column `{column}` is excluded"),
+            ));
+        }
+
+        if let Some(pk) = &self.pk_column {
+            match &opts.order_by {
+                None => opts.order_by = Some(QueryOrder::ASC(pk.clone())),
+                Some(order) if opts.then_by.is_none() && order.column() != pk => {
+                    opts.then_by = Some(QueryOrder::ASC(pk.clone()));
+                }
+                Some(_) => {}
+            }
+        }
+
+        if self.exclude_columns.iter().flatten().next().is_none() || opts.columns.is_some() {
+            return Ok(());
+        }
+
+        let columns: Vec<String> = all_columns()?.into_iter().filter(|c| !self.is_excluded(c)).collect();
+        if columns.is_empty() {
+            return Err(AppError::HttpError(
+                StatusCode::BAD_REQUEST,
+                "All the table columns are excluded".to_string(),
+            ));
+        }
+        opts.columns = Some(columns);
+
+        Ok(())
+    }
+}
+
 impl PartialEq for TableConfig {
     fn eq(&self, other: &Self) -> bool {
         self.label == other.label
@@ -150,6 +278,11 @@ pub struct TableQueryOpts {
     pub columns: Option<Vec<String>>,
 
     pub order_by: Option<QueryOrder>,
+
+    /// Orders the rows `order_by` doesn't tell apart, like a primary key so pages don't
+    /// overlap.
+    #[serde(default)]
+    pub then_by: Option<QueryOrder>,
     pub search_opts: Option<TableSearchOpts>,
 }
 
@@ -170,6 +303,7 @@ impl TryFrom<TableQueryOpts> for BasableQuery {
             filters,
             columns,
             order_by,
+            then_by,
             search_opts,
         } = opts;
 
@@ -184,7 +318,7 @@ impl TryFrom<TableQueryOpts> for BasableQuery {
             row_count: Some(row_count),
             offset: Some(offset),
             filters: filter_chain,
-            order_by,
+            order_by: order_by.into_iter().chain(then_by).collect(),
             search_opts,
             ..Default::default()
         };
@@ -210,12 +344,55 @@ pub struct TableSummary {
 
 #[derive(Deserialize, Default)]
 pub struct UpdateTableData {
+    /// Defaults to the `pk_column` of the table config.
+    #[serde(default)]
     pub unique_key: String,
     pub columns: Vec<String>,
     pub unique_values: Vec<String>,
     pub input: Vec<HashMap<String, String>>,
 }
 
+/// An operation of a [`TableEditBatch`]. Updated and deleted rows are found by the value of
+/// the batch `unique_key`.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(tag = "op")]
+pub enum TableEdit {
+    Insert {
+        values: HashMap<String, Option<String>>,
+    },
+    Update {
+        key: String,
+        values: HashMap<String, Option<String>>,
+    },
+    Delete {
+        key: String,
+    },
+}
+
+/// Inserts, updates and deletes applied in a single transaction.
+#[derive(Deserialize, Default)]
+pub struct TableEditBatch {
+    /// Defaults to the `pk_column` of the table config.
+    #[serde(default)]
+    pub unique_key: String,
+    pub edits: Vec<TableEdit>,
+}
+
+/// Rows written in a single transaction by the batch endpoint.
+#[derive(Deserialize, Default)]
+pub struct BatchTableData {
+    /// Column name to value for each row. Every row must have the same columns.
+    pub rows: Vec<HashMap<String, Option<String>>>,
+
+    /// Update the rows matching an existing row instead of inserting them.
+    #[serde(default)]
+    pub upsert: bool,
+
+    /// Columns matching rows to existing ones. Defaults to the `pk_column` of the table
+    /// config, then to a unique column of the table, among the written columns.
+    pub upsert_on: Option<Vec<String>>,
+}
+
 #[derive(Deserialize, Clone)]
 pub enum TableExportFormat {
     CSV,
@@ -223,8 +400,12 @@ pub enum TableExportFormat {
     PSV,
     TEXT,
     JSON,
+    /// One JSON object per line.
+    NDJSON,
     HTML,
     XML,
+    /// An Excel workbook of one sheet.
+    XLSX,
 }
 
 impl TableExportFormat {
@@ -235,8 +416,10 @@ impl TableExportFormat {
             TableExportFormat::PSV => "psv",
             TableExportFormat::TEXT => "txt",
             TableExportFormat::JSON => "json",
+            TableExportFormat::NDJSON => "ndjson",
             TableExportFormat::HTML => "html",
             TableExportFormat::XML => "xml",
+            TableExportFormat::XLSX => "xlsx",
         };
 
         ext.to_string()
@@ -249,23 +432,23 @@ impl TableExportFormat {
             TableExportFormat::PSV => "text/plain", // No specific MIME type; plain text is the closest match
             TableExportFormat::TEXT => "text/plain",
             TableExportFormat::JSON => "application/json",
+            TableExportFormat::NDJSON => "application/x-ndjson",
             TableExportFormat::HTML => "text/html",
             TableExportFormat::XML => "application/xml", // Or "text/xml" based on context
+            TableExportFormat::XLSX => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
         };
         
         mime_type.to_string()
     }
 
-    pub fn field_delimiter(&self) -> Option<String> {
-        let dlm = match self {
-            TableExportFormat::CSV => Some(","),
-            TableExportFormat::TSV => Some("\\t"),
-            TableExportFormat::PSV => Some("|"),
-            TableExportFormat::TEXT => Some(";"),
+    pub fn field_delimiter(&self) -> Option<char> {
+        match self {
+            TableExportFormat::CSV => Some(','),
+            TableExportFormat::TSV => Some('\t'),
+            TableExportFormat::PSV => Some('|'),
+            TableExportFormat::TEXT => Some(';'),
             _ => None
-        };
-
-        dlm.map(|dlm_str| dlm_str.to_string())
+        }
     }
 }
 
@@ -279,12 +462,107 @@ pub struct TableExportTrim {
 pub struct TableExportOpts {
     pub format: TableExportFormat,
     pub query_opts: TableQueryOpts,
-    pub trim: Option<TableExportTrim>
+    pub trim: Option<TableExportTrim>,
+    /// Compress the download with gzip.
+    #[serde(default)]
+    pub gzip: bool,
+    #[serde(default)]
+    pub xml: XmlExportOpts
 }
 
-#[derive(Serialize)]
-pub struct  TableExportResponse {
-    pub data: String,
-    pub mimetype: String,
-    pub filename: String
+/// Element names of XML exports. Names that aren't valid XML names are sanitised, like column
+/// names.
+#[derive(Deserialize, Clone, Default)]
+pub struct XmlExportOpts {
+    /// Defaults to `rows`.
+    pub root: Option<String>,
+    /// Defaults to `row`.
+    pub row: Option<String>
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use axum::http::StatusCode;
+
+    use crate::{
+        error::AppError,
+        query::{
+            filter::{Filter, FilterCombinator, FilterExpression},
+            QueryOrder,
+        },
+    };
+
+    use super::{TableConfig, TableQueryOpts, TableSearchOpts};
+
+    #[test]
+    fn test_apply_config() {
+        let config = TableConfig {
+            pk_column: Some("id".to_string()),
+            exclude_columns: Some(vec!["password".to_string()]),
+            ..Default::default()
+        };
+
+        let opts = || TableQueryOpts {
+            table: "users".to_string(),
+            offset: 0,
+            row_count: 10,
+            filters: None,
+            columns: None,
+            order_by: None,
+            then_by: None,
+            search_opts: Some(TableSearchOpts {
+                search_cols: vec!["name".to_string()],
+                query: "ada".to_string(),
+            }),
+        };
+        let all = || Ok(vec!["id".to_string(), "name".to_string(), "password".to_string()]);
+
+        let mut query = opts();
+        config.apply(&mut query, all).unwrap();
+
+        assert_eq!(query.columns, Some(vec!["id".to_string(), "name".to_string()]));
+        assert_eq!(query.search_opts.unwrap().search_cols, vec!["name".to_string()]);
+        assert!(matches!(query.order_by, Some(QueryOrder::ASC(col)) if col == "id"));
+        assert!(query.then_by.is_none());
+
+        // Rows ordered by another column are then ordered by the pk, to page through them.
+        let mut query = opts();
+        query.order_by = Some(QueryOrder::DESC("name".to_string()));
+        config.apply(&mut query, all).unwrap();
+        assert!(matches!(query.then_by, Some(QueryOrder::ASC(col)) if col == "id"));
+
+        let mut query = opts();
+        query.order_by = Some(QueryOrder::DESC("name".to_string()));
+        query.then_by = Some(QueryOrder::DESC("id".to_string()));
+        config.apply(&mut query, all).unwrap();
+        assert!(matches!(query.then_by, Some(QueryOrder::DESC(col)) if col == "id"));
+
+        // Queries naming the excluded column are rejected, whatever they use it for.
+        let rejected = |query: &mut TableQueryOpts| {
+            matches!(
+                config.apply(query, all),
+                Err(AppError::HttpError(StatusCode::BAD_REQUEST, _))
+            )
+        };
+
+        let mut query = opts();
+        query.columns = Some(vec!["id".to_string(), "password".to_string()]);
+        assert!(rejected(&mut query));
+
+        let mut query = opts();
+        query.filters = Some(vec![Filter {
+            combinator: FilterCombinator::BASE,
+            column: "password".to_string(),
+            expression: FilterExpression::Contains("a".to_string()),
+        }]);
+        assert!(rejected(&mut query));
+
+        let mut query = opts();
+        query.search_opts.as_mut().unwrap().search_cols.push("password".to_string());
+        assert!(rejected(&mut query));
+
+        let mut query = opts();
+        query.order_by = Some(QueryOrder::DESC("password".to_string()));
+        assert!(rejected(&mut query));
+    }
 }
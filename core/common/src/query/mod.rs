@@ -1,5 +1,3 @@
-use std::fmt::Display;
-
 use filter::FilterChain;
 use serde::Deserialize;
 
@@ -23,17 +21,21 @@ pub enum QueryOrder {
     DESC(String),
 }
 
-impl Display for QueryOrder {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let order = match self {
-            QueryOrder::ASC(col) => format!("`{col}` ASC"),
-            QueryOrder::DESC(col) => format!("`{col}` DESC"),
-        };
-
-        write!(f, "{order}")
+impl QueryOrder {
+    pub fn column(&self) -> &str {
+        match self {
+            QueryOrder::ASC(col) | QueryOrder::DESC(col) => col,
+        }
     }
 }
 
+/// `LEFT JOIN table ON <query table>.column = table.foreign_column`
+pub struct QueryJoin {
+    pub table: String,
+    pub column: String,
+    pub foreign_column: String,
+}
+
 #[derive(Default)]
 pub struct BasableQuery {
     pub table: String,
@@ -41,9 +43,10 @@ pub struct BasableQuery {
     pub filters: FilterChain,
     pub row_count: Option<usize>,
     pub offset: Option<usize>,
-    pub order_by: Option<QueryOrder>,
+    /// Sort keys, in order.
+    pub order_by: Vec<QueryOrder>,
     pub group_by: Option<Vec<String>>,
-    pub left_join: Option<String>,
+    pub left_join: Option<QueryJoin>,
     pub having: FilterChain,
     pub search_opts: Option<TableSearchOpts>
 }